@@ -0,0 +1,75 @@
+/// Non-value-based reference policies to compare against the policies found by value iteration.
+// src/baselines.rs
+use crate::config::ESTADO_META;
+use crate::entorno::Entorno;
+use crate::mdp_model::{distancias_a_peligro_con_entorno, vecinos_inversos};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+/// Builds the policy that reaches the goal along the path of maximum clearance from danger.
+///
+/// Same as `politica_mas_segura_con_entorno` with `Entorno::default()`.
+///
+/// # Returns
+///
+/// The policy (State -> Action) of every state that can reach the goal, the goal excluded.
+pub fn politica_mas_segura() -> HashMap<String, String> {
+    politica_mas_segura_con_entorno(&Entorno::default())
+}
+
+/// Builds the policy that reaches the goal along the path of maximum clearance from danger, under the
+/// movement rules of `ref_entorno`.
+///
+/// The clearance of a cell is its BFS distance to the nearest danger state
+/// (`mdp_model::distancias_a_peligro_con_entorno`), and a path's clearance is the smallest clearance of its
+/// cells. Each state follows the path to the goal with the largest clearance, breaking ties by the fewest
+/// moves, found with a Dijkstra search backwards from the goal on (clearance, length). Walls, one-way
+/// passages and teleporters are honored by searching over the reversed moves (`mdp_model::vecinos_inversos`).
+/// Without danger states this is a shortest path. Moves are taken as deterministic, so the policy ignores
+/// the transition noise that value iteration accounts for.
+///
+/// # Arguments
+///
+/// * `ref_entorno` - The movement rules to honor.
+///
+/// # Returns
+///
+/// The policy (State -> Action) of every state that can reach the goal, the goal excluded.
+pub fn politica_mas_segura_con_entorno(ref_entorno: &Entorno) -> HashMap<String, String> {
+    let hm_s_uiHolguras = distancias_a_peligro_con_entorno(ref_entorno);
+    let holgura = |sEstado: &str| hm_s_uiHolguras.get(sEstado).copied().unwrap_or(usize::MAX);
+    let hm_s_vec_tpl_Inversos = vecinos_inversos(ref_entorno);
+
+    // Best (clearance, length) found for each state and the action that starts that path.
+    let mut hm_s_tpl_ui_uiMejor: HashMap<&'static str, (usize, usize)> =
+        HashMap::from([(ESTADO_META, (holgura(ESTADO_META), 0))]);
+    let mut hm_s_sAccion: HashMap<&'static str, &'static str> = HashMap::new();
+    let mut bh_tpl_Pendientes = BinaryHeap::from([(holgura(ESTADO_META), Reverse(0_usize), ESTADO_META)]);
+
+    while let Some((uiHolgura, Reverse(uiLargo), sEstado)) = bh_tpl_Pendientes.pop() {
+        if hm_s_tpl_ui_uiMejor[sEstado] != (uiHolgura, uiLargo) {
+            continue; // Stale entry
+        }
+        // Each predecessor reaches `sEstado` in one move with its action.
+        for &(sPredecesor, sAccion) in hm_s_vec_tpl_Inversos.get(sEstado).into_iter().flatten() {
+            let tpl_ui_uiCandidato = (uiHolgura.min(holgura(sPredecesor)), uiLargo + 1);
+            let bMejor = match hm_s_tpl_ui_uiMejor.get(sPredecesor) {
+                None => true,
+                Some(&(uiHolguraActual, uiLargoActual)) => {
+                    tpl_ui_uiCandidato.0 > uiHolguraActual
+                        || (tpl_ui_uiCandidato.0 == uiHolguraActual && tpl_ui_uiCandidato.1 < uiLargoActual)
+                }
+            };
+            if bMejor {
+                hm_s_tpl_ui_uiMejor.insert(sPredecesor, tpl_ui_uiCandidato);
+                hm_s_sAccion.insert(sPredecesor, sAccion);
+                bh_tpl_Pendientes.push((tpl_ui_uiCandidato.0, Reverse(tpl_ui_uiCandidato.1), sPredecesor));
+            }
+        }
+    }
+
+    hm_s_sAccion
+        .into_iter()
+        .map(|(sEstado, sAccion)| (sEstado.to_string(), sAccion.to_string()))
+        .collect()
+}
@@ -1,6 +1,9 @@
 // src/config.rs
 /// Configuration for the MDP model, including map layout, states, rewards, and actions.
+use crate::texto::{tipo_celda, TipoCelda};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Number of rows in the map.
 pub const FILAS_MAPA: usize = 6;
@@ -15,6 +18,13 @@ pub const ESTADOS_PELIGRO: [&str; 4] = ["P1", "P2", "P3", "P4"];
 /// Array of states considered as obstacles.
 pub const OBSTACULOS: [&str; 10] = ["O1", "O2", "O3", "O4", "O5", "O6", "O7", "O8", "O9", "O10"];
 
+/// Reward of the goal state in `obtener_recompensas`.
+pub const RECOMPENSA_META: f64 = 10.0;
+/// Reward of the danger states in `obtener_recompensas`.
+pub const RECOMPENSA_PELIGRO: f64 = -0.5;
+/// Reward of every other non-obstacle state in `obtener_recompensas` (the step cost).
+pub const RECOMPENSA_PASO: f64 = -0.1;
+
 /// 2D array defining the layout of the map and the name of each state.
 pub const MAPA_ESTADOS: [[&str; 8]; 6] = [
     ["S0", "S1", "P1", "O1", "S3", "O2", "S4", "S5"],
@@ -25,6 +35,83 @@ pub const MAPA_ESTADOS: [[&str; 8]; 6] = [
     ["S32", "O9", "S33", "S34", "O10", "S35", "S36", "S37"],
 ];
 
+/// Returns `MAPA_ESTADOS` as owned rows, the layout read by `cargar_mapa_csv` and checked by `validar_mapa`.
+pub fn mapa_estados_como_filas() -> Vec<Vec<String>> {
+    MAPA_ESTADOS
+        .iter()
+        .map(|arr_sFilaEstados| arr_sFilaEstados.iter().map(|sEstado| sEstado.to_string()).collect())
+        .collect()
+}
+
+/// Checks that a map layout is consistent with the declared special states.
+///
+/// Every row must have the same number of cells, every cell must have a non-empty name used by no
+/// other cell (state lookups return the first match), the goal `ESTADO_META` must appear exactly once,
+/// and every state of `ESTADOS_PELIGRO` and `OBSTACULOS` must be on the map.
+///
+/// # Arguments
+///
+/// * `ref_arr_vec_sMapa` - The state names, one `Vec` per map row: `mapa_estados_como_filas()` for the
+///   built-in map, or a layout read by `cargar_mapa_csv`.
+///
+/// # Returns
+///
+/// `Ok(())`, or `Err` with one message per problem, naming the offending cells by (row, column).
+pub fn validar_mapa(ref_arr_vec_sMapa: &[Vec<String>]) -> Result<(), Vec<String>> {
+    let mut vec_sErrores: Vec<String> = Vec::new();
+    let mut hm_s_tpl_ui_uiPrimera: HashMap<&str, (usize, usize)> = HashMap::new();
+
+    let uiColumnas = ref_arr_vec_sMapa.first().map_or(0, Vec::len);
+    if uiColumnas == 0 {
+        vec_sErrores.push("El mapa no tiene celdas".to_string());
+    }
+    for (uiFila, vec_sFilaEstados) in ref_arr_vec_sMapa.iter().enumerate() {
+        if vec_sFilaEstados.len() != uiColumnas {
+            vec_sErrores.push(format!(
+                "La fila {} tiene {} celdas y la primera {}",
+                uiFila,
+                vec_sFilaEstados.len(),
+                uiColumnas
+            ));
+        }
+        for (uiCol, sEstado) in vec_sFilaEstados.iter().enumerate() {
+            let sEstado = sEstado.as_str();
+            if sEstado.trim().is_empty() {
+                vec_sErrores.push(format!("Celda ({}, {}) sin nombre", uiFila, uiCol));
+                continue;
+            }
+            if let Some((uiFilaPrimera, uiColPrimera)) = hm_s_tpl_ui_uiPrimera.get(sEstado) {
+                vec_sErrores.push(format!(
+                    "Estado '{}' repetido en ({}, {}) y ({}, {})",
+                    sEstado, uiFilaPrimera, uiColPrimera, uiFila, uiCol
+                ));
+            } else {
+                hm_s_tpl_ui_uiPrimera.insert(sEstado, (uiFila, uiCol));
+            }
+        }
+    }
+
+    if !hm_s_tpl_ui_uiPrimera.contains_key(ESTADO_META) {
+        vec_sErrores.push(format!("La meta '{}' no está en el mapa", ESTADO_META));
+    }
+    for sPeligro in ESTADOS_PELIGRO.iter() {
+        if !hm_s_tpl_ui_uiPrimera.contains_key(sPeligro) {
+            vec_sErrores.push(format!("El peligro '{}' no está en el mapa", sPeligro));
+        }
+    }
+    for sObstaculo in OBSTACULOS.iter() {
+        if !hm_s_tpl_ui_uiPrimera.contains_key(sObstaculo) {
+            vec_sErrores.push(format!("El obstáculo '{}' no está en el mapa", sObstaculo));
+        }
+    }
+
+    if vec_sErrores.is_empty() {
+        Ok(())
+    } else {
+        Err(vec_sErrores)
+    }
+}
+
 /// Generates and returns a map of rewards for each state.
 ///
 /// Rewards are defined as:
@@ -32,6 +119,9 @@ pub const MAPA_ESTADOS: [[&str; 8]; 6] = [
 /// - Danger states (`ESTADOS_PELIGRO`): -0.5
 /// - All other non-obstacle states: -0.1 (step cost)
 ///
+/// Per-state overrides (e.g. from `cargar_recompensas_csv`) are carried in
+/// `Entorno::hm_s_f64RecompensasPersonalizadas` and applied by `entorno::recompensas_con_entorno`.
+///
 /// # Returns
 ///
 /// A `HashMap` where keys are state names (`&'static str`) and values are their rewards (`f64`).
@@ -40,11 +130,11 @@ pub fn obtener_recompensas() -> HashMap<&'static str, f64> {
     for arr_sFilaEstados in MAPA_ESTADOS.iter() {
         for &sEstado in arr_sFilaEstados.iter() {
             let f64Recompensa = if sEstado == ESTADO_META {
-                10.0
+                RECOMPENSA_META
             } else if ESTADOS_PELIGRO.contains(&sEstado) {
-                -0.5
+                RECOMPENSA_PELIGRO
             } else {
-                -0.1
+                RECOMPENSA_PASO
             };
             hm_s_f64Recompensas.insert(sEstado, f64Recompensa);
         }
@@ -52,6 +142,177 @@ pub fn obtener_recompensas() -> HashMap<&'static str, f64> {
     hm_s_f64Recompensas
 }
 
+/// Reads per-state reward overrides from a CSV file with `estado,recompensa` rows.
+///
+/// A header row is expected; unlisted states keep their default reward. Rows for states that are not on
+/// the map are skipped with a warning. Set the result as `Entorno::hm_s_f64RecompensasPersonalizadas`.
+///
+/// # Arguments
+///
+/// * `sRuta` - Path of the CSV file.
+///
+/// # Returns
+///
+/// The rewards by state name, or an I/O/parse error.
+pub fn cargar_recompensas_csv(sRuta: &str) -> Result<HashMap<String, f64>, Box<dyn std::error::Error>> {
+    let mut rdrLector = csv::Reader::from_path(sRuta)?;
+    let mut hm_s_f64Recompensas = HashMap::new();
+    for resRegistro in rdrLector.records() {
+        let srRegistro = resRegistro?;
+        let sEstado = srRegistro.get(0).ok_or("fila sin estado")?.trim().to_string();
+        let f64Recompensa: f64 = srRegistro.get(1).ok_or("fila sin recompensa")?.trim().parse()?;
+        if !MAPA_ESTADOS.iter().flatten().any(|sEstadoMapa| *sEstadoMapa == sEstado) {
+            log::warn!("Recompensa para un estado que no está en el mapa: {}", sEstado);
+            continue;
+        }
+        hm_s_f64Recompensas.insert(sEstado, f64Recompensa);
+    }
+    Ok(hm_s_f64Recompensas)
+}
+
+/// Writes the map layout (`MAPA_ESTADOS`) to a CSV file, so a results directory records the world it came from.
+///
+/// The header is `c0,c1,…,leyenda`; each row holds the state names of one map row followed by a legend
+/// with one marker per cell: `M` goal, `P` danger, `#` obstacle, `.` any other state.
+/// `cargar_mapa_csv` reads the file back.
+///
+/// # Arguments
+///
+/// * `sRuta` - Path of the output file.
+///
+/// # Returns
+///
+/// `Result<(), Box<dyn std::error::Error>>` indicating success or an I/O error.
+pub fn guardar_mapa_csv(sRuta: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut wtrEscritor = csv::Writer::from_path(sRuta)?;
+    let mut vec_sEncabezado: Vec<String> = (0..COLUMNAS_MAPA).map(|uiCol| format!("c{}", uiCol)).collect();
+    vec_sEncabezado.push("leyenda".to_string());
+    wtrEscritor.write_record(&vec_sEncabezado)?;
+
+    for arr_sFilaEstados in MAPA_ESTADOS.iter() {
+        let sLeyenda: String = arr_sFilaEstados
+            .iter()
+            .map(|sEstado| {
+                if *sEstado == ESTADO_META {
+                    'M'
+                } else if ESTADOS_PELIGRO.contains(sEstado) {
+                    'P'
+                } else if OBSTACULOS.contains(sEstado) {
+                    '#'
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        let mut vec_sRegistro: Vec<&str> = arr_sFilaEstados.to_vec();
+        vec_sRegistro.push(&sLeyenda);
+        wtrEscritor.write_record(&vec_sRegistro)?;
+    }
+    wtrEscritor.flush()?;
+
+    log::info!("{} guardado.", sRuta);
+    Ok(())
+}
+
+/// Creates a fresh directory for the outputs of one run, so consecutive runs do not overwrite each other.
+///
+/// The directory is `<sDirBase>/ejecucion_<seconds since the Unix epoch>`, which sorts in run order; if it
+/// already exists (two runs in the same second), `_2`, `_3`, … is appended.
+///
+/// # Arguments
+///
+/// * `sDirBase` - Directory to create the run directory in; created if it does not exist.
+///
+/// # Returns
+///
+/// The path of the new directory, or an I/O error.
+pub fn crear_directorio_ejecucion(sDirBase: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let u64Segundos = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let sNombre = format!("ejecucion_{}", u64Segundos);
+    let mut pbDirectorio = Path::new(sDirBase).join(&sNombre);
+    let mut uiSufijo = 2;
+    while pbDirectorio.exists() {
+        pbDirectorio = Path::new(sDirBase).join(format!("{}_{}", sNombre, uiSufijo));
+        uiSufijo += 1;
+    }
+    std::fs::create_dir_all(&pbDirectorio)?;
+    log::info!("Directorio de la ejecución: {}", pbDirectorio.display());
+    Ok(pbDirectorio)
+}
+
+/// Reads a map layout written by `guardar_mapa_csv`.
+///
+/// Comparing the result with `MAPA_ESTADOS` tells whether saved results were produced on the current map.
+///
+/// # Arguments
+///
+/// * `sRuta` - Path of the CSV file.
+///
+/// # Returns
+///
+/// The state names, one `Vec` per map row (the legend column is dropped), or an error if the file
+/// cannot be read, its rows have different lengths or the layout fails `validar_mapa`.
+pub fn cargar_mapa_csv(sRuta: &str) -> Result<Vec<Vec<String>>, Box<dyn std::error::Error>> {
+    let mut rdrLector = csv::Reader::from_path(sRuta)?;
+    let mut vec_vec_sMapa: Vec<Vec<String>> = Vec::new();
+    for resRegistro in rdrLector.records() {
+        let srRegistro = resRegistro?;
+        let uiCeldas = srRegistro.len().saturating_sub(1);
+        vec_vec_sMapa.push(srRegistro.iter().take(uiCeldas).map(|sCelda| sCelda.to_string()).collect());
+    }
+    validar_mapa(&vec_vec_sMapa)
+        .map_err(|vec_sErrores| format!("Mapa inválido en '{}': {}", sRuta, vec_sErrores.join("; ")))?;
+    Ok(vec_vec_sMapa)
+}
+
+/// Reads the cell types of a map file written by `guardar_mapa_csv` from its legend column.
+///
+/// The legend markers are those of `guardar_mapa_csv` (`M` goal, `P` danger, `#` obstacle, `.` any other
+/// state). Only the cells whose marker differs from their `MAPA_ESTADOS` type are returned, ready for
+/// `Entorno::hm_s_eTiposCelda`. As in `entorno::alternar_tipo_celda`, `ESTADO_META` and the map's own
+/// `OBSTACULOS` cannot change type.
+///
+/// # Arguments
+///
+/// * `sRuta` - Path of the CSV file; its layout must be `MAPA_ESTADOS` (see `cargar_mapa_csv`).
+///
+/// # Returns
+///
+/// The edited cells and their types, or an error if the file cannot be read, its layout is not
+/// `MAPA_ESTADOS`, or a legend has the wrong length, an unknown marker or changes a fixed cell.
+pub fn cargar_tipos_celda_csv(sRuta: &str) -> Result<HashMap<String, TipoCelda>, Box<dyn std::error::Error>> {
+    if cargar_mapa_csv(sRuta)? != mapa_estados_como_filas() {
+        return Err(format!("El mapa de '{}' no es la rejilla de MAPA_ESTADOS", sRuta).into());
+    }
+    let mut rdrLector = csv::Reader::from_path(sRuta)?;
+    let mut hm_s_eTipos: HashMap<String, TipoCelda> = HashMap::new();
+    for (arr_sFilaEstados, resRegistro) in MAPA_ESTADOS.iter().zip(rdrLector.records()) {
+        let srRegistro = resRegistro?;
+        let sLeyenda = srRegistro.get(srRegistro.len().saturating_sub(1)).unwrap_or("");
+        if sLeyenda.chars().count() != arr_sFilaEstados.len() {
+            return Err(format!("Leyenda '{}' de longitud distinta a la fila en '{}'", sLeyenda, sRuta).into());
+        }
+        for (sEstado, cMarcador) in arr_sFilaEstados.iter().zip(sLeyenda.chars()) {
+            let eTipo = match cMarcador {
+                'M' => TipoCelda::Meta,
+                'P' => TipoCelda::Peligro,
+                '#' => TipoCelda::Obstaculo,
+                '.' => TipoCelda::Normal,
+                _ => return Err(format!("Marcador '{}' desconocido para {} en '{}'", cMarcador, sEstado, sRuta).into()),
+            };
+            let eTipoMapa = tipo_celda(sEstado);
+            if eTipo == eTipoMapa {
+                continue;
+            }
+            if matches!(eTipoMapa, TipoCelda::Meta | TipoCelda::Obstaculo) {
+                return Err(format!("{} no puede cambiar de tipo en '{}'", sEstado, sRuta).into());
+            }
+            hm_s_eTipos.insert(sEstado.to_string(), eTipo);
+        }
+    }
+    Ok(hm_s_eTipos)
+}
+
 /// Returns a vector of valid actions the robot can take.
 ///
 /// Actions are: "N" (North), "S" (South), "E" (East), "O" (West).
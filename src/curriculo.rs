@@ -0,0 +1,259 @@
+/// Curriculum learning over generated maps: a Q-learning agent trained on grids of increasing difficulty.
+///
+/// The levels are `escalado::MapaGenerado` grids of one side length, ordered by `MapaGenerado::dificultad`.
+/// Cells are addressed by index, so a Q-table learned on one level applies as is to the next: the goal is
+/// always the bottom-right corner, and what transfers is the drift towards it.
+// src/curriculo.rs
+use crate::config::{acciones, prob_transicion};
+use crate::escalado::{generar_mapa_cuadrado, CeldaGenerada, MapaGenerado};
+use ::rand::rngs::StdRng;
+use ::rand::seq::SliceRandom;
+use ::rand::{Rng, SeedableRng};
+
+/// Settings of `ejecutar_curriculo_con_opciones`.
+///
+/// `OpcionesCurriculo::default()` gives the settings of `ejecutar_curriculo`.
+#[derive(Debug, Clone)]
+pub struct OpcionesCurriculo {
+    /// Side length of every level's grid.
+    pub uiLado: usize,
+    /// Training episodes per level.
+    pub uiEpisodiosPorNivel: usize,
+    /// Step cap per episode.
+    pub uiMaxPasos: usize,
+    /// The discount factor (gamma).
+    pub f64Lambda: f64,
+    /// The learning rate.
+    pub f64Alpha: f64,
+    /// Exploration probability of the epsilon-greedy behavior policy.
+    pub f64Epsilon: f64,
+    /// `true` (the default): each level starts from the Q-table left by the previous one. `false`: every
+    /// level starts from zeros, the baseline the transfer is measured against.
+    pub bTransferirTablaQ: bool,
+}
+
+impl Default for OpcionesCurriculo {
+    fn default() -> Self {
+        OpcionesCurriculo {
+            uiLado: 8,
+            uiEpisodiosPorNivel: 500,
+            uiMaxPasos: 200,
+            f64Lambda: 0.95,
+            f64Alpha: 0.5,
+            f64Epsilon: 0.1,
+            bTransferirTablaQ: true,
+        }
+    }
+}
+
+/// Outcome of one level of the curriculum.
+#[derive(Debug, Clone)]
+pub struct NivelCurriculo {
+    /// Seed the level's grid was generated with (see `escalado::generar_mapa_cuadrado`).
+    pub u64SemillaMapa: u64,
+    /// `MapaGenerado::dificultad` of the grid.
+    pub f64Dificultad: f64,
+    /// Number of episodes after which the greedy policy first reached the goal, with no noise, from every
+    /// cell that can reach it. `None` if it never did within `uiEpisodiosPorNivel`.
+    pub opt_uiEpisodiosHastaResolver: Option<usize>,
+    /// Steps taken in each episode (equals `uiMaxPasos` if the goal was not reached).
+    pub vec_uiPasosPorEpisodio: Vec<usize>,
+    /// Undiscounted sum of rewards collected in each episode, R(goal) included when reached.
+    pub vec_f64RecompensaPorEpisodio: Vec<f64>,
+}
+
+/// Runs a curriculum of `uiNiveles` generated maps with the default `OpcionesCurriculo`.
+///
+/// Same as `ejecutar_curriculo_con_opciones` with `OpcionesCurriculo::default()`.
+pub fn ejecutar_curriculo(uiNiveles: usize, u64Semilla: u64) -> Vec<NivelCurriculo> {
+    ejecutar_curriculo_con_opciones(uiNiveles, u64Semilla, &OpcionesCurriculo::default())
+}
+
+/// Trains one Q-learning agent on generated maps of increasing difficulty, in sequence.
+///
+/// Maps are generated from consecutive seeds starting at `u64Semilla`, skipping those whose goal no other
+/// cell can reach, until there are `uiNiveles`; they are then sorted by `MapaGenerado::dificultad`, easiest
+/// first. On each level the agent runs one-step Q-learning under `config::prob_transicion`, with the
+/// conventions of `learning`: an action in s earns R(s), episodes start at a random cell that can reach the
+/// goal, and reaching the goal ends the episode with a terminal value of R(goal). With `bTransferirTablaQ`
+/// the Q-table carries over from one level to the next, so levels sharing structure with the earlier ones
+/// should be solved in fewer episodes than with `bTransferirTablaQ = false`.
+///
+/// The per-level curves can be written with `experimento::guardar_curriculo_csv`.
+///
+/// # Arguments
+///
+/// * `uiNiveles` - Number of levels.
+/// * `u64Semilla` - Seed of the first map and of the learner's random number generator.
+/// * `ref_opciones` - Grid size and learner settings.
+///
+/// # Returns
+///
+/// One `NivelCurriculo` per level, easiest first.
+///
+/// # Panics
+///
+/// If `ref_opciones.uiLado` is below 2 (a 1×1 grid has no cell besides the goal).
+pub fn ejecutar_curriculo_con_opciones(
+    uiNiveles: usize,
+    u64Semilla: u64,
+    ref_opciones: &OpcionesCurriculo,
+) -> Vec<NivelCurriculo> {
+    assert!(ref_opciones.uiLado >= 2, "ejecutar_curriculo: el lado debe ser al menos 2");
+    let mut vec_tpl_Mapas: Vec<(u64, MapaGenerado, f64)> = Vec::with_capacity(uiNiveles);
+    let mut u64SemillaMapa = u64Semilla;
+    while vec_tpl_Mapas.len() < uiNiveles {
+        let mapa = generar_mapa_cuadrado(ref_opciones.uiLado, u64SemillaMapa);
+        if mapa.distancias_a_meta().iter().flatten().count() > 1 {
+            let f64Dificultad = mapa.dificultad();
+            vec_tpl_Mapas.push((u64SemillaMapa, mapa, f64Dificultad));
+        }
+        u64SemillaMapa += 1;
+    }
+    vec_tpl_Mapas.sort_by(|tplA, tplB| tplA.2.total_cmp(&tplB.2));
+
+    let mut rng = StdRng::seed_from_u64(u64Semilla);
+    let uiCeldas = ref_opciones.uiLado * ref_opciones.uiLado;
+    let mut vec_arr_f64Q = vec![[0.0; 4]; uiCeldas];
+    let mut vec_Niveles = Vec::with_capacity(uiNiveles);
+    for (uiNivel, (u64SemillaMapa, mapa, f64Dificultad)) in vec_tpl_Mapas.into_iter().enumerate() {
+        if !ref_opciones.bTransferirTablaQ {
+            vec_arr_f64Q = vec![[0.0; 4]; uiCeldas];
+        }
+        let (opt_uiEpisodiosHastaResolver, vec_uiPasosPorEpisodio, vec_f64RecompensaPorEpisodio) =
+            entrenar_nivel(&mapa, &mut vec_arr_f64Q, ref_opciones, &mut rng);
+        match opt_uiEpisodiosHastaResolver {
+            Some(uiEpisodios) => log::info!(
+                "Currículo: nivel {} (dificultad {:.3}) resuelto en {} episodios",
+                uiNivel + 1,
+                f64Dificultad,
+                uiEpisodios
+            ),
+            None => log::info!(
+                "Currículo: nivel {} (dificultad {:.3}) sin resolver tras {} episodios",
+                uiNivel + 1,
+                f64Dificultad,
+                ref_opciones.uiEpisodiosPorNivel
+            ),
+        }
+        vec_Niveles.push(NivelCurriculo {
+            u64SemillaMapa,
+            f64Dificultad,
+            opt_uiEpisodiosHastaResolver,
+            vec_uiPasosPorEpisodio,
+            vec_f64RecompensaPorEpisodio,
+        });
+    }
+    vec_Niveles
+}
+
+/// Runs the Q-learning episodes of one level, updating `ref_mut_vec_arr_f64Q` in place.
+///
+/// # Returns
+///
+/// The episodes until solved (see `NivelCurriculo::opt_uiEpisodiosHastaResolver`), and the steps and
+/// undiscounted reward of each episode.
+fn entrenar_nivel(
+    ref_mapa: &MapaGenerado,
+    ref_mut_vec_arr_f64Q: &mut [[f64; 4]],
+    ref_opciones: &OpcionesCurriculo,
+    ref_mut_rng: &mut StdRng,
+) -> (Option<usize>, Vec<usize>, Vec<f64>) {
+    let hm_s_hm_s_f64Modelo = prob_transicion();
+    let vec_sAcciones = acciones();
+    let uiMeta = ref_mapa.indice_meta();
+    let f64RecompensaMeta = ref_mapa.recompensa(uiMeta);
+    let vec_uiIniciales: Vec<usize> = ref_mapa
+        .distancias_a_meta()
+        .iter()
+        .enumerate()
+        .filter(|(uiIndice, opt_uiDistancia)| opt_uiDistancia.is_some() && *uiIndice != uiMeta)
+        .map(|(uiIndice, _)| uiIndice)
+        .collect();
+
+    let mut opt_uiEpisodiosHastaResolver = None;
+    let mut vec_uiPasosPorEpisodio = Vec::with_capacity(ref_opciones.uiEpisodiosPorNivel);
+    let mut vec_f64RecompensaPorEpisodio = Vec::with_capacity(ref_opciones.uiEpisodiosPorNivel);
+    for uiEpisodio in 0..ref_opciones.uiEpisodiosPorNivel {
+        let mut uiEstado = *vec_uiIniciales.choose(ref_mut_rng).unwrap();
+        let mut f64RecompensaEpisodio = 0.0;
+        let mut uiPasos = 0;
+        while uiPasos < ref_opciones.uiMaxPasos {
+            let uiAccion = if ref_mut_rng.gen::<f64>() < ref_opciones.f64Epsilon {
+                ref_mut_rng.gen_range(0..vec_sAcciones.len())
+            } else {
+                accion_greedy(&ref_mut_vec_arr_f64Q[uiEstado])
+            };
+
+            // Sample the effective direction in action order, as `learning::paso_entorno`.
+            let hm_s_f64ProbAccion = &hm_s_hm_s_f64Modelo[vec_sAcciones[uiAccion]];
+            let f64Muestra: f64 = ref_mut_rng.gen();
+            let mut f64Acumulada = 0.0;
+            let mut sDireccion = vec_sAcciones[uiAccion];
+            for sCandidata in vec_sAcciones.iter() {
+                if let Some(f64Prob) = hm_s_f64ProbAccion.get(sCandidata) {
+                    f64Acumulada += f64Prob;
+                    sDireccion = sCandidata;
+                    if f64Muestra < f64Acumulada {
+                        break;
+                    }
+                }
+            }
+            let uiSiguiente = ref_mapa.destino(uiEstado, sDireccion);
+            let f64Recompensa = ref_mapa.recompensa(uiEstado);
+            let bTerminal = uiSiguiente == uiMeta;
+            let f64ValorSiguiente = if bTerminal {
+                f64RecompensaMeta
+            } else {
+                ref_mut_vec_arr_f64Q[uiSiguiente].iter().copied().fold(f64::NEG_INFINITY, f64::max)
+            };
+            let f64Q = &mut ref_mut_vec_arr_f64Q[uiEstado][uiAccion];
+            *f64Q += ref_opciones.f64Alpha * (f64Recompensa + ref_opciones.f64Lambda * f64ValorSiguiente - *f64Q);
+
+            f64RecompensaEpisodio += f64Recompensa;
+            uiPasos += 1;
+            uiEstado = uiSiguiente;
+            if bTerminal {
+                f64RecompensaEpisodio += f64RecompensaMeta;
+                break;
+            }
+        }
+        vec_uiPasosPorEpisodio.push(uiPasos);
+        vec_f64RecompensaPorEpisodio.push(f64RecompensaEpisodio);
+
+        if opt_uiEpisodiosHastaResolver.is_none()
+            && politica_resuelve(ref_mapa, ref_mut_vec_arr_f64Q, &vec_uiIniciales)
+        {
+            opt_uiEpisodiosHastaResolver = Some(uiEpisodio + 1);
+        }
+    }
+    (opt_uiEpisodiosHastaResolver, vec_uiPasosPorEpisodio, vec_f64RecompensaPorEpisodio)
+}
+
+/// Index, in `acciones()` order, of the best action of a cell; ties go to the first, as `learning::accion_greedy`.
+fn accion_greedy(ref_arr_f64Q: &[f64; 4]) -> usize {
+    let mut uiMejor = 0;
+    for (uiAccion, f64Q) in ref_arr_f64Q.iter().enumerate() {
+        if *f64Q > ref_arr_f64Q[uiMejor] {
+            uiMejor = uiAccion;
+        }
+    }
+    uiMejor
+}
+
+/// Checks whether following the greedy policy with no noise reaches the goal from every start cell.
+///
+/// A path longer than the number of states has revisited a cell, so it would loop forever.
+fn politica_resuelve(ref_mapa: &MapaGenerado, ref_vec_arr_f64Q: &[[f64; 4]], ref_vec_uiIniciales: &[usize]) -> bool {
+    let vec_sAcciones = acciones();
+    ref_vec_uiIniciales.iter().all(|&uiInicio| {
+        let mut uiEstado = uiInicio;
+        for _ in 0..ref_mapa.num_estados() {
+            if ref_mapa.vec_eCeldas[uiEstado] == CeldaGenerada::Meta {
+                return true;
+            }
+            uiEstado = ref_mapa.destino(uiEstado, vec_sAcciones[accion_greedy(&ref_vec_arr_f64Q[uiEstado])]);
+        }
+        ref_mapa.vec_eCeldas[uiEstado] == CeldaGenerada::Meta
+    })
+}
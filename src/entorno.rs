@@ -0,0 +1,476 @@
+/// Runtime movement rules layered on top of the static map, shared by the solver and the simulations.
+// src/entorno.rs
+use crate::config::{
+    acciones, obtener_recompensas, ESTADO_META, MAPA_ESTADOS, OBSTACULOS, RECOMPENSA_META, RECOMPENSA_PASO,
+    RECOMPENSA_PELIGRO,
+};
+use crate::mdp_model::{mover, obtener_estado, obtener_posicion};
+use crate::texto::{tipo_celda, TipoCelda};
+use ::rand::Rng;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+/// Extra movement rules for the map defined in `config`.
+///
+/// `Entorno::default()` adds no rules, so moves behave exactly as with `mover`/`obtener_estado`.
+/// The same `Entorno` must be given to the solver (`OpcionesValueIteration`) and to the
+/// simulation (`OpcionesSimulacion`) for both to model the same world.
+#[derive(Debug, Clone)]
+pub struct Entorno {
+    /// One-way passages: moving in direction `.1` ("N", "S", "E", "O") out of state `.0`
+    /// is blocked and leaves the agent in place, as if it had hit a wall.
+    pub hs_tpl_s_sMovimientosBloqueados: HashSet<(String, String)>,
+    /// Thin walls between two adjacent cells. A wall blocks moves in both directions,
+    /// so `(a, b)` and `(b, a)` are equivalent; only one of them needs to be listed.
+    pub hs_tpl_s_sParedes: HashSet<(String, String)>,
+    /// Teleporters: entering the source state (key) relocates the agent to the target state
+    /// (value) before its next decision. Targets are not chained, and targets that are not
+    /// valid map states are ignored.
+    pub hm_s_sTeletransportadores: HashMap<String, String>,
+    /// Per-cell danger semantics. `None` keeps the default: the solver only penalizes danger states
+    /// and the simulation resets on all of them. `Some(letales)` makes the listed danger states lethal
+    /// (terminal in the solver, reset in the simulation) and every other danger state costly
+    /// (its penalty is paid but the robot passes through, in both).
+    pub opt_hs_sPeligrosLetales: Option<HashSet<String>>,
+    /// Wind (or conveyor) cells: from a listed state, with probability `.1` the move goes in direction
+    /// `.0` ("N", "S", "E", "O") whatever the action, and otherwise follows the usual noisy action.
+    /// See `probabilidades_con_viento` and `direccion_con_viento`.
+    pub hm_s_tpl_s_f64Viento: HashMap<String, (String, f64)>,
+    /// Penalty paid, on top of R(s), by a move that leaves the agent in place because it hit the map edge,
+    /// an obstacle, a wall or a blocked passage (see `es_choque`). 0 (the default) makes bumps free.
+    pub f64CostoChoque: f64,
+    /// When the goal's reward is earned; see `ModoRecompensaMeta`.
+    pub eModoRecompensaMeta: ModoRecompensaMeta,
+    /// `true` (the default): the goal ends the episode. `false`: the goal is an ordinary cell that pays its
+    /// reward on every step spent in it, gets an action like any other state, and the robot keeps moving
+    /// (a continuing task). The solver then backs the goal up instead of fixing its value, and
+    /// `simulacion_1000_pasos_con_opciones` no longer resets the robot there.
+    pub bMetaAbsorbente: bool,
+    /// What the outcomes of an action other than its intended direction mean; see `ModoFallo`.
+    pub eModoFallo: ModoFallo,
+    /// Per-state reward overrides (e.g. from `config::cargar_recompensas_csv`) replacing the tier reward of
+    /// their states; see `recompensas_con_entorno`. Empty (the default) keeps `config::obtener_recompensas`.
+    pub hm_s_f64RecompensasPersonalizadas: HashMap<String, f64>,
+    /// Per-cell type overrides on top of the compile-time map (see `tipo_celda_con_entorno`), set by the cell
+    /// editor (`alternar_tipo_celda`). An `Obstaculo` blocks moves into it and cannot be a teleporter target;
+    /// a `Peligro` is a danger state for the rewards, `opt_hs_sPeligrosLetales` and the simulations' resets and
+    /// counters, and a `Normal` cell is not; entering a `Meta` cell reaches `ESTADO_META`. Cells not listed
+    /// keep their `texto::tipo_celda`.
+    pub hm_s_eTiposCelda: HashMap<String, TipoCelda>,
+}
+
+impl Default for Entorno {
+    fn default() -> Self {
+        Entorno {
+            hs_tpl_s_sMovimientosBloqueados: HashSet::new(),
+            hs_tpl_s_sParedes: HashSet::new(),
+            hm_s_sTeletransportadores: HashMap::new(),
+            opt_hs_sPeligrosLetales: None,
+            hm_s_tpl_s_f64Viento: HashMap::new(),
+            f64CostoChoque: 0.0,
+            eModoRecompensaMeta: ModoRecompensaMeta::default(),
+            bMetaAbsorbente: true,
+            eModoFallo: ModoFallo::default(),
+            hm_s_f64RecompensasPersonalizadas: HashMap::new(),
+            hm_s_eTiposCelda: HashMap::new(),
+        }
+    }
+}
+
+/// Timing of the goal's reward R(goal) in the discounted accounting.
+///
+/// Both modes count R(goal) once per arrival; they differ only in the discount applied to it.
+/// The solver and `simulation::verificar_consistencia_con_entorno` honor the same mode, so the
+/// discounted return of a rollout estimates V(start) in either.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ModoRecompensaMeta {
+    /// The goal is a terminal state worth V(goal) = R(goal): a path reaching it after T moves earns λ^T·R(goal).
+    #[default]
+    Terminal,
+    /// The move that enters the goal pays R(goal) with that move's discount, λ^(T−1)·R(goal),
+    /// and the goal itself is worth V(goal) = 0.
+    AlEntrar,
+}
+
+/// Meaning of a failed action: the probability mass a transition model puts outside the intended direction.
+///
+/// The solver and the noisy simulators (`learning::paso_entorno`) apply the same mode through
+/// `probabilidades_con_fallo`, so both model the same actuator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ModoFallo {
+    /// The robot slips into the other directions with the model's probabilities (0.1 to each side by default).
+    #[default]
+    DireccionAleatoria,
+    /// The robot does not move: the failure mass becomes `DIRECCION_SIN_MOVIMIENTO`, which keeps it in place
+    /// without paying `f64CostoChoque`.
+    SinMovimiento,
+}
+
+/// Pseudo-direction of a failed action under `ModoFallo::SinMovimiento`: the robot stays where it is.
+///
+/// `resolver_destino` leaves the robot in place for it, and it is never a bump (see `paga_choque`).
+pub const DIRECCION_SIN_MOVIMIENTO: &str = "-";
+
+/// Returns the reward of every state under `ref_entorno`.
+///
+/// The tiers of `config::obtener_recompensas`, with the tier of every cell in `hm_s_eTiposCelda` taken from its
+/// overridden type, then `hm_s_f64RecompensasPersonalizadas` replacing the reward of their states; overrides
+/// for names that are not on the map are ignored.
+///
+/// # Arguments
+///
+/// * `ref_entorno` - The rules holding the reward overrides.
+///
+/// # Returns
+///
+/// A `HashMap` from state name to reward, with the same keys as `obtener_recompensas`.
+pub fn recompensas_con_entorno(ref_entorno: &Entorno) -> HashMap<&'static str, f64> {
+    let mut hm_s_f64Recompensas = obtener_recompensas();
+    for (sEstado, eTipo) in &ref_entorno.hm_s_eTiposCelda {
+        let f64RecompensaTipo = match eTipo {
+            TipoCelda::Meta => RECOMPENSA_META,
+            TipoCelda::Peligro => RECOMPENSA_PELIGRO,
+            TipoCelda::Normal => RECOMPENSA_PASO,
+            TipoCelda::Obstaculo => continue,
+        };
+        if let Some(f64RecompensaRef) = hm_s_f64Recompensas.get_mut(sEstado.as_str()) {
+            *f64RecompensaRef = f64RecompensaTipo;
+        }
+    }
+    for (sEstado, f64Recompensa) in &ref_entorno.hm_s_f64RecompensasPersonalizadas {
+        if let Some(f64RecompensaRef) = hm_s_f64Recompensas.get_mut(sEstado.as_str()) {
+            *f64RecompensaRef = *f64Recompensa;
+        }
+    }
+    hm_s_f64Recompensas
+}
+
+/// Resolves the state reached when moving from a state in a given direction.
+///
+/// The agent stays in place if the move leaves the map, hits an obstacle (including the `Obstaculo` cells of
+/// `hm_s_eTiposCelda`), crosses a wall or is blocked by a one-way passage in `ref_entorno`. If it enters a
+/// `Meta` cell of `hm_s_eTiposCelda` it ends up at `ESTADO_META`, and if it enters a teleporter source it
+/// ends up at the teleporter's target instead, so the next reward earned is the target's.
+///
+/// # Arguments
+///
+/// * `ref_entorno` - The movement rules to honor.
+/// * `sEstado` - The origin state.
+/// * `sDireccion` - The direction actually moved ("N", "S", "E", "O"), i.e. after any slip.
+///
+/// # Returns
+///
+/// The destination state name, or `None` if `sEstado` is not on the map.
+pub fn resolver_destino(ref_entorno: &Entorno, sEstado: &str, sDireccion: &str) -> Option<&'static str> {
+    let (uiFila, uiCol) = obtener_posicion(sEstado)?;
+    let sOrigen = MAPA_ESTADOS[uiFila][uiCol];
+
+    if !ref_entorno.hs_tpl_s_sMovimientosBloqueados.is_empty()
+        && ref_entorno
+            .hs_tpl_s_sMovimientosBloqueados
+            .contains(&(sOrigen.to_string(), sDireccion.to_string()))
+    {
+        return Some(sOrigen);
+    }
+
+    let (iNuevaFila, iNuevaCol) = mover(uiFila, uiCol, sDireccion);
+    let sDestino = obtener_estado(iNuevaFila, iNuevaCol)
+        .filter(|sDestino| !es_tipo_editado(ref_entorno, sDestino, TipoCelda::Obstaculo))
+        .unwrap_or(sOrigen);
+
+    if sDestino != sOrigen && hay_pared(ref_entorno, sOrigen, sDestino) {
+        return Some(sOrigen);
+    }
+    if sDestino != sOrigen {
+        if es_tipo_editado(ref_entorno, sDestino, TipoCelda::Meta) {
+            return Some(ESTADO_META);
+        }
+        if let Some(sTeletransportado) = destino_teletransportador(ref_entorno, sDestino) {
+            return Some(sTeletransportado);
+        }
+    }
+    Some(sDestino)
+}
+
+/// Lists the states reachable from `sEstado` in one noise-free move, with the action that reaches each.
+///
+/// Moves are resolved with `resolver_destino`, so they respect the map edges, obstacles, walls, one-way
+/// passages and teleporters of `ref_entorno`; moves that leave the agent in place are left out.
+///
+/// # Arguments
+///
+/// * `ref_entorno` - The movement rules to honor.
+/// * `sEstado` - The origin state.
+///
+/// # Returns
+///
+/// `(neighbor, action)` pairs in `acciones()` order; empty if `sEstado` is not on the map or is boxed in.
+pub fn vecinos_con_entorno(ref_entorno: &Entorno, sEstado: &str) -> Vec<(&'static str, &'static str)> {
+    acciones()
+        .into_iter()
+        .filter_map(|sAccion| {
+            let sVecino = resolver_destino(ref_entorno, sEstado, sAccion)?;
+            (sVecino != sEstado).then_some((sVecino, sAccion))
+        })
+        .collect()
+}
+
+/// Checks whether `Entorno::hm_s_eTiposCelda` overrides the type of `sEstado` to `eTipo`.
+fn es_tipo_editado(ref_entorno: &Entorno, sEstado: &str, eTipo: TipoCelda) -> bool {
+    ref_entorno.hm_s_eTiposCelda.get(sEstado) == Some(&eTipo)
+}
+
+/// Returns the target of the teleporter whose source is `sEstado`, if there is one and the
+/// target is a valid (non-obstacle) map state.
+fn destino_teletransportador(ref_entorno: &Entorno, sEstado: &str) -> Option<&'static str> {
+    let sObjetivo = ref_entorno.hm_s_sTeletransportadores.get(sEstado)?;
+    let (uiFila, uiCol) = obtener_posicion(sObjetivo)?;
+    let sObjetivoMapa = MAPA_ESTADOS[uiFila][uiCol];
+    if OBSTACULOS.contains(&sObjetivoMapa) || es_tipo_editado(ref_entorno, sObjetivoMapa, TipoCelda::Obstaculo) {
+        None
+    } else {
+        Some(sObjetivoMapa)
+    }
+}
+
+/// Checks whether a move from `sEstado` that ended in `sEstadoDestino` was a bump that pays `f64CostoChoque`.
+///
+/// Every direction moves the agent unless something blocks it, so a move that leaves it in place is a
+/// collision with the map edge, an obstacle, a wall or a blocked passage.
+pub fn es_choque(sEstado: &str, sEstadoDestino: &str) -> bool {
+    sEstado == sEstadoDestino
+}
+
+/// Checks whether a move in `sDireccion` that ended in `sEstadoDestino` pays `f64CostoChoque`.
+///
+/// Same as `es_choque`, except that staying in place after a failed action (`DIRECCION_SIN_MOVIMIENTO`)
+/// is not a collision.
+pub fn paga_choque(sEstado: &str, sDireccion: &str, sEstadoDestino: &str) -> bool {
+    sDireccion != DIRECCION_SIN_MOVIMIENTO && es_choque(sEstado, sEstadoDestino)
+}
+
+/// Returns the fixed value of the goal state under `ref_entorno.eModoRecompensaMeta`.
+///
+/// With a non-absorbing goal (`bMetaAbsorbente = false`) this is instead the reward of a step spent in it.
+///
+/// # Arguments
+///
+/// * `ref_entorno` - The rules whose goal mode applies.
+/// * `f64RecompensaMeta` - R(goal).
+///
+/// # Returns
+///
+/// `f64RecompensaMeta` in `Terminal` mode, 0 in `AlEntrar` mode.
+pub fn valor_meta(ref_entorno: &Entorno, f64RecompensaMeta: f64) -> f64 {
+    match ref_entorno.eModoRecompensaMeta {
+        ModoRecompensaMeta::Terminal => f64RecompensaMeta,
+        ModoRecompensaMeta::AlEntrar => 0.0,
+    }
+}
+
+/// Returns the reward paid by a move for entering `sEstadoDestino`, on top of the origin's R(s).
+///
+/// # Arguments
+///
+/// * `ref_entorno` - The rules whose goal mode applies.
+/// * `sEstadoDestino` - The state the move ended in.
+/// * `f64RecompensaMeta` - R(goal).
+///
+/// # Returns
+///
+/// `f64RecompensaMeta` if the mode is `AlEntrar` and the move entered the goal, 0 otherwise.
+pub fn recompensa_entrada(ref_entorno: &Entorno, sEstadoDestino: &str, f64RecompensaMeta: f64) -> f64 {
+    if ref_entorno.eModoRecompensaMeta == ModoRecompensaMeta::AlEntrar && sEstadoDestino == ESTADO_META {
+        f64RecompensaMeta
+    } else {
+        0.0
+    }
+}
+
+/// Checks whether a state is a danger state under `ref_entorno`: one of `ESTADOS_PELIGRO` or a `Peligro` cell of
+/// `hm_s_eTiposCelda`, unless that map gives it another type.
+pub fn es_peligro_con_entorno(ref_entorno: &Entorno, sEstado: &str) -> bool {
+    tipo_celda_con_entorno(ref_entorno, sEstado) == TipoCelda::Peligro
+}
+
+/// Checks whether a state ends the value of a path in the solver, as the goal does.
+///
+/// Only danger states (`es_peligro_con_entorno`) listed in `opt_hs_sPeligrosLetales` are; with `None`, no
+/// danger state is.
+pub fn es_peligro_terminal(ref_entorno: &Entorno, sEstado: &str) -> bool {
+    match &ref_entorno.opt_hs_sPeligrosLetales {
+        None => false,
+        Some(hs_sLetales) => hs_sLetales.contains(sEstado) && es_peligro_con_entorno(ref_entorno, sEstado),
+    }
+}
+
+/// Checks whether entering a state resets the robot in the simulation.
+///
+/// With `opt_hs_sPeligrosLetales = None` every danger state (`es_peligro_con_entorno`) does; otherwise only
+/// the lethal ones, matching `es_peligro_terminal`.
+pub fn reinicia_en_peligro(ref_entorno: &Entorno, sEstado: &str) -> bool {
+    match &ref_entorno.opt_hs_sPeligrosLetales {
+        None => es_peligro_con_entorno(ref_entorno, sEstado),
+        Some(_) => es_peligro_terminal(ref_entorno, sEstado),
+    }
+}
+
+/// Checks whether a wall separates two cells, in either order.
+///
+/// # Arguments
+///
+/// * `ref_entorno` - The movement rules holding the walls.
+/// * `sEstadoA`, `sEstadoB` - The two cells.
+///
+/// # Returns
+///
+/// `true` if `(sEstadoA, sEstadoB)` or `(sEstadoB, sEstadoA)` is in `hs_tpl_s_sParedes`.
+pub fn hay_pared(ref_entorno: &Entorno, sEstadoA: &str, sEstadoB: &str) -> bool {
+    if ref_entorno.hs_tpl_s_sParedes.is_empty() {
+        return false;
+    }
+    let (sA, sB) = (sEstadoA.to_string(), sEstadoB.to_string());
+    ref_entorno.hs_tpl_s_sParedes.contains(&(sA.clone(), sB.clone()))
+        || ref_entorno.hs_tpl_s_sParedes.contains(&(sB, sA))
+}
+
+/// Applies `ref_entorno.eModoFallo` to the direction distribution of an action, before any wind.
+///
+/// With `DireccionAleatoria` the distribution is returned unchanged (borrowed, without copying). With
+/// `SinMovimiento` the intended direction keeps its probability and the rest goes to
+/// `DIRECCION_SIN_MOVIMIENTO`.
+///
+/// # Arguments
+///
+/// * `ref_entorno` - The movement rules holding the failure mode.
+/// * `sAccion` - The action taken, i.e. the intended direction.
+/// * `ref_hm_s_f64ProbAccion` - The action's distribution over effective directions, from the model.
+///
+/// # Returns
+///
+/// The distribution over effective directions (possibly including `DIRECCION_SIN_MOVIMIENTO`).
+pub fn probabilidades_con_fallo<'a>(
+    ref_entorno: &Entorno,
+    sAccion: &str,
+    ref_hm_s_f64ProbAccion: &'a HashMap<String, f64>,
+) -> Cow<'a, HashMap<String, f64>> {
+    if ref_entorno.eModoFallo == ModoFallo::DireccionAleatoria {
+        return Cow::Borrowed(ref_hm_s_f64ProbAccion);
+    }
+    let f64ProbExito = ref_hm_s_f64ProbAccion.get(sAccion).copied().unwrap_or(0.0);
+    let f64ProbFallo: f64 = ref_hm_s_f64ProbAccion
+        .iter()
+        .filter(|(sDireccion, _)| sDireccion.as_str() != sAccion)
+        .map(|(_, f64Prob)| f64Prob)
+        .sum();
+    let mut hm_s_f64Fallo = HashMap::new();
+    if f64ProbExito > 0.0 {
+        hm_s_f64Fallo.insert(sAccion.to_string(), f64ProbExito);
+    }
+    if f64ProbFallo > 0.0 {
+        hm_s_f64Fallo.insert(DIRECCION_SIN_MOVIMIENTO.to_string(), f64ProbFallo);
+    }
+    Cow::Owned(hm_s_f64Fallo)
+}
+
+/// Composes a state's wind with the direction distribution of an action.
+///
+/// With wind `(dirección, p)` at `sEstado` the result is (1 − p)·P(d) + p·[d = dirección]; states without
+/// wind get the distribution unchanged (borrowed, without copying).
+///
+/// # Arguments
+///
+/// * `ref_entorno` - The movement rules holding the wind.
+/// * `sEstado` - The origin state.
+/// * `ref_hm_s_f64ProbAccion` - The action's distribution over effective directions.
+///
+/// # Returns
+///
+/// The distribution over effective directions once the wind is applied.
+pub fn probabilidades_con_viento<'a>(
+    ref_entorno: &Entorno,
+    sEstado: &str,
+    ref_hm_s_f64ProbAccion: &'a HashMap<String, f64>,
+) -> Cow<'a, HashMap<String, f64>> {
+    let Some((sDireccionViento, f64ProbViento)) = ref_entorno.hm_s_tpl_s_f64Viento.get(sEstado) else {
+        return Cow::Borrowed(ref_hm_s_f64ProbAccion);
+    };
+    let mut hm_s_f64Compuesta: HashMap<String, f64> = ref_hm_s_f64ProbAccion
+        .iter()
+        .map(|(sDireccion, f64Prob)| (sDireccion.clone(), (1.0 - f64ProbViento) * f64Prob))
+        .collect();
+    *hm_s_f64Compuesta.entry(sDireccionViento.clone()).or_insert(0.0) += f64ProbViento;
+    Cow::Owned(hm_s_f64Compuesta)
+}
+
+/// Samples whether the wind at `sEstado` overrides a move, for the simulators.
+///
+/// Draws from `ref_mut_rng` only if `sEstado` has wind, so runs without wind see the same random stream.
+///
+/// # Arguments
+///
+/// * `ref_entorno` - The movement rules holding the wind.
+/// * `sEstado` - The origin state.
+/// * `sDireccion` - The direction the move would take without wind.
+/// * `ref_mut_rng` - The random number generator.
+///
+/// # Returns
+///
+/// The wind's direction with its probability, otherwise `sDireccion`.
+pub fn direccion_con_viento<'a>(
+    ref_entorno: &'a Entorno,
+    sEstado: &str,
+    sDireccion: &'a str,
+    ref_mut_rng: &mut impl Rng,
+) -> &'a str {
+    match ref_entorno.hm_s_tpl_s_f64Viento.get(sEstado) {
+        Some((sDireccionViento, f64ProbViento)) if ref_mut_rng.gen::<f64>() < *f64ProbViento => sDireccionViento,
+        _ => sDireccion,
+    }
+}
+
+/// Returns the type of a map cell under `ref_entorno`: its `hm_s_eTiposCelda` override if it has one, otherwise
+/// `texto::tipo_celda`.
+pub fn tipo_celda_con_entorno(ref_entorno: &Entorno, sEstado: &str) -> TipoCelda {
+    ref_entorno.hm_s_eTiposCelda.get(sEstado).copied().unwrap_or_else(|| tipo_celda(sEstado))
+}
+
+/// Cycles a cell to the next type (normal → obstacle → danger → goal → normal) by editing
+/// `hm_s_eTiposCelda`, so the world can be changed at runtime without touching the compile-time map.
+///
+/// A cell that returns to its map type loses its override. Re-solve with the edited `Entorno` for the
+/// policy to take it into account.
+///
+/// # Arguments
+///
+/// * `ref_mut_entorno` - The rules to edit.
+/// * `sEstado` - The cell to cycle.
+///
+/// # Returns
+///
+/// The new type of the cell, or `Err` if it is not on the map, is `ESTADO_META` (the only fixed goal cannot
+/// be removed) or is one of the map's own `OBSTACULOS`.
+pub fn alternar_tipo_celda(ref_mut_entorno: &mut Entorno, sEstado: &str) -> Result<TipoCelda, String> {
+    if obtener_posicion(sEstado).is_none() {
+        return Err(format!("Estado fuera del mapa: {}", sEstado));
+    }
+    let eTipoMapa = tipo_celda(sEstado);
+    match eTipoMapa {
+        TipoCelda::Meta => return Err(format!("{} es la única meta del mapa y no se puede quitar", sEstado)),
+        TipoCelda::Obstaculo => return Err(format!("{} es un obstáculo del mapa y no se puede editar", sEstado)),
+        TipoCelda::Normal | TipoCelda::Peligro => {}
+    }
+
+    let eTipoNuevo = match tipo_celda_con_entorno(ref_mut_entorno, sEstado) {
+        TipoCelda::Normal => TipoCelda::Obstaculo,
+        TipoCelda::Obstaculo => TipoCelda::Peligro,
+        TipoCelda::Peligro => TipoCelda::Meta,
+        TipoCelda::Meta => TipoCelda::Normal,
+    };
+    if eTipoNuevo == eTipoMapa {
+        ref_mut_entorno.hm_s_eTiposCelda.remove(sEstado);
+    } else {
+        ref_mut_entorno.hm_s_eTiposCelda.insert(sEstado.to_string(), eTipoNuevo);
+    }
+    Ok(eTipoNuevo)
+}
@@ -0,0 +1,316 @@
+/// Solver scaling benchmark on generated square grids of any size.
+///
+/// The project map (`config::MAPA_ESTADOS`) has fixed dimensions, so the benchmark runs its own copy of
+/// the value iteration backup on index-based grids: same reward tiers, transition model and goal handling
+/// as `mdp_model::value_iteration`, with cells addressed by position instead of by name.
+// src/escalado.rs
+use crate::config::{acciones, prob_transicion};
+use crate::mdp_model::{F64_PESO_DIFICULTAD_DISTANCIA, F64_PESO_DIFICULTAD_OBSTACULOS, F64_PESO_DIFICULTAD_PELIGRO};
+use ::rand::rngs::StdRng;
+use ::rand::{Rng, SeedableRng};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::collections::{HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Probability that a generated cell (other than the goal) is an obstacle.
+const F64_FRACCION_OBSTACULOS: f64 = 0.15;
+/// Probability that a generated cell (other than the goal) is a danger state, if not an obstacle.
+const F64_FRACCION_PELIGROS: f64 = 0.08;
+/// Convergence threshold of the benchmark solves (the same as `main`).
+const F64_EPSILON_ESCALADO: f64 = 0.001;
+/// Seed of the maps generated by `benchmark_escalado`, so every run times the same grids.
+const U64_SEMILLA_ESCALADO: u64 = 42;
+/// Rewards of the default tiers of `config::obtener_recompensas`.
+pub(crate) const F64_RECOMPENSA_META: f64 = 10.0;
+pub(crate) const F64_RECOMPENSA_PELIGRO: f64 = -0.5;
+pub(crate) const F64_RECOMPENSA_PASO: f64 = -0.1;
+
+/// Kind of a generated cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CeldaGenerada {
+    /// Ordinary cell with the step cost.
+    Libre,
+    /// Danger cell with the danger penalty (not terminal, as in the project map).
+    Peligro,
+    /// Blocked cell; moves into it leave the robot in place.
+    Obstaculo,
+    /// The goal, with its reward as fixed value.
+    Meta,
+}
+
+/// A generated square grid, stored row by row.
+#[derive(Debug, Clone)]
+pub struct MapaGenerado {
+    /// Side length in cells.
+    pub uiLado: usize,
+    /// The `uiLado × uiLado` cells, row-major.
+    pub vec_eCeldas: Vec<CeldaGenerada>,
+}
+
+impl MapaGenerado {
+    /// Number of states: the cells that are not obstacles.
+    pub fn num_estados(&self) -> usize {
+        self.vec_eCeldas.iter().filter(|eCelda| **eCelda != CeldaGenerada::Obstaculo).count()
+    }
+
+    /// Index of the goal cell (the bottom-right corner).
+    pub fn indice_meta(&self) -> usize {
+        self.vec_eCeldas.len() - 1
+    }
+
+    /// Reward of a cell under the default tiers: R(goal), the danger penalty or the step cost (0 for obstacles).
+    pub fn recompensa(&self, uiIndice: usize) -> f64 {
+        match self.vec_eCeldas[uiIndice] {
+            CeldaGenerada::Obstaculo => 0.0,
+            CeldaGenerada::Meta => F64_RECOMPENSA_META,
+            CeldaGenerada::Peligro => F64_RECOMPENSA_PELIGRO,
+            CeldaGenerada::Libre => F64_RECOMPENSA_PASO,
+        }
+    }
+
+    /// Resolves the cell reached moving from `uiIndice` in `sDireccion` ("N", "S", "E", "O"), as `mdp_model::mover`
+    /// does on the project map: moves off the grid, into an obstacle or in an unknown direction stay in place.
+    pub fn destino(&self, uiIndice: usize, sDireccion: &str) -> usize {
+        let (uiFila, uiCol) = (uiIndice / self.uiLado, uiIndice % self.uiLado);
+        let opt_tpl_ui_uiNueva = match sDireccion {
+            "N" => uiFila.checked_sub(1).map(|uiF| (uiF, uiCol)),
+            "S" => (uiFila + 1 < self.uiLado).then_some((uiFila + 1, uiCol)),
+            "E" => (uiCol + 1 < self.uiLado).then_some((uiFila, uiCol + 1)),
+            "O" => uiCol.checked_sub(1).map(|uiC| (uiFila, uiC)),
+            _ => None,
+        };
+        match opt_tpl_ui_uiNueva {
+            Some((uiF, uiC)) if self.vec_eCeldas[uiF * self.uiLado + uiC] != CeldaGenerada::Obstaculo => {
+                uiF * self.uiLado + uiC
+            }
+            _ => uiIndice,
+        }
+    }
+
+    /// Noise-free BFS distance from every cell to the goal, as `mdp_model::distancias_a_meta`.
+    ///
+    /// # Returns
+    ///
+    /// One entry per cell, row-major: `None` for obstacles and cells cut off from the goal.
+    pub fn distancias_a_meta(&self) -> Vec<Option<usize>> {
+        let mut vec_opt_uiDistancias = vec![None; self.vec_eCeldas.len()];
+        let uiMeta = self.indice_meta();
+        vec_opt_uiDistancias[uiMeta] = Some(0);
+        let mut vdq_uiPendientes = VecDeque::from([uiMeta]);
+        while let Some(uiActual) = vdq_uiPendientes.pop_front() {
+            let uiDistancia = vec_opt_uiDistancias[uiActual].unwrap();
+            // Moves are symmetric on a grid without walls, so the cells that reach uiActual in one step
+            // are the ones it reaches.
+            for sDireccion in acciones() {
+                let uiVecino = self.destino(uiActual, sDireccion);
+                if uiVecino != uiActual && vec_opt_uiDistancias[uiVecino].is_none() {
+                    vec_opt_uiDistancias[uiVecino] = Some(uiDistancia + 1);
+                    vdq_uiPendientes.push_back(uiVecino);
+                }
+            }
+        }
+        vec_opt_uiDistancias
+    }
+
+    /// Scores how hard the grid is to navigate, in [0, 1], with the features and weights of
+    /// `mdp_model::dificultad_mapa`: the normalized distance of the farthest cell that reaches the goal,
+    /// the obstacle fraction, and the fraction of danger cells on or next to that cell's shortest path.
+    pub fn dificultad(&self) -> f64 {
+        let vec_opt_uiDistancias = self.distancias_a_meta();
+        let uiAlcanzables = vec_opt_uiDistancias.iter().flatten().count();
+
+        // Farthest cell from the goal; ties go to the first one in row-major order.
+        let mut uiMasLejano = self.indice_meta();
+        for (uiIndice, opt_uiDistancia) in vec_opt_uiDistancias.iter().enumerate() {
+            if opt_uiDistancia.is_some_and(|uiD| uiD > vec_opt_uiDistancias[uiMasLejano].unwrap()) {
+                uiMasLejano = uiIndice;
+            }
+        }
+        let f64Distancia = if uiAlcanzables > 1 {
+            vec_opt_uiDistancias[uiMasLejano].unwrap() as f64 / (uiAlcanzables - 1) as f64
+        } else {
+            0.0
+        };
+
+        let uiObstaculos = self.vec_eCeldas.iter().filter(|eCelda| **eCelda == CeldaGenerada::Obstaculo).count();
+        let f64Obstaculos = uiObstaculos as f64 / self.vec_eCeldas.len() as f64;
+
+        // Walk the shortest path from the farthest cell, collecting danger cells on or next to it.
+        let es_peligro = |uiIndice: usize| self.vec_eCeldas[uiIndice] == CeldaGenerada::Peligro;
+        let mut hs_uiPeligrosCercanos: HashSet<usize> = HashSet::new();
+        let mut uiActual = uiMasLejano;
+        loop {
+            let vec_uiVecinos: Vec<usize> = acciones()
+                .iter()
+                .map(|sDireccion| self.destino(uiActual, sDireccion))
+                .filter(|uiVecino| *uiVecino != uiActual)
+                .collect();
+            hs_uiPeligrosCercanos.extend(
+                std::iter::once(uiActual).chain(vec_uiVecinos.iter().copied()).filter(|uiI| es_peligro(*uiI)),
+            );
+            let uiDistancia = vec_opt_uiDistancias[uiActual].unwrap();
+            if uiDistancia == 0 {
+                break;
+            }
+            uiActual = *vec_uiVecinos
+                .iter()
+                .find(|uiVecino| vec_opt_uiDistancias[**uiVecino].is_some_and(|uiD| uiD + 1 == uiDistancia))
+                .unwrap();
+        }
+        let uiPeligros = self.vec_eCeldas.iter().filter(|eCelda| **eCelda == CeldaGenerada::Peligro).count();
+        let f64Peligro = if uiPeligros > 0 {
+            hs_uiPeligrosCercanos.len() as f64 / uiPeligros as f64
+        } else {
+            0.0
+        };
+
+        F64_PESO_DIFICULTAD_DISTANCIA * f64Distancia
+            + F64_PESO_DIFICULTAD_OBSTACULOS * f64Obstaculos
+            + F64_PESO_DIFICULTAD_PELIGRO * f64Peligro
+    }
+}
+
+/// Generates a random square grid with the goal in the bottom-right corner.
+///
+/// Every other cell is an obstacle with probability `F64_FRACCION_OBSTACULOS`, otherwise a danger cell
+/// with probability `F64_FRACCION_PELIGROS`, otherwise free. Connectivity is not enforced: cells cut off
+/// from the goal simply converge to the value of wandering forever.
+///
+/// # Arguments
+///
+/// * `uiLado` - Side length in cells.
+/// * `u64Semilla` - Seed for the random number generator; a seed always gives the same grid.
+///
+/// # Returns
+///
+/// The generated `MapaGenerado`.
+///
+/// # Panics
+///
+/// If `uiLado` is 0.
+pub fn generar_mapa_cuadrado(uiLado: usize, u64Semilla: u64) -> MapaGenerado {
+    assert!(uiLado > 0, "generar_mapa_cuadrado: el lado debe ser al menos 1");
+    let mut rng = StdRng::seed_from_u64(u64Semilla);
+    let uiCeldas = uiLado * uiLado;
+    let vec_eCeldas = (0..uiCeldas)
+        .map(|uiIndice| {
+            if uiIndice == uiCeldas - 1 {
+                CeldaGenerada::Meta
+            } else if rng.gen::<f64>() < F64_FRACCION_OBSTACULOS {
+                CeldaGenerada::Obstaculo
+            } else if rng.gen::<f64>() < F64_FRACCION_PELIGROS {
+                CeldaGenerada::Peligro
+            } else {
+                CeldaGenerada::Libre
+            }
+        })
+        .collect();
+    MapaGenerado { uiLado, vec_eCeldas }
+}
+
+/// Solves a generated grid with value iteration, as `mdp_model::value_iteration` does the project map.
+///
+/// Each sweep backs up every non-obstacle, non-goal cell with V(s) = R(s) + λ·max_a Σ P(s'|s,a)·V(s')
+/// under `config::prob_transicion`, from the values of the previous sweep; the goal keeps its reward.
+/// With the `parallel` feature the cells of a sweep are backed up concurrently with rayon, which gives
+/// the same values since a sweep only reads the previous one.
+///
+/// # Arguments
+///
+/// * `ref_mapa` - The grid to solve.
+/// * `f64Lambda` - The discount factor (below 1 unless every cell can reach the goal).
+/// * `f64Epsilon` - Stop once the largest change of a sweep is below this.
+///
+/// # Returns
+///
+/// The value of every cell (0 for obstacles), row-major, and the number of sweeps.
+pub fn resolver_mapa_generado(ref_mapa: &MapaGenerado, f64Lambda: f64, f64Epsilon: f64) -> (Vec<f64>, usize) {
+    // Outcomes (destination index, probability) of every action of every cell, resolved once.
+    let hm_s_hm_s_f64Modelo = prob_transicion();
+    let vec_vec_vec_tpl_ui_f64Resultados: Vec<Vec<Vec<(usize, f64)>>> = (0..ref_mapa.vec_eCeldas.len())
+        .map(|uiIndice| {
+            acciones()
+                .iter()
+                .map(|sAccion| {
+                    hm_s_hm_s_f64Modelo[sAccion]
+                        .iter()
+                        .map(|(sDireccion, f64Prob)| (ref_mapa.destino(uiIndice, sDireccion), *f64Prob))
+                        .collect()
+                })
+                .collect()
+        })
+        .collect();
+
+    let backup = |uiIndice: usize, ref_vec_f64V: &[f64]| -> f64 {
+        let f64Recompensa = ref_mapa.recompensa(uiIndice);
+        if matches!(ref_mapa.vec_eCeldas[uiIndice], CeldaGenerada::Obstaculo | CeldaGenerada::Meta) {
+            return f64Recompensa;
+        }
+        let f64MejorEsperado = vec_vec_vec_tpl_ui_f64Resultados[uiIndice]
+            .iter()
+            .map(|vec_tpl_ui_f64Accion| {
+                vec_tpl_ui_f64Accion.iter().map(|(uiDestino, f64Prob)| f64Prob * ref_vec_f64V[*uiDestino]).sum::<f64>()
+            })
+            .fold(f64::NEG_INFINITY, f64::max);
+        f64Recompensa + f64Lambda * f64MejorEsperado
+    };
+
+    let mut vec_f64V = vec![0.0; ref_mapa.vec_eCeldas.len()];
+    let mut uiBarridos = 0;
+    loop {
+        uiBarridos += 1;
+        #[cfg(feature = "parallel")]
+        let vec_f64VNuevo: Vec<f64> = (0..vec_f64V.len()).into_par_iter().map(|uiI| backup(uiI, &vec_f64V)).collect();
+        #[cfg(not(feature = "parallel"))]
+        let vec_f64VNuevo: Vec<f64> = (0..vec_f64V.len()).map(|uiI| backup(uiI, &vec_f64V)).collect();
+        let f64DeltaMax = vec_f64V
+            .iter()
+            .zip(&vec_f64VNuevo)
+            .fold(0.0_f64, |f64Max, (f64Viejo, f64Nuevo)| f64Max.max((f64Viejo - f64Nuevo).abs()));
+        vec_f64V = vec_f64VNuevo;
+        if f64DeltaMax < f64Epsilon {
+            break;
+        }
+    }
+    (vec_f64V, uiBarridos)
+}
+
+/// Times the solver on generated square grids of increasing size.
+///
+/// For each side length a grid is generated with `generar_mapa_cuadrado` (fixed seed
+/// `U64_SEMILLA_ESCALADO`, so runs are comparable) and solved with `resolver_mapa_generado`; only the
+/// solve is timed. A sweep costs O(states · actions · outcomes), so time over states × sweeps (logged
+/// per size) should stay roughly flat. Build with `--features parallel` to compare parallel sweeps.
+///
+/// # Arguments
+///
+/// * `ref_arr_uiTamanos` - Side lengths to try (each at least 1).
+/// * `f64Lambda` - The discount factor (below 1).
+///
+/// # Returns
+///
+/// One `(side, states, solve time)` tuple per size, in the given order.
+///
+/// # Panics
+///
+/// If a side length is 0.
+pub fn benchmark_escalado(ref_arr_uiTamanos: &[usize], f64Lambda: f64) -> Vec<(usize, usize, Duration)> {
+    ref_arr_uiTamanos
+        .iter()
+        .map(|&uiLado| {
+            let mapa = generar_mapa_cuadrado(uiLado, U64_SEMILLA_ESCALADO);
+            let instInicio = Instant::now();
+            let (_, uiBarridos) = resolver_mapa_generado(&mapa, f64Lambda, F64_EPSILON_ESCALADO);
+            let durTiempo = instInicio.elapsed();
+            log::info!(
+                "Escalado: lado {}, {} estados, {} barridos, {:?}",
+                uiLado,
+                mapa.num_estados(),
+                uiBarridos,
+                durTiempo
+            );
+            (uiLado, mapa.num_estados(), durTiempo)
+        })
+        .collect()
+}
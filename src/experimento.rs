@@ -0,0 +1,592 @@
+/// Experiment sweeps over (lambda, success probability) with a column-oriented result for analysis tools,
+/// and CSV export of the learners' learning curves (single runs and curricula).
+// src/experimento.rs
+use crate::config::ESTADO_META;
+use crate::curriculo::NivelCurriculo;
+use crate::learning::{monte_carlo_valor, ResultadoAprendizaje};
+use crate::mdp_model::{evaluar_politica, retorno_esperado, value_iteration};
+use crate::robustness::construir_modelo_ruido;
+use crate::simulation::{distribucion_inicial_ordenada, episodios_con_modelo, es_estado_inicial_valido};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+/// Convergence threshold of the solves in `ejecutar_experimento` (the same as `main`).
+const F64_EPSILON_EXPERIMENTO: f64 = 0.001;
+
+/// Parameters of `ejecutar_experimento`.
+///
+/// `ParametrosExperimento::default()` sweeps the lambdas of `main` at the base success probability.
+#[derive(Debug, Clone)]
+pub struct ParametrosExperimento {
+    /// Discount factors to solve with.
+    pub vec_f64Lambdas: Vec<f64>,
+    /// Probabilities of moving in the intended direction; the rest is split between the two lateral ones
+    /// according to `f64FraccionDerecha`.
+    pub vec_f64ProbsExito: Vec<f64>,
+    /// Share of the lateral error that goes to the "right" of the intended direction, in [0, 1]. As in
+    /// `robustness::construir_modelo_ruido`, that is east for "N"/"S" moves and south for "E"/"O" moves.
+    /// 0.5 (the default) splits the error evenly; other values model a robot with a consistent drift.
+    pub f64FraccionDerecha: f64,
+    /// Independent episodes simulated per combination.
+    pub uiEpisodios: usize,
+    /// Step cap per episode.
+    pub uiMaxPasosPorEpisodio: usize,
+    /// Base seed for the episodes. Each combination mixes its lambda and success-probability indices into it
+    /// (see `semilla_combinacion`), so the cells draw independent episodes and a sweep stays reproducible.
+    pub u64Semilla: u64,
+    /// If set, every episode starts from this state, so the rows compare the policies from one fixed cell.
+    /// It must pass `simulation::es_estado_inicial_valido`, and takes precedence over
+    /// `opt_hm_s_f64DistribucionInicial`.
+    pub opt_sEstadoInicial: Option<String>,
+    /// Weights of the episodes' start states (see `simulation::distribucion_inicial_ordenada`).
+    /// `None` starts uniformly over the valid non-danger states.
+    pub opt_hm_s_f64DistribucionInicial: Option<HashMap<String, f64>>,
+    /// If `true`, prints the (lambda, success probability) combination being solved to stderr before each
+    /// solve, so a stalled sweep shows where it stopped. Independent of the log level.
+    pub bProgreso: bool,
+}
+
+impl ParametrosExperimento {
+    /// Checks the user-supplied settings: `f64FraccionDerecha` must be in [0, 1], `opt_sEstadoInicial` must
+    /// pass `simulation::es_estado_inicial_valido` and `opt_hm_s_f64DistribucionInicial` must pass
+    /// `simulation::distribucion_inicial_ordenada`.
+    ///
+    /// `ejecutar_experimento` and `ejecutar_experimento_csv` call it first and return its error.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())`, or `Err` describing the first problem found.
+    pub fn validar(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.f64FraccionDerecha) {
+            return Err(format!("f64FraccionDerecha debe estar en [0, 1], es {}", self.f64FraccionDerecha));
+        }
+        if let Some(sEstado) = &self.opt_sEstadoInicial {
+            if !es_estado_inicial_valido(sEstado) {
+                return Err(format!("Estado inicial no válido: {}", sEstado));
+            }
+        }
+        if let Some(ref_hm_s_f64Distribucion) = &self.opt_hm_s_f64DistribucionInicial {
+            distribucion_inicial_ordenada(ref_hm_s_f64Distribucion)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for ParametrosExperimento {
+    fn default() -> Self {
+        ParametrosExperimento {
+            vec_f64Lambdas: vec![0.86, 0.90, 0.94, 0.98],
+            vec_f64ProbsExito: vec![0.8],
+            f64FraccionDerecha: 0.5,
+            uiEpisodios: 1000,
+            uiMaxPasosPorEpisodio: 100,
+            u64Semilla: 42,
+            opt_sEstadoInicial: None,
+            opt_hm_s_f64DistribucionInicial: None,
+            bProgreso: false,
+        }
+    }
+}
+
+/// Experiment results as parallel columns: row `i` of every vector describes the same combination.
+///
+/// The layout matches a DataFrame, so `guardar_json` output loads directly with
+/// `pandas.DataFrame(json)` / `polars.DataFrame(json)`, and `guardar_csv` with any CSV reader.
+/// Both files use the same column names.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResultadoTabular {
+    /// Discount factor.
+    #[serde(rename = "lambda")]
+    pub vec_f64Lambda: Vec<f64>,
+    /// Success probability of the transition model.
+    #[serde(rename = "prob_exito")]
+    pub vec_f64ProbExito: Vec<f64>,
+    /// Probability of slipping to the left of the intended direction.
+    #[serde(rename = "prob_izq")]
+    pub vec_f64ProbIzq: Vec<f64>,
+    /// Probability of slipping to the right of the intended direction.
+    #[serde(rename = "prob_der")]
+    pub vec_f64ProbDer: Vec<f64>,
+    /// Mean undiscounted return per episode.
+    #[serde(rename = "recompensa_media")]
+    pub vec_f64RecompensaMedia: Vec<f64>,
+    /// 10th percentile of the per-episode returns: the bad runs (e.g. into danger) the mean hides.
+    #[serde(rename = "recompensa_p10")]
+    pub vec_f64RecompensaP10: Vec<f64>,
+    /// Median of the per-episode returns.
+    #[serde(rename = "recompensa_p50")]
+    pub vec_f64RecompensaP50: Vec<f64>,
+    /// 90th percentile of the per-episode returns.
+    #[serde(rename = "recompensa_p90")]
+    pub vec_f64RecompensaP90: Vec<f64>,
+    /// Fraction of episodes that reached the goal.
+    #[serde(rename = "tasa_exito")]
+    pub vec_f64TasaExito: Vec<f64>,
+    /// Episodes that ended on entering a danger state, per 1000 simulated steps (all episodes' steps).
+    #[serde(rename = "peligros_por_1000_pasos")]
+    pub vec_f64PeligrosPor1000Pasos: Vec<f64>,
+    /// Mean steps of the episodes that reached the goal (`None` if none did).
+    #[serde(rename = "pasos_medios")]
+    pub vec_opt_f64PasosMedios: Vec<Option<f64>>,
+    /// Expected start-state value of the solve (`mdp_model::retorno_esperado`, uniform start).
+    #[serde(rename = "valor_inicial")]
+    pub vec_f64ValorInicial: Vec<f64>,
+}
+
+impl ResultadoTabular {
+    /// Number of rows.
+    pub fn len(&self) -> usize {
+        self.vec_f64Lambda.len()
+    }
+
+    /// Returns `true` if there are no rows.
+    pub fn is_empty(&self) -> bool {
+        self.vec_f64Lambda.is_empty()
+    }
+
+    /// Writes the table as CSV, one row per combination; a missing mean step count is left empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `sRuta` - Path of the output file.
+    ///
+    /// # Returns
+    ///
+    /// `Result<(), Box<dyn std::error::Error>>` indicating success or an I/O error.
+    pub fn guardar_csv(&self, sRuta: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut bwArchivo = BufWriter::new(File::create(sRuta)?);
+        escribir_encabezado_csv(&mut bwArchivo)?;
+        for uiFila in 0..self.len() {
+            escribir_fila_csv(&mut bwArchivo, &self.fila(uiFila))?;
+        }
+        bwArchivo.flush()?;
+
+        log::info!("{} guardado.", sRuta);
+        Ok(())
+    }
+
+    /// Writes the table as a JSON object of columns (column name -> array of values).
+    ///
+    /// # Arguments
+    ///
+    /// * `sRuta` - Path of the output file.
+    ///
+    /// # Returns
+    ///
+    /// `Result<(), Box<dyn std::error::Error>>` indicating success or an I/O/serialization error.
+    pub fn guardar_json(&self, sRuta: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let fArchivo = File::create(sRuta)?;
+        serde_json::to_writer_pretty(BufWriter::new(fArchivo), self)?;
+        log::info!("{} guardado.", sRuta);
+        Ok(())
+    }
+}
+
+/// Solves and simulates every (lambda, success probability) combination of `ref_parametros`.
+///
+/// Besides the mean, each row keeps the 10th, 50th and 90th percentiles of the per-episode returns, and the
+/// danger-hit rate per 1000 steps (see `plot_utils::graficar_superficie_peligros`).
+///
+/// Each combination is solved with `value_iteration` under `construir_modelo_ruido` with that success
+/// probability and the lateral error split by `f64FraccionDerecha`, then its policy is run for
+/// `uiEpisodios` independent episodes with the same noise (as in `simulation::tasa_exito`).
+/// With the `parallel` feature the combinations run concurrently with rayon; each combination seeds its
+/// episodes from `u64Semilla` and its own position in the sweep (see `semilla_combinacion`), so the rows are
+/// the same, and in the same order, either way.
+///
+/// # Arguments
+///
+/// * `ref_parametros` - The sweep (see `ParametrosExperimento`).
+///
+/// # Returns
+///
+/// A `ResultadoTabular` with one row per combination, lambdas in the outer loop, or `Err` with the message
+/// of `ParametrosExperimento::validar` if `ref_parametros` fails it.
+pub fn ejecutar_experimento(ref_parametros: &ParametrosExperimento) -> Result<ResultadoTabular, String> {
+    ref_parametros.validar()?;
+    let opt_vec_tpl_s_f64Distribucion =
+        ref_parametros.opt_hm_s_f64DistribucionInicial.as_ref().map(distribucion_inicial_ordenada).transpose()?;
+    let vec_tpl_f64f64Combinaciones: Vec<(f64, f64)> = ref_parametros
+        .vec_f64Lambdas
+        .iter()
+        .flat_map(|&f64Lambda| {
+            ref_parametros.vec_f64ProbsExito.iter().map(move |&f64ProbExito| (f64Lambda, f64ProbExito))
+        })
+        .collect();
+    let uiCombinaciones = vec_tpl_f64f64Combinaciones.len();
+    let uiProbs = ref_parametros.vec_f64ProbsExito.len();
+
+    // Each combination is an independent solve-and-simulate job seeded only by `u64Semilla` and its indices,
+    // so the rows do not depend on which thread runs them.
+    let evaluar = |(uiIndice, &(f64Lambda, f64ProbExito)): (usize, &(f64, f64))| -> FilaExperimento {
+        if ref_parametros.bProgreso {
+            eprintln!(
+                "… [{}/{}] Resolviendo λ = {:.2}, prob = {:.2}",
+                uiIndice + 1,
+                uiCombinaciones,
+                f64Lambda,
+                f64ProbExito
+            );
+        }
+        evaluar_combinacion(
+            ref_parametros,
+            opt_vec_tpl_s_f64Distribucion.as_deref(),
+            (uiIndice / uiProbs, uiIndice % uiProbs),
+            f64Lambda,
+            f64ProbExito,
+        )
+    };
+
+    // `collect` keeps the combinations in sweep order in both cases.
+    #[cfg(feature = "parallel")]
+    let vec_Filas: Vec<FilaExperimento> = vec_tpl_f64f64Combinaciones.par_iter().enumerate().map(evaluar).collect();
+    #[cfg(not(feature = "parallel"))]
+    let vec_Filas: Vec<FilaExperimento> = vec_tpl_f64f64Combinaciones.iter().enumerate().map(evaluar).collect();
+
+    let mut resultado = ResultadoTabular::default();
+    for fila in vec_Filas {
+        resultado.agregar_fila(fila);
+    }
+    Ok(resultado)
+}
+
+/// One row of `ResultadoTabular`: the outcome of a single (lambda, success probability) combination.
+struct FilaExperimento {
+    f64Lambda: f64,
+    f64ProbExito: f64,
+    f64ProbIzq: f64,
+    f64ProbDer: f64,
+    f64RecompensaMedia: f64,
+    f64RecompensaP10: f64,
+    f64RecompensaP50: f64,
+    f64RecompensaP90: f64,
+    f64TasaExito: f64,
+    f64PeligrosPor1000Pasos: f64,
+    opt_f64PasosMedios: Option<f64>,
+    f64ValorInicial: f64,
+}
+
+impl ResultadoTabular {
+    /// Returns row `uiFila` of every column.
+    fn fila(&self, uiFila: usize) -> FilaExperimento {
+        FilaExperimento {
+            f64Lambda: self.vec_f64Lambda[uiFila],
+            f64ProbExito: self.vec_f64ProbExito[uiFila],
+            f64ProbIzq: self.vec_f64ProbIzq[uiFila],
+            f64ProbDer: self.vec_f64ProbDer[uiFila],
+            f64RecompensaMedia: self.vec_f64RecompensaMedia[uiFila],
+            f64RecompensaP10: self.vec_f64RecompensaP10[uiFila],
+            f64RecompensaP50: self.vec_f64RecompensaP50[uiFila],
+            f64RecompensaP90: self.vec_f64RecompensaP90[uiFila],
+            f64TasaExito: self.vec_f64TasaExito[uiFila],
+            f64PeligrosPor1000Pasos: self.vec_f64PeligrosPor1000Pasos[uiFila],
+            opt_f64PasosMedios: self.vec_opt_f64PasosMedios[uiFila],
+            f64ValorInicial: self.vec_f64ValorInicial[uiFila],
+        }
+    }
+
+    /// Appends a row to every column.
+    fn agregar_fila(&mut self, fila: FilaExperimento) {
+        self.vec_f64Lambda.push(fila.f64Lambda);
+        self.vec_f64ProbExito.push(fila.f64ProbExito);
+        self.vec_f64ProbIzq.push(fila.f64ProbIzq);
+        self.vec_f64ProbDer.push(fila.f64ProbDer);
+        self.vec_f64RecompensaMedia.push(fila.f64RecompensaMedia);
+        self.vec_f64RecompensaP10.push(fila.f64RecompensaP10);
+        self.vec_f64RecompensaP50.push(fila.f64RecompensaP50);
+        self.vec_f64RecompensaP90.push(fila.f64RecompensaP90);
+        self.vec_f64TasaExito.push(fila.f64TasaExito);
+        self.vec_f64PeligrosPor1000Pasos.push(fila.f64PeligrosPor1000Pasos);
+        self.vec_opt_f64PasosMedios.push(fila.opt_f64PasosMedios);
+        self.vec_f64ValorInicial.push(fila.f64ValorInicial);
+    }
+}
+
+/// Writes the CSV header of `ResultadoTabular::guardar_csv`.
+fn escribir_encabezado_csv(ref_mut_escritor: &mut impl Write) -> std::io::Result<()> {
+    writeln!(
+        ref_mut_escritor,
+        "lambda,prob_exito,prob_izq,prob_der,recompensa_media,recompensa_p10,recompensa_p50,recompensa_p90,\
+         tasa_exito,peligros_por_1000_pasos,pasos_medios,valor_inicial"
+    )
+}
+
+/// Writes one CSV row of `ResultadoTabular::guardar_csv`; a missing mean step count is left empty.
+fn escribir_fila_csv(ref_mut_escritor: &mut impl Write, ref_fila: &FilaExperimento) -> std::io::Result<()> {
+    writeln!(
+        ref_mut_escritor,
+        "{},{},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4},{},{:.4}",
+        ref_fila.f64Lambda,
+        ref_fila.f64ProbExito,
+        ref_fila.f64ProbIzq,
+        ref_fila.f64ProbDer,
+        ref_fila.f64RecompensaMedia,
+        ref_fila.f64RecompensaP10,
+        ref_fila.f64RecompensaP50,
+        ref_fila.f64RecompensaP90,
+        ref_fila.f64TasaExito,
+        ref_fila.f64PeligrosPor1000Pasos,
+        ref_fila.opt_f64PasosMedios.map_or(String::new(), |f64Pasos| format!("{:.2}", f64Pasos)),
+        ref_fila.f64ValorInicial
+    )
+}
+
+/// Runs the same sweep as `ejecutar_experimento`, writing each row to a CSV file as soon as it is computed.
+///
+/// The file has the header and columns of `ResultadoTabular::guardar_csv`. It is written through a
+/// `BufWriter` that is flushed after every row, so if a long sweep is interrupted the rows finished so far
+/// are already on disk. The combinations run one after another, even with the `parallel` feature, so that
+/// rows reach the file in sweep order.
+///
+/// # Arguments
+///
+/// * `ref_parametros` - The sweep (see `ParametrosExperimento`).
+/// * `sRuta` - Path of the output file.
+///
+/// # Returns
+///
+/// The complete `ResultadoTabular`, or an I/O error, or the error of `ParametrosExperimento::validar` (before
+/// the file is created) if `ref_parametros` fails it.
+pub fn ejecutar_experimento_csv(
+    ref_parametros: &ParametrosExperimento,
+    sRuta: &str,
+) -> Result<ResultadoTabular, Box<dyn std::error::Error>> {
+    ref_parametros.validar()?;
+    let opt_vec_tpl_s_f64Distribucion =
+        ref_parametros.opt_hm_s_f64DistribucionInicial.as_ref().map(distribucion_inicial_ordenada).transpose()?;
+    let uiCombinaciones = ref_parametros.vec_f64Lambdas.len() * ref_parametros.vec_f64ProbsExito.len();
+    let mut bwArchivo = BufWriter::new(File::create(sRuta)?);
+    escribir_encabezado_csv(&mut bwArchivo)?;
+    bwArchivo.flush()?;
+
+    let mut resultado = ResultadoTabular::default();
+    for (uiIndiceLambda, &f64Lambda) in ref_parametros.vec_f64Lambdas.iter().enumerate() {
+        for (uiIndiceProb, &f64ProbExito) in ref_parametros.vec_f64ProbsExito.iter().enumerate() {
+            if ref_parametros.bProgreso {
+                eprintln!(
+                    "… [{}/{}] Resolviendo λ = {:.2}, prob = {:.2}",
+                    resultado.len() + 1,
+                    uiCombinaciones,
+                    f64Lambda,
+                    f64ProbExito
+                );
+            }
+            let fila = evaluar_combinacion(
+                ref_parametros,
+                opt_vec_tpl_s_f64Distribucion.as_deref(),
+                (uiIndiceLambda, uiIndiceProb),
+                f64Lambda,
+                f64ProbExito,
+            );
+            escribir_fila_csv(&mut bwArchivo, &fila)?;
+            bwArchivo.flush()?;
+            resultado.agregar_fila(fila);
+        }
+    }
+
+    log::info!("{} guardado.", sRuta);
+    Ok(resultado)
+}
+
+/// Seed of the episodes of the combination at (`uiIndiceLambda`, `uiIndiceProb`) of the sweep: `u64Semilla`
+/// with both indices mixed in by SplitMix64 rounds, so neighbouring cells get unrelated streams.
+fn semilla_combinacion(u64Semilla: u64, uiIndiceLambda: usize, uiIndiceProb: usize) -> u64 {
+    let mezclar = |u64Valor: u64| -> u64 {
+        let mut u64Z = u64Valor.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        u64Z = (u64Z ^ (u64Z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        u64Z = (u64Z ^ (u64Z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        u64Z ^ (u64Z >> 31)
+    };
+    mezclar(mezclar(mezclar(u64Semilla) ^ uiIndiceLambda as u64) ^ uiIndiceProb as u64)
+}
+
+/// Solves and simulates one (lambda, success probability) combination of `ejecutar_experimento`, starting
+/// the episodes from `opt_ref_arr_tpl_s_f64Distribucion` (the validated `opt_hm_s_f64DistribucionInicial`)
+/// and seeding them with `semilla_combinacion` of the combination's `(lambda, probability)` indices.
+fn evaluar_combinacion(
+    ref_parametros: &ParametrosExperimento,
+    opt_ref_arr_tpl_s_f64Distribucion: Option<&[(&'static str, f64)]>,
+    tpl_uiIndices: (usize, usize),
+    f64Lambda: f64,
+    f64ProbExito: f64,
+) -> FilaExperimento {
+    let f64ProbDer = (1.0 - f64ProbExito) * ref_parametros.f64FraccionDerecha;
+    let f64ProbIzq = (1.0 - f64ProbExito) - f64ProbDer;
+    let hm_s_hm_s_f64Modelo = construir_modelo_ruido(f64ProbIzq, f64ProbExito, f64ProbDer);
+    let (hm_s_f64Valores, hm_s_sPolitica) =
+        value_iteration(f64Lambda, F64_EPSILON_EXPERIMENTO, Some(&hm_s_hm_s_f64Modelo));
+
+    let vec_tpl_bb_f64_uiEpisodios = episodios_con_modelo(
+        &hm_s_sPolitica,
+        ref_parametros.uiEpisodios,
+        ref_parametros.uiMaxPasosPorEpisodio,
+        hm_s_hm_s_f64Modelo,
+        semilla_combinacion(ref_parametros.u64Semilla, tpl_uiIndices.0, tpl_uiIndices.1),
+        ref_parametros.opt_sEstadoInicial.as_deref(),
+        opt_ref_arr_tpl_s_f64Distribucion,
+    );
+    let f64Episodios = vec_tpl_bb_f64_uiEpisodios.len().max(1) as f64;
+    let mut vec_f64Retornos: Vec<f64> =
+        vec_tpl_bb_f64_uiEpisodios.iter().map(|(_, _, f64Retorno, _)| *f64Retorno).collect();
+    let f64RecompensaMedia = vec_f64Retornos.iter().sum::<f64>() / f64Episodios;
+    vec_f64Retornos.sort_by(|f64A, f64B| f64A.total_cmp(f64B));
+    let vec_uiPasosExito: Vec<usize> = vec_tpl_bb_f64_uiEpisodios
+        .iter()
+        .filter(|(bExito, _, _, _)| *bExito)
+        .map(|(_, _, _, uiPasos)| *uiPasos)
+        .collect();
+    // Episodes end on the first danger entered, so each danger-ending episode is one hit.
+    let uiPeligros = vec_tpl_bb_f64_uiEpisodios.iter().filter(|(_, bPeligro, _, _)| *bPeligro).count();
+    let uiPasosTotales: usize = vec_tpl_bb_f64_uiEpisodios.iter().map(|(_, _, _, uiPasos)| *uiPasos).sum();
+
+    FilaExperimento {
+        f64Lambda,
+        f64ProbExito,
+        f64ProbIzq,
+        f64ProbDer,
+        f64RecompensaMedia,
+        f64RecompensaP10: percentil(&vec_f64Retornos, 0.10),
+        f64RecompensaP50: percentil(&vec_f64Retornos, 0.50),
+        f64RecompensaP90: percentil(&vec_f64Retornos, 0.90),
+        f64TasaExito: vec_uiPasosExito.len() as f64 / f64Episodios,
+        f64PeligrosPor1000Pasos: uiPeligros as f64 * 1000.0 / uiPasosTotales.max(1) as f64,
+        opt_f64PasosMedios: (!vec_uiPasosExito.is_empty())
+            .then(|| vec_uiPasosExito.iter().sum::<usize>() as f64 / vec_uiPasosExito.len() as f64),
+        f64ValorInicial: retorno_esperado(&hm_s_f64Valores, None),
+    }
+}
+
+/// Percentile `f64P` (in [0, 1]) of sorted values, interpolating linearly between the two closest ranks
+/// (rank `f64P·(n − 1)`, as NumPy's default). Returns 0 for no values, as the mean does.
+fn percentil(ref_arr_f64Ordenados: &[f64], f64P: f64) -> f64 {
+    let Some(uiUltimo) = ref_arr_f64Ordenados.len().checked_sub(1) else {
+        return 0.0;
+    };
+    let f64Rango = f64P.clamp(0.0, 1.0) * uiUltimo as f64;
+    let uiBajo = f64Rango.floor() as usize;
+    let uiAlto = f64Rango.ceil() as usize;
+    let f64Fraccion = f64Rango - uiBajo as f64;
+    ref_arr_f64Ordenados[uiBajo] + f64Fraccion * (ref_arr_f64Ordenados[uiAlto] - ref_arr_f64Ordenados[uiBajo])
+}
+
+/// Writes a learner's learning curve to CSV: `episodio,recompensa,pasos`, one row per episode.
+///
+/// The episode index counts from 0, as in `learning::EsquemaEpsilon::epsilon`; the reward is the
+/// undiscounted per-episode total and the steps equal `uiMaxPasos` for episodes that missed the goal.
+/// Curves of different learners or hyperparameters can be written to separate files and overlaid.
+///
+/// # Arguments
+///
+/// * `ref_resultado` - The output of any of the `learning` learners.
+/// * `sRuta` - Path of the output file.
+///
+/// # Returns
+///
+/// `Result<(), Box<dyn std::error::Error>>` indicating success or an I/O error.
+pub fn guardar_curva_aprendizaje_csv(
+    ref_resultado: &ResultadoAprendizaje,
+    sRuta: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut bwArchivo = BufWriter::new(File::create(sRuta)?);
+    writeln!(bwArchivo, "episodio,recompensa,pasos")?;
+    for (uiEpisodio, (f64Recompensa, uiPasos)) in ref_resultado
+        .vec_f64RecompensaPorEpisodio
+        .iter()
+        .zip(&ref_resultado.vec_uiPasosPorEpisodio)
+        .enumerate()
+    {
+        writeln!(bwArchivo, "{},{:.4},{}", uiEpisodio, f64Recompensa, uiPasos)?;
+    }
+    bwArchivo.flush()?;
+
+    log::info!("{} guardado.", sRuta);
+    Ok(())
+}
+
+/// Writes the learning curves of a curriculum to CSV: `nivel,dificultad,episodio,recompensa,pasos`.
+///
+/// One row per episode of every level, in curriculum order; levels count from 1 and episodes from 0 within
+/// each level, as in `guardar_curva_aprendizaje_csv`. The episodes-to-solve of each level is logged.
+///
+/// # Arguments
+///
+/// * `ref_arr_Niveles` - The output of `curriculo::ejecutar_curriculo`.
+/// * `sRuta` - Path of the output file.
+///
+/// # Returns
+///
+/// `Result<(), Box<dyn std::error::Error>>` indicating success or an I/O error.
+pub fn guardar_curriculo_csv(
+    ref_arr_Niveles: &[NivelCurriculo],
+    sRuta: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut bwArchivo = BufWriter::new(File::create(sRuta)?);
+    writeln!(bwArchivo, "nivel,dificultad,episodio,recompensa,pasos")?;
+    for (uiNivel, ref_nivel) in ref_arr_Niveles.iter().enumerate() {
+        for (uiEpisodio, (f64Recompensa, uiPasos)) in ref_nivel
+            .vec_f64RecompensaPorEpisodio
+            .iter()
+            .zip(&ref_nivel.vec_uiPasosPorEpisodio)
+            .enumerate()
+        {
+            writeln!(
+                bwArchivo,
+                "{},{:.4},{},{:.4},{}",
+                uiNivel + 1,
+                ref_nivel.f64Dificultad,
+                uiEpisodio,
+                f64Recompensa,
+                uiPasos
+            )?;
+        }
+    }
+    bwArchivo.flush()?;
+
+    log::info!("{} guardado.", sRuta);
+    Ok(())
+}
+
+/// Measures how close first-visit Monte Carlo gets to the model-based values of a policy as episodes grow.
+///
+/// For each budget in `ref_arr_uiEpisodios`, runs `learning::monte_carlo_valor` with that many episodes
+/// (same seed) and compares its estimates with `mdp_model::evaluar_politica` under the base model. The
+/// goal, whose value both fix to R(goal), and states Monte Carlo never visited are skipped.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_sPolitica` - The policy (State -> Action) to evaluate.
+/// * `ref_arr_uiEpisodios` - Episode budgets to try, e.g. `[100, 1000, 10000]`.
+/// * `f64Lambda` - The discount factor (below 1).
+/// * `uiMaxPasos` - Step cap per Monte Carlo episode.
+/// * `u64Semilla` - Seed for the random number generator.
+///
+/// # Returns
+///
+/// `(episodes, mean |V_MC − V_modelo|)` for each budget, in the given order.
+pub fn error_monte_carlo(
+    ref_hm_s_sPolitica: &HashMap<String, String>,
+    ref_arr_uiEpisodios: &[usize],
+    f64Lambda: f64,
+    uiMaxPasos: usize,
+    u64Semilla: u64,
+) -> Vec<(usize, f64)> {
+    let hm_s_f64Modelo = evaluar_politica(ref_hm_s_sPolitica, f64Lambda, 1e-9, None);
+    ref_arr_uiEpisodios
+        .iter()
+        .map(|&uiEpisodios| {
+            let hm_s_f64MonteCarlo =
+                monte_carlo_valor(ref_hm_s_sPolitica, uiEpisodios, f64Lambda, uiMaxPasos, u64Semilla);
+            let vec_f64Errores: Vec<f64> = hm_s_f64MonteCarlo
+                .iter()
+                .filter(|(sEstado, _)| sEstado.as_str() != ESTADO_META)
+                .map(|(sEstado, f64Valor)| (f64Valor - hm_s_f64Modelo[sEstado.as_str()]).abs())
+                .collect();
+            let f64ErrorMedio = vec_f64Errores.iter().sum::<f64>() / vec_f64Errores.len().max(1) as f64;
+            (uiEpisodios, f64ErrorMedio)
+        })
+        .collect()
+}
@@ -0,0 +1,179 @@
+/// egui inspector panel drawn over the visual simulation (`egui` feature).
+///
+/// Shows the selected state's Q-values as bars, the sweep count of the last solve and toggles
+/// for policy-arrow and value overlays on the map. Clicking a map cell selects its state.
+// src/inspector.rs
+use crate::config::{acciones, COLUMNAS_MAPA, ESTADO_META, FILAS_MAPA, MAPA_ESTADOS, OBSTACULOS};
+use crate::entorno::Entorno;
+use crate::learning::TablaQ;
+use crate::mdp_model::{tabla_q_desde_valores, value_iteration_con_opciones, OpcionesValueIteration};
+use egui_macroquad::egui;
+use macroquad::prelude::*;
+use std::collections::HashMap;
+
+/// State of the inspector panel and the solve it describes.
+pub struct Inspector {
+    /// State whose details are shown, set by clicking a map cell.
+    pub opt_sEstadoSeleccionado: Option<String>,
+    /// Draw the policy arrow in every cell.
+    pub bMostrarFlechas: bool,
+    /// Draw V(s) in every cell.
+    pub bMostrarValores: bool,
+    /// State values of the last solve.
+    pub hm_s_f64Valores: HashMap<&'static str, f64>,
+    /// Q-values derived from `hm_s_f64Valores`.
+    pub tabla_q: TablaQ,
+    /// Sweeps performed by the last solve.
+    pub uiIteraciones: usize,
+    /// `true` while the pointer is over the panel, so clicks there do not select cells.
+    bPunteroEnPanel: bool,
+}
+
+impl Inspector {
+    /// Solves the MDP for `f64Lambda`, an optional transition model and the movement rules of `ref_entorno`
+    /// and builds a panel for it.
+    pub fn nuevo(
+        f64Lambda: f64,
+        opt_hm_s_hm_s_f64Modelo: Option<&HashMap<String, HashMap<String, f64>>>,
+        ref_entorno: &Entorno,
+    ) -> Self {
+        let mut inspector = Inspector {
+            opt_sEstadoSeleccionado: None,
+            bMostrarFlechas: true,
+            bMostrarValores: false,
+            hm_s_f64Valores: HashMap::new(),
+            tabla_q: TablaQ::new(),
+            uiIteraciones: 0,
+            bPunteroEnPanel: false,
+        };
+        inspector.resolver(f64Lambda, opt_hm_s_hm_s_f64Modelo, ref_entorno);
+        inspector
+    }
+
+    /// Re-solves with new parameters (called when the policy is re-solved from the control panel).
+    pub fn resolver(
+        &mut self,
+        f64Lambda: f64,
+        opt_hm_s_hm_s_f64Modelo: Option<&HashMap<String, HashMap<String, f64>>>,
+        ref_entorno: &Entorno,
+    ) {
+        let opciones = OpcionesValueIteration {
+            entorno: ref_entorno.clone(),
+            ..Default::default()
+        };
+        let resultado = value_iteration_con_opciones(f64Lambda, 0.001, opt_hm_s_hm_s_f64Modelo, &opciones);
+        self.tabla_q = tabla_q_desde_valores(&resultado.hm_s_f64Valores, f64Lambda, opt_hm_s_hm_s_f64Modelo);
+        self.hm_s_f64Valores = resultado.hm_s_f64Valores;
+        self.uiIteraciones = resultado.uiIteraciones;
+    }
+
+    /// Selects the state under the mouse on a left click on the map.
+    pub fn procesar_click(&mut self, f32TamanoCelda: f32) {
+        if self.bPunteroEnPanel || !is_mouse_button_pressed(MouseButton::Left) {
+            return;
+        }
+        let (f32MouseX, f32MouseY) = mouse_position();
+        let iCol = (f32MouseX / f32TamanoCelda).floor() as isize;
+        let iFila = (f32MouseY / f32TamanoCelda).floor() as isize;
+        if iFila >= 0 && iFila < FILAS_MAPA as isize && iCol >= 0 && iCol < COLUMNAS_MAPA as isize {
+            self.opt_sEstadoSeleccionado = Some(MAPA_ESTADOS[iFila as usize][iCol as usize].to_string());
+        }
+    }
+
+    /// Draws the enabled overlays (policy arrows, values) and outlines the selected cell.
+    pub fn dibujar_superposiciones(&self, ref_hm_s_sPolitica: &HashMap<String, String>, f32TamanoCelda: f32) {
+        for (uiFila, arr_sFilaEstados) in MAPA_ESTADOS.iter().enumerate() {
+            for (uiCol, sEstado) in arr_sFilaEstados.iter().enumerate() {
+                if OBSTACULOS.contains(sEstado) {
+                    continue;
+                }
+                let f32X = uiCol as f32 * f32TamanoCelda;
+                let f32Y = uiFila as f32 * f32TamanoCelda;
+                let f32CentroX = f32X + f32TamanoCelda / 2.0;
+                let f32CentroY = f32Y + f32TamanoCelda / 2.0;
+
+                if self.bMostrarFlechas && *sEstado != ESTADO_META {
+                    if let Some(sAccion) = ref_hm_s_sPolitica.get(*sEstado) {
+                        let (f32Dx, f32Dy) = match sAccion.as_str() {
+                            "N" => (0.0, -1.0),
+                            "S" => (0.0, 1.0),
+                            "E" => (1.0, 0.0),
+                            _ => (-1.0, 0.0),
+                        };
+                        let f32Largo = f32TamanoCelda * 0.3;
+                        draw_line(
+                            f32CentroX - f32Dx * f32Largo / 2.0,
+                            f32CentroY - f32Dy * f32Largo / 2.0,
+                            f32CentroX + f32Dx * f32Largo / 2.0,
+                            f32CentroY + f32Dy * f32Largo / 2.0,
+                            3.0,
+                            BLACK,
+                        );
+                        draw_circle(f32CentroX + f32Dx * f32Largo / 2.0, f32CentroY + f32Dy * f32Largo / 2.0, 4.0, BLACK);
+                    }
+                }
+                if self.bMostrarValores {
+                    if let Some(f64Valor) = self.hm_s_f64Valores.get(*sEstado) {
+                        draw_text(&format!("{:.2}", f64Valor), f32X + 6.0, f32Y + f32TamanoCelda - 8.0, 18.0, BLACK);
+                    }
+                }
+                if self.opt_sEstadoSeleccionado.as_deref() == Some(*sEstado) {
+                    draw_rectangle_lines(f32X + 1.0, f32Y + 1.0, f32TamanoCelda - 2.0, f32TamanoCelda - 2.0, 4.0, YELLOW);
+                }
+            }
+        }
+    }
+
+    /// Builds and draws the egui panel. Call once per frame, after the map has been drawn.
+    pub fn dibujar_panel(&mut self, ref_hm_s_sPolitica: &HashMap<String, String>) {
+        egui_macroquad::ui(|ctxEgui| {
+            egui::Window::new("Inspector")
+                .anchor(egui::Align2::RIGHT_TOP, [-10.0, 10.0])
+                .resizable(false)
+                .show(ctxEgui, |uiEgui| {
+                    uiEgui.label(format!("Barridos de value iteration: {}", self.uiIteraciones));
+                    uiEgui.checkbox(&mut self.bMostrarFlechas, "Flechas de la política");
+                    uiEgui.checkbox(&mut self.bMostrarValores, "Valores V(s)");
+                    uiEgui.separator();
+
+                    match &self.opt_sEstadoSeleccionado {
+                        None => {
+                            uiEgui.label("Haz clic en una celda para inspeccionarla.");
+                        }
+                        Some(sEstado) => {
+                            uiEgui.heading(sEstado.as_str());
+                            if let Some(f64Valor) = self.hm_s_f64Valores.get(sEstado.as_str()) {
+                                uiEgui.label(format!("V(s) = {:.3}", f64Valor));
+                            }
+                            if let Some(sAccion) = ref_hm_s_sPolitica.get(sEstado) {
+                                uiEgui.label(format!("Acción: {}", sAccion));
+                            }
+                            match self.tabla_q.get(sEstado) {
+                                Some(hm_s_f64Q) => {
+                                    // Bars are scaled between the worst and best action of the state.
+                                    let f64Min = hm_s_f64Q.values().copied().fold(f64::INFINITY, f64::min);
+                                    let f64Max = hm_s_f64Q.values().copied().fold(f64::NEG_INFINITY, f64::max);
+                                    for sAccion in acciones() {
+                                        let f64Q = hm_s_f64Q[sAccion];
+                                        let f32Fraccion = if f64Max > f64Min {
+                                            (0.1 + 0.9 * (f64Q - f64Min) / (f64Max - f64Min)) as f32
+                                        } else {
+                                            1.0
+                                        };
+                                        uiEgui.add(
+                                            egui::ProgressBar::new(f32Fraccion).text(format!("{}: {:.3}", sAccion, f64Q)),
+                                        );
+                                    }
+                                }
+                                None => {
+                                    uiEgui.label("Sin valores Q (meta u obstáculo).");
+                                }
+                            }
+                        }
+                    }
+                });
+            self.bPunteroEnPanel = ctxEgui.is_pointer_over_area();
+        });
+        egui_macroquad::draw();
+    }
+}
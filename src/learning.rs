@@ -0,0 +1,998 @@
+/// Model-free learners (Dyna-Q, n-step Q-learning, SARSA(λ), Expected SARSA, Double Q-learning) and the environment-stepping helpers they share.
+///
+/// The environment follows the same conventions as `value_iteration`: taking an action in `s`
+/// earns R(s), the effective direction is sampled from a transition model, moves are resolved with
+/// `entorno::resolver_destino`, and reaching the goal ends the episode with a terminal value of R(goal).
+/// The greedy policy of a converged Q-table is therefore comparable with the solver's policy.
+// src/learning.rs
+use crate::config::{acciones, obtener_recompensas, ESTADO_META, MAPA_ESTADOS, OBSTACULOS};
+use crate::entorno::{
+    direccion_con_viento, paga_choque, probabilidades_con_fallo, resolver_destino, Entorno, DIRECCION_SIN_MOVIMIENTO,
+};
+use crate::mdp_model::{distancias_a_meta, modelo_transicion_base};
+use ::rand::rngs::StdRng;
+use ::rand::seq::SliceRandom;
+use ::rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+/// Action values Q(s, a): state -> action -> value.
+pub type TablaQ = HashMap<String, HashMap<String, f64>>;
+
+/// Output of a model-free learner.
+#[derive(Debug, Clone)]
+pub struct ResultadoAprendizaje {
+    /// The learned action values.
+    pub tabla_q: TablaQ,
+    /// The greedy policy with respect to `tabla_q` (State -> Action).
+    pub hm_s_sPolitica: HashMap<String, String>,
+    /// Steps taken in each episode (equals `uiMaxPasos` if the goal was not reached).
+    pub vec_uiPasosPorEpisodio: Vec<usize>,
+    /// Undiscounted sum of rewards collected in each episode.
+    pub vec_f64RecompensaPorEpisodio: Vec<f64>,
+}
+
+/// The result of one environment step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransicionEntorno {
+    /// The state reached.
+    pub sEstadoSiguiente: &'static str,
+    /// The reward earned for the step, R(s) of the state that was left.
+    pub f64Recompensa: f64,
+    /// `true` if the state reached is the goal.
+    pub bTerminal: bool,
+}
+
+/// Shared environment description used by every learner.
+#[derive(Debug, Clone)]
+pub struct EntornoAprendizaje {
+    /// Reward of each state.
+    pub hm_s_f64Recompensas: HashMap<&'static str, f64>,
+    /// Transition model (action -> effective direction -> probability).
+    pub hm_s_hm_s_f64Modelo: HashMap<String, HashMap<String, f64>>,
+    /// Extra movement rules.
+    pub entorno: Entorno,
+    /// Potential Φ(s) for potential-based reward shaping (see `recompensa_modelada`). States missing
+    /// from the map have potential 0. `None` learns from the plain rewards.
+    pub opt_hm_s_f64Potencial: Option<HashMap<String, f64>>,
+}
+
+impl Default for EntornoAprendizaje {
+    /// Default rewards, `config::prob_transicion()` and no extra movement rules.
+    fn default() -> Self {
+        EntornoAprendizaje {
+            hm_s_f64Recompensas: obtener_recompensas(),
+            hm_s_hm_s_f64Modelo: modelo_transicion_base(),
+            entorno: Entorno::default(),
+            opt_hm_s_f64Potencial: None,
+        }
+    }
+}
+
+/// Returns the states an episode may start from: every non-goal, non-obstacle state.
+pub fn estados_iniciales_validos() -> Vec<&'static str> {
+    MAPA_ESTADOS
+        .iter()
+        .flatten()
+        .copied()
+        .filter(|sEstado| *sEstado != ESTADO_META && !OBSTACULOS.contains(sEstado))
+        .collect()
+}
+
+/// Creates a Q-table with every action of every non-obstacle, non-goal state set to 0.
+pub fn tabla_q_inicial() -> TablaQ {
+    estados_iniciales_validos()
+        .into_iter()
+        .map(|sEstado| {
+            (
+                sEstado.to_string(),
+                acciones().iter().map(|sAccion| (sAccion.to_string(), 0.0)).collect(),
+            )
+        })
+        .collect()
+}
+
+/// Takes one step in the environment.
+///
+/// The reward is R(s), minus `Entorno::f64CostoChoque` if the move bumped and left the agent in place.
+///
+/// # Arguments
+///
+/// * `ref_entorno` - The environment (rewards, transition model, movement rules).
+/// * `sEstado` - The current state.
+/// * `sAccion` - The intended action.
+/// * `ref_mut_rng` - Random number generator used to sample the effective direction.
+///
+/// # Returns
+///
+/// The `TransicionEntorno` with the next state, the reward and whether the episode ended.
+pub fn paso_entorno(
+    ref_entorno: &EntornoAprendizaje,
+    sEstado: &str,
+    sAccion: &str,
+    ref_mut_rng: &mut StdRng,
+) -> TransicionEntorno {
+    let sDireccion =
+        muestrear_direccion(&ref_entorno.entorno, &ref_entorno.hm_s_hm_s_f64Modelo[sAccion], sAccion, ref_mut_rng);
+
+    // Wind at the origin may override the sampled direction.
+    let sDireccion = direccion_con_viento(&ref_entorno.entorno, sEstado, sDireccion, ref_mut_rng);
+    let sEstadoSiguiente = resolver_destino(&ref_entorno.entorno, sEstado, sDireccion).unwrap();
+    let f64CostoChoque =
+        if paga_choque(sEstado, sDireccion, sEstadoSiguiente) { ref_entorno.entorno.f64CostoChoque } else { 0.0 };
+    TransicionEntorno {
+        sEstadoSiguiente,
+        f64Recompensa: *ref_entorno.hm_s_f64Recompensas.get(sEstado).unwrap_or(&0.0) - f64CostoChoque,
+        bTerminal: sEstadoSiguiente == ESTADO_META,
+    }
+}
+
+/// Samples the direction a move actually takes, before wind: one of `acciones()` drawn from the action's
+/// row of the transition model after `Entorno::eModoFallo` is applied, or `DIRECCION_SIN_MOVIMIENTO` for a
+/// failed action under `ModoFallo::SinMovimiento`.
+///
+/// Candidates are drawn in action order (the failed action last), so a seeded generator gives the same
+/// direction every run.
+///
+/// # Arguments
+///
+/// * `ref_entorno` - The movement rules.
+/// * `ref_hm_s_f64ModeloAccion` - Transition model row of `sAccion` (direction -> probability).
+/// * `sAccion` - The intended action.
+/// * `ref_mut_rng` - The random number generator.
+pub fn muestrear_direccion<'a>(
+    ref_entorno: &Entorno,
+    ref_hm_s_f64ModeloAccion: &HashMap<String, f64>,
+    sAccion: &'a str,
+    ref_mut_rng: &mut impl Rng,
+) -> &'a str {
+    let hm_s_f64ProbAccion = probabilidades_con_fallo(ref_entorno, sAccion, ref_hm_s_f64ModeloAccion);
+
+    let f64Muestra: f64 = ref_mut_rng.gen();
+    let mut f64Acumulada = 0.0;
+    let mut sDireccion = sAccion;
+    for sCandidata in acciones().iter().chain(std::iter::once(&DIRECCION_SIN_MOVIMIENTO)) {
+        if let Some(f64Prob) = hm_s_f64ProbAccion.get(*sCandidata) {
+            f64Acumulada += f64Prob;
+            sDireccion = sCandidata;
+            if f64Muestra < f64Acumulada {
+                break;
+            }
+        }
+    }
+    sDireccion
+}
+
+/// Returns the reward a learner should update with: R(s) plus, if the environment has a potential,
+/// the shaping term F(s, s') = λ·Φ(s') − Φ(s).
+///
+/// The goal's potential is taken as 0, as required for the shaping to leave the optimal policy
+/// unchanged in episodic tasks. Because the terms telescope along any trajectory, only the speed of
+/// learning changes. Per-episode rewards reported by the learners stay unshaped.
+///
+/// # Arguments
+///
+/// * `ref_entorno` - The environment, holding the optional potential.
+/// * `sEstado` - The state the step started from.
+/// * `ref_transicion` - The step taken from `sEstado`.
+/// * `f64Lambda` - The discount factor (gamma).
+pub fn recompensa_modelada(
+    ref_entorno: &EntornoAprendizaje,
+    sEstado: &str,
+    ref_transicion: &TransicionEntorno,
+    f64Lambda: f64,
+) -> f64 {
+    match &ref_entorno.opt_hm_s_f64Potencial {
+        None => ref_transicion.f64Recompensa,
+        Some(hm_s_f64Potencial) => {
+            let f64PotencialSiguiente = if ref_transicion.bTerminal {
+                0.0
+            } else {
+                hm_s_f64Potencial.get(ref_transicion.sEstadoSiguiente).copied().unwrap_or(0.0)
+            };
+            let f64PotencialActual = hm_s_f64Potencial.get(sEstado).copied().unwrap_or(0.0);
+            ref_transicion.f64Recompensa + f64Lambda * f64PotencialSiguiente - f64PotencialActual
+        }
+    }
+}
+
+/// Returns the potential Φ(s) = −(BFS distance from s to the goal), a shaping potential that rewards
+/// every step towards the goal. States that cannot reach the goal are left out (potential 0).
+pub fn potencial_distancia_meta() -> HashMap<String, f64> {
+    distancias_a_meta()
+        .into_iter()
+        .map(|(sEstado, uiDistancia)| (sEstado.to_string(), -(uiDistancia as f64)))
+        .collect()
+}
+
+/// Returns the action with the highest Q-value in a state (ties go to the first action in `acciones()`).
+pub fn accion_greedy(ref_tabla_q: &TablaQ, sEstado: &str) -> String {
+    let hm_s_f64Q = &ref_tabla_q[sEstado];
+    let mut sMejorAccion = acciones()[0];
+    let mut f64MejorValor = f64::NEG_INFINITY;
+    for sAccion in acciones().iter() {
+        if hm_s_f64Q[*sAccion] > f64MejorValor {
+            f64MejorValor = hm_s_f64Q[*sAccion];
+            sMejorAccion = sAccion;
+        }
+    }
+    sMejorAccion.to_string()
+}
+
+/// Returns every action of a state whose Q-value is within `f64Tolerancia` of the best one,
+/// in `acciones()` order. The greedy action of `accion_greedy` is always the first element.
+pub fn acciones_optimas(ref_tabla_q: &TablaQ, sEstado: &str, f64Tolerancia: f64) -> Vec<String> {
+    let hm_s_f64Q = &ref_tabla_q[sEstado];
+    let f64MejorValor = hm_s_f64Q.values().copied().fold(f64::NEG_INFINITY, f64::max);
+    acciones()
+        .iter()
+        .filter(|sAccion| matches!(hm_s_f64Q.get(**sAccion), Some(f64Q) if f64MejorValor - f64Q <= f64Tolerancia))
+        .map(|sAccion| sAccion.to_string())
+        .collect()
+}
+
+/// How the exploration probability of the epsilon-greedy learners changes over episodes.
+///
+/// A plain `f64` converts to `Constante`, so the learners still accept a fixed epsilon.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EsquemaEpsilon {
+    /// The same epsilon in every episode.
+    Constante(f64),
+    /// Goes linearly from `f64Inicio` (episode 0) to `f64Fin` (episode `uiEpisodios`) and stays at `f64Fin` after.
+    DecaimientoLineal { f64Inicio: f64, f64Fin: f64, uiEpisodios: usize },
+    /// `f64Inicio · f64Tasa^episodio`, with `f64Tasa` in (0, 1].
+    DecaimientoExponencial { f64Inicio: f64, f64Tasa: f64 },
+}
+
+impl From<f64> for EsquemaEpsilon {
+    fn from(f64Epsilon: f64) -> Self {
+        EsquemaEpsilon::Constante(f64Epsilon)
+    }
+}
+
+impl EsquemaEpsilon {
+    /// Returns the exploration probability for episode `uiEpisodio` (counting from 0).
+    pub fn epsilon(&self, uiEpisodio: usize) -> f64 {
+        match *self {
+            EsquemaEpsilon::Constante(f64Epsilon) => f64Epsilon,
+            EsquemaEpsilon::DecaimientoLineal { f64Inicio, f64Fin, uiEpisodios } => {
+                let f64Fraccion = if uiEpisodios == 0 {
+                    1.0
+                } else {
+                    (uiEpisodio as f64 / uiEpisodios as f64).min(1.0)
+                };
+                f64Inicio + (f64Fin - f64Inicio) * f64Fraccion
+            }
+            EsquemaEpsilon::DecaimientoExponencial { f64Inicio, f64Tasa } => {
+                f64Inicio * f64Tasa.powi(uiEpisodio.min(i32::MAX as usize) as i32)
+            }
+        }
+    }
+}
+
+/// Picks a random action with probability `f64EpsilonGreedy`, otherwise the greedy one.
+pub fn accion_epsilon_greedy(
+    ref_tabla_q: &TablaQ,
+    sEstado: &str,
+    f64EpsilonGreedy: f64,
+    ref_mut_rng: &mut StdRng,
+) -> String {
+    if ref_mut_rng.gen::<f64>() < f64EpsilonGreedy {
+        acciones().choose(ref_mut_rng).unwrap().to_string()
+    } else {
+        accion_greedy(ref_tabla_q, sEstado)
+    }
+}
+
+/// Temperatures at or below this are treated as 0 by `accion_softmax` (always greedy).
+pub const F64_TEMPERATURA_MINIMA: f64 = 1e-9;
+
+/// Returns the Boltzmann (softmax) distribution P(a) ∝ exp(Q(s,a) / `f64Temperatura`) over the actions of a state.
+///
+/// At or below `F64_TEMPERATURA_MINIMA` the whole probability goes to `accion_greedy`.
+///
+/// # Returns
+///
+/// `(action, probability)` pairs in `acciones()` order, summing to 1.
+pub fn distribucion_softmax(ref_tabla_q: &TablaQ, sEstado: &str, f64Temperatura: f64) -> Vec<(&'static str, f64)> {
+    if f64Temperatura <= F64_TEMPERATURA_MINIMA {
+        let sGreedy = accion_greedy(ref_tabla_q, sEstado);
+        return acciones()
+            .into_iter()
+            .map(|sAccion| (sAccion, if sAccion == sGreedy { 1.0 } else { 0.0 }))
+            .collect();
+    }
+    let hm_s_f64Q = &ref_tabla_q[sEstado];
+    // Subtract the maximum before exponentiating so low temperatures do not overflow.
+    let f64MejorValor = hm_s_f64Q.values().copied().fold(f64::NEG_INFINITY, f64::max);
+    let vec_tpl_s_f64Pesos: Vec<(&'static str, f64)> = acciones()
+        .into_iter()
+        .map(|sAccion| (sAccion, ((hm_s_f64Q[sAccion] - f64MejorValor) / f64Temperatura).exp()))
+        .collect();
+    let f64Total: f64 = vec_tpl_s_f64Pesos.iter().map(|(_, f64Peso)| f64Peso).sum();
+    vec_tpl_s_f64Pesos.into_iter().map(|(sAccion, f64Peso)| (sAccion, f64Peso / f64Total)).collect()
+}
+
+/// Samples an action from the Boltzmann (softmax) distribution P(a) ∝ exp(Q(s,a) / `f64Temperatura`).
+///
+/// High temperatures approach a uniform choice; as the temperature goes to 0 the distribution
+/// concentrates on the best action, and at or below `F64_TEMPERATURA_MINIMA` this returns
+/// `accion_greedy` without drawing from `ref_mut_rng`.
+pub fn accion_softmax(ref_tabla_q: &TablaQ, sEstado: &str, f64Temperatura: f64, ref_mut_rng: &mut StdRng) -> String {
+    if f64Temperatura <= F64_TEMPERATURA_MINIMA {
+        return accion_greedy(ref_tabla_q, sEstado);
+    }
+    distribucion_softmax(ref_tabla_q, sEstado, f64Temperatura)
+        .choose_weighted(ref_mut_rng, |(_, f64Prob)| *f64Prob)
+        .unwrap()
+        .0
+        .to_string()
+}
+
+/// Returns max_a Q(s', a), or R(goal) if `ref_transicion` ended the episode.
+pub fn valor_siguiente(ref_entorno: &EntornoAprendizaje, ref_tabla_q: &TablaQ, ref_transicion: &TransicionEntorno) -> f64 {
+    if ref_transicion.bTerminal {
+        ref_entorno.hm_s_f64Recompensas[ESTADO_META]
+    } else {
+        let sAccion = accion_greedy(ref_tabla_q, ref_transicion.sEstadoSiguiente);
+        ref_tabla_q[ref_transicion.sEstadoSiguiente][&sAccion]
+    }
+}
+
+/// Returns Σ_a π(a|s')·Q(s', a) for the epsilon-greedy policy π over the current Q-table,
+/// or R(goal) if `ref_transicion` ended the episode.
+///
+/// π gives every action ε/|A| and the greedy action (same tie-break as `accion_greedy`) an extra 1 − ε.
+pub fn valor_esperado_epsilon_greedy(
+    ref_entorno: &EntornoAprendizaje,
+    ref_tabla_q: &TablaQ,
+    ref_transicion: &TransicionEntorno,
+    f64EpsilonGreedy: f64,
+) -> f64 {
+    if ref_transicion.bTerminal {
+        return ref_entorno.hm_s_f64Recompensas[ESTADO_META];
+    }
+    let hm_s_f64Q = &ref_tabla_q[ref_transicion.sEstadoSiguiente];
+    let f64Uniforme = f64EpsilonGreedy / acciones().len() as f64;
+    let sAccionGreedy = accion_greedy(ref_tabla_q, ref_transicion.sEstadoSiguiente);
+    acciones()
+        .iter()
+        .map(|sAccion| {
+            let f64Prob = if *sAccion == sAccionGreedy { f64Uniforme + 1.0 - f64EpsilonGreedy } else { f64Uniforme };
+            f64Prob * hm_s_f64Q[*sAccion]
+        })
+        .sum()
+}
+
+/// Returns the element-wise average of two Q-tables with the same states and actions.
+pub fn promedio_tablas_q(ref_tabla_a: &TablaQ, ref_tabla_b: &TablaQ) -> TablaQ {
+    ref_tabla_a
+        .iter()
+        .map(|(sEstado, hm_s_f64QA)| (sEstado.clone(), promedio_filas_q(hm_s_f64QA, &ref_tabla_b[sEstado])))
+        .collect()
+}
+
+/// Returns the element-wise average of one state's Q-values (action -> value) in two tables.
+fn promedio_filas_q(
+    ref_hm_s_f64QA: &HashMap<String, f64>,
+    ref_hm_s_f64QB: &HashMap<String, f64>,
+) -> HashMap<String, f64> {
+    ref_hm_s_f64QA
+        .iter()
+        .map(|(sAccion, f64QA)| (sAccion.clone(), (f64QA + ref_hm_s_f64QB[sAccion]) / 2.0))
+        .collect()
+}
+
+/// Returns, per state, the gap between the best and the second-best action value.
+///
+/// A small gap marks a state where a slight change of the model could flip the chosen action.
+/// A state with a single action has an infinite gap (the choice cannot flip); states without
+/// actions are left out.
+pub fn brecha_q(ref_tabla_q: &TablaQ) -> HashMap<String, f64> {
+    ref_tabla_q
+        .iter()
+        .filter(|(_, hm_s_f64Q)| !hm_s_f64Q.is_empty())
+        .map(|(sEstado, hm_s_f64Q)| {
+            let mut vec_f64Valores: Vec<f64> = hm_s_f64Q.values().copied().collect();
+            vec_f64Valores.sort_by(|f64A, f64B| f64B.partial_cmp(f64A).unwrap());
+            let f64Brecha = match vec_f64Valores.get(1) {
+                Some(f64Segundo) => vec_f64Valores[0] - f64Segundo,
+                None => f64::INFINITY,
+            };
+            (sEstado.clone(), f64Brecha)
+        })
+        .collect()
+}
+
+/// Builds the greedy policy (State -> Action) of a Q-table.
+pub fn politica_greedy(ref_tabla_q: &TablaQ) -> HashMap<String, String> {
+    ref_tabla_q
+        .keys()
+        .map(|sEstado| (sEstado.clone(), accion_greedy(ref_tabla_q, sEstado)))
+        .collect()
+}
+
+/// Builds the set-valued optimal policy of a Q-table: per state, every action whose Q-value is
+/// within `f64Tolerancia` of the state's maximum (see `acciones_optimas`).
+///
+/// With `f64Tolerancia = 0.0` only exact ties are kept, so most states get a single action; a larger
+/// tolerance also admits near-optimal actions and grows the sets, up to all four actions once it
+/// exceeds the state's spread of Q-values. States without actions are left out.
+pub fn politica_multi_optima(ref_tabla_q: &TablaQ, f64Tolerancia: f64) -> HashMap<String, Vec<String>> {
+    ref_tabla_q
+        .iter()
+        .filter(|(_, hm_s_f64Q)| !hm_s_f64Q.is_empty())
+        .map(|(sEstado, _)| (sEstado.clone(), acciones_optimas(ref_tabla_q, sEstado, f64Tolerancia)))
+        .collect()
+}
+
+/// Dyna-Q: Q-learning from real steps plus planning updates replayed from a learned model.
+///
+/// After each real step, Q(s,a) is updated towards r + λ·max Q(s',·), the observed outcome is stored
+/// as the model's prediction for (s,a), and `uiPlanificacion` previously seen (s,a) pairs are drawn at
+/// random and updated the same way from the model. With `uiPlanificacion = 0` this is plain Q-learning.
+///
+/// # Arguments
+///
+/// * `f64Lambda` - The discount factor (gamma).
+/// * `f64Alpha` - The learning rate.
+/// * `esquemaEpsilon` - Exploration probability of the epsilon-greedy behavior policy per episode (see
+///   `EsquemaEpsilon`); a plain `f64` is a constant one.
+/// * `uiPlanificacion` - Number of planning updates after each real step.
+/// * `uiEpisodios` - Number of episodes.
+/// * `uiMaxPasos` - Step cap per episode.
+/// * `u64Semilla` - Seed for the random number generator.
+/// * `opt_hm_s_f64Potencial` - Optional shaping potential Φ (see `recompensa_modelada`).
+///
+/// # Returns
+///
+/// A `ResultadoAprendizaje`; `vec_uiPasosPorEpisodio` is the steps-to-goal learning curve.
+#[allow(clippy::too_many_arguments)]
+pub fn dyna_q(
+    f64Lambda: f64,
+    f64Alpha: f64,
+    esquemaEpsilon: impl Into<EsquemaEpsilon>,
+    uiPlanificacion: usize,
+    uiEpisodios: usize,
+    uiMaxPasos: usize,
+    u64Semilla: u64,
+    opt_hm_s_f64Potencial: Option<HashMap<String, f64>>,
+) -> ResultadoAprendizaje {
+    let esquemaEpsilon: EsquemaEpsilon = esquemaEpsilon.into();
+    let entorno = EntornoAprendizaje {
+        opt_hm_s_f64Potencial,
+        ..Default::default()
+    };
+    let mut rng = StdRng::seed_from_u64(u64Semilla);
+    let vec_sIniciales = estados_iniciales_validos();
+
+    let mut tabla_q = tabla_q_inicial();
+    // Learned model: last outcome observed for each (state, action), plus the list of seen pairs for sampling.
+    let mut hm_tpl_s_sModelo: HashMap<(String, String), TransicionEntorno> = HashMap::new();
+    let mut vec_tpl_s_sVistos: Vec<(String, String)> = Vec::new();
+
+    let mut vec_uiPasosPorEpisodio = Vec::with_capacity(uiEpisodios);
+    let mut vec_f64RecompensaPorEpisodio = Vec::with_capacity(uiEpisodios);
+
+    for uiEpisodio in 0..uiEpisodios {
+        let f64EpsilonGreedy = esquemaEpsilon.epsilon(uiEpisodio);
+        let mut sEstado: &'static str = vec_sIniciales.choose(&mut rng).unwrap();
+        let mut uiPasos = 0;
+        let mut f64RecompensaEpisodio = 0.0;
+
+        while uiPasos < uiMaxPasos {
+            let sAccion = accion_epsilon_greedy(&tabla_q, sEstado, f64EpsilonGreedy, &mut rng);
+            let transicion = paso_entorno(&entorno, sEstado, &sAccion, &mut rng);
+            uiPasos += 1;
+            f64RecompensaEpisodio += transicion.f64Recompensa;
+
+            // Direct reinforcement learning from the real step
+            let f64Objetivo = recompensa_modelada(&entorno, sEstado, &transicion, f64Lambda)
+                + f64Lambda * valor_siguiente(&entorno, &tabla_q, &transicion);
+            let f64Q = tabla_q.get_mut(sEstado).unwrap().get_mut(&sAccion).unwrap();
+            *f64Q += f64Alpha * (f64Objetivo - *f64Q);
+
+            // Model learning
+            let tpl_s_sClave = (sEstado.to_string(), sAccion);
+            if hm_tpl_s_sModelo.insert(tpl_s_sClave.clone(), transicion).is_none() {
+                vec_tpl_s_sVistos.push(tpl_s_sClave);
+            }
+
+            // Planning from the learned model
+            for _ in 0..uiPlanificacion {
+                let (sEstadoPlan, sAccionPlan) = vec_tpl_s_sVistos.choose(&mut rng).unwrap();
+                let transicionPlan = hm_tpl_s_sModelo[&(sEstadoPlan.clone(), sAccionPlan.clone())];
+                let f64ObjetivoPlan = recompensa_modelada(&entorno, sEstadoPlan, &transicionPlan, f64Lambda)
+                    + f64Lambda * valor_siguiente(&entorno, &tabla_q, &transicionPlan);
+                let f64QPlan = tabla_q.get_mut(sEstadoPlan).unwrap().get_mut(sAccionPlan).unwrap();
+                *f64QPlan += f64Alpha * (f64ObjetivoPlan - *f64QPlan);
+            }
+
+            if transicion.bTerminal {
+                f64RecompensaEpisodio += entorno.hm_s_f64Recompensas[ESTADO_META];
+                break;
+            }
+            sEstado = transicion.sEstadoSiguiente;
+        }
+
+        vec_uiPasosPorEpisodio.push(uiPasos);
+        vec_f64RecompensaPorEpisodio.push(f64RecompensaEpisodio);
+    }
+
+    ResultadoAprendizaje {
+        hm_s_sPolitica: politica_greedy(&tabla_q),
+        tabla_q,
+        vec_uiPasosPorEpisodio,
+        vec_f64RecompensaPorEpisodio,
+    }
+}
+
+/// n-step Q-learning: updates Q(s_τ, a_τ) towards the n-step return
+/// r_τ + λ·r_τ+1 + … + λ^(n-1)·r_τ+n-1 + λ^n·max Q(s_τ+n, ·).
+///
+/// Returns shorter than n near the end of an episode use R(goal) as the terminal value, or bootstrap
+/// from the last state if the episode was cut off by `uiMaxPasos`. With `uiN = 1` this is one-step Q-learning.
+///
+/// # Arguments
+///
+/// * `uiN` - Number of rewards in each return (at least 1).
+/// * `f64Lambda` - The discount factor (gamma).
+/// * `f64Alpha` - The learning rate.
+/// * `esquemaEpsilon` - Exploration probability of the epsilon-greedy behavior policy per episode (see
+///   `EsquemaEpsilon`); a plain `f64` is a constant one.
+/// * `uiEpisodios` - Number of episodes.
+/// * `uiMaxPasos` - Step cap per episode.
+/// * `u64Semilla` - Seed for the random number generator.
+/// * `opt_hm_s_f64Potencial` - Optional shaping potential Φ (see `recompensa_modelada`).
+///
+/// # Returns
+///
+/// A `ResultadoAprendizaje` with the Q-table, greedy policy and per-episode learning curve.
+#[allow(clippy::too_many_arguments)]
+pub fn q_learning_n_step(
+    uiN: usize,
+    f64Lambda: f64,
+    f64Alpha: f64,
+    esquemaEpsilon: impl Into<EsquemaEpsilon>,
+    uiEpisodios: usize,
+    uiMaxPasos: usize,
+    u64Semilla: u64,
+    opt_hm_s_f64Potencial: Option<HashMap<String, f64>>,
+) -> ResultadoAprendizaje {
+    let esquemaEpsilon: EsquemaEpsilon = esquemaEpsilon.into();
+    let uiN = uiN.max(1);
+    let entorno = EntornoAprendizaje {
+        opt_hm_s_f64Potencial,
+        ..Default::default()
+    };
+    let mut rng = StdRng::seed_from_u64(u64Semilla);
+    let vec_sIniciales = estados_iniciales_validos();
+    let mut tabla_q = tabla_q_inicial();
+
+    let mut vec_uiPasosPorEpisodio = Vec::with_capacity(uiEpisodios);
+    let mut vec_f64RecompensaPorEpisodio = Vec::with_capacity(uiEpisodios);
+
+    for uiEpisodio in 0..uiEpisodios {
+        let f64EpsilonGreedy = esquemaEpsilon.epsilon(uiEpisodio);
+        // s_t, a_t and r_t (reward of the step from s_t) for the whole episode
+        let mut vec_sEstados: Vec<&'static str> = vec![vec_sIniciales.choose(&mut rng).unwrap()];
+        let mut vec_sAcciones: Vec<String> = Vec::new();
+        let mut vec_f64Recompensas: Vec<f64> = Vec::new();
+        // Rewards used in the returns (shaped if there is a potential)
+        let mut vec_f64RecompensasModeladas: Vec<f64> = Vec::new();
+        let mut uiFin = usize::MAX; // T: index of the last state, known once the episode ends
+        let mut bLlegoMeta = false;
+        let mut uiT = 0;
+
+        loop {
+            if uiT < uiFin {
+                let sEstado = vec_sEstados[uiT];
+                let sAccion = accion_epsilon_greedy(&tabla_q, sEstado, f64EpsilonGreedy, &mut rng);
+                let transicion = paso_entorno(&entorno, sEstado, &sAccion, &mut rng);
+                vec_sAcciones.push(sAccion);
+                vec_f64Recompensas.push(transicion.f64Recompensa);
+                vec_f64RecompensasModeladas.push(recompensa_modelada(&entorno, sEstado, &transicion, f64Lambda));
+                vec_sEstados.push(transicion.sEstadoSiguiente);
+                if transicion.bTerminal {
+                    uiFin = uiT + 1;
+                    bLlegoMeta = true;
+                } else if uiT + 1 >= uiMaxPasos {
+                    uiFin = uiT + 1;
+                }
+            }
+
+            // τ: the time whose estimate is updated now
+            if uiT + 1 >= uiN {
+                let uiTau = uiT + 1 - uiN;
+                let uiHasta = (uiTau + uiN).min(uiFin);
+                let mut f64Retorno: f64 = (uiTau..uiHasta)
+                    .map(|uiI| f64Lambda.powi((uiI - uiTau) as i32) * vec_f64RecompensasModeladas[uiI])
+                    .sum();
+                let f64DescuentoCola = f64Lambda.powi((uiHasta - uiTau) as i32);
+                if uiHasta == uiFin && bLlegoMeta {
+                    f64Retorno += f64DescuentoCola * entorno.hm_s_f64Recompensas[ESTADO_META];
+                } else {
+                    let sEstadoCola = vec_sEstados[uiHasta];
+                    f64Retorno += f64DescuentoCola * tabla_q[sEstadoCola][&accion_greedy(&tabla_q, sEstadoCola)];
+                }
+
+                let f64Q = tabla_q
+                    .get_mut(vec_sEstados[uiTau])
+                    .unwrap()
+                    .get_mut(&vec_sAcciones[uiTau])
+                    .unwrap();
+                *f64Q += f64Alpha * (f64Retorno - *f64Q);
+
+                if uiTau + 1 == uiFin {
+                    break;
+                }
+            }
+            uiT += 1;
+        }
+
+        let mut f64RecompensaEpisodio: f64 = vec_f64Recompensas.iter().sum();
+        if bLlegoMeta {
+            f64RecompensaEpisodio += entorno.hm_s_f64Recompensas[ESTADO_META];
+        }
+        vec_uiPasosPorEpisodio.push(uiFin);
+        vec_f64RecompensaPorEpisodio.push(f64RecompensaEpisodio);
+    }
+
+    ResultadoAprendizaje {
+        hm_s_sPolitica: politica_greedy(&tabla_q),
+        tabla_q,
+        vec_uiPasosPorEpisodio,
+        vec_f64RecompensaPorEpisodio,
+    }
+}
+
+/// SARSA(λ) with replacing eligibility traces.
+///
+/// Every step computes the TD error δ = r + λ·Q(s',a') − Q(s,a) (with R(goal) as the terminal value),
+/// sets e(s,a) = 1, updates every pair by Q += α·δ·e and then decays all traces by λ·`f64TrazaLambda`.
+/// With `f64TrazaLambda = 0` this is one-step SARSA; values near 1 propagate reward much further back.
+///
+/// # Arguments
+///
+/// * `f64TrazaLambda` - Trace decay parameter in [0, 1].
+/// * `f64Lambda` - The discount factor (gamma).
+/// * `f64Alpha` - The learning rate.
+/// * `esquemaEpsilon` - Exploration probability of the epsilon-greedy policy per episode (see
+///   `EsquemaEpsilon`); a plain `f64` is a constant one.
+/// * `uiEpisodios` - Number of episodes.
+/// * `uiMaxPasos` - Step cap per episode.
+/// * `u64Semilla` - Seed for the random number generator.
+/// * `opt_hm_s_f64Potencial` - Optional shaping potential Φ (see `recompensa_modelada`).
+///
+/// # Returns
+///
+/// A `ResultadoAprendizaje` with the Q-table, greedy policy and per-episode learning curve.
+#[allow(clippy::too_many_arguments)]
+pub fn sarsa_lambda(
+    f64TrazaLambda: f64,
+    f64Lambda: f64,
+    f64Alpha: f64,
+    esquemaEpsilon: impl Into<EsquemaEpsilon>,
+    uiEpisodios: usize,
+    uiMaxPasos: usize,
+    u64Semilla: u64,
+    opt_hm_s_f64Potencial: Option<HashMap<String, f64>>,
+) -> ResultadoAprendizaje {
+    let esquemaEpsilon: EsquemaEpsilon = esquemaEpsilon.into();
+    let entorno = EntornoAprendizaje {
+        opt_hm_s_f64Potencial,
+        ..Default::default()
+    };
+    let mut rng = StdRng::seed_from_u64(u64Semilla);
+    let vec_sIniciales = estados_iniciales_validos();
+    let mut tabla_q = tabla_q_inicial();
+
+    let mut vec_uiPasosPorEpisodio = Vec::with_capacity(uiEpisodios);
+    let mut vec_f64RecompensaPorEpisodio = Vec::with_capacity(uiEpisodios);
+
+    for uiEpisodio in 0..uiEpisodios {
+        let f64EpsilonGreedy = esquemaEpsilon.epsilon(uiEpisodio);
+        // Eligibility traces e(s,a), reset every episode
+        let mut tabla_e = tabla_q_inicial();
+        let mut sEstado: &'static str = vec_sIniciales.choose(&mut rng).unwrap();
+        let mut sAccion = accion_epsilon_greedy(&tabla_q, sEstado, f64EpsilonGreedy, &mut rng);
+        let mut uiPasos = 0;
+        let mut f64RecompensaEpisodio = 0.0;
+
+        while uiPasos < uiMaxPasos {
+            let transicion = paso_entorno(&entorno, sEstado, &sAccion, &mut rng);
+            uiPasos += 1;
+            f64RecompensaEpisodio += transicion.f64Recompensa;
+
+            let opt_sAccionSiguiente = if transicion.bTerminal {
+                None
+            } else {
+                Some(accion_epsilon_greedy(&tabla_q, transicion.sEstadoSiguiente, f64EpsilonGreedy, &mut rng))
+            };
+            let f64ValorSiguiente = match &opt_sAccionSiguiente {
+                Some(sAccionSiguiente) => tabla_q[transicion.sEstadoSiguiente][sAccionSiguiente],
+                None => entorno.hm_s_f64Recompensas[ESTADO_META],
+            };
+            let f64Delta = recompensa_modelada(&entorno, sEstado, &transicion, f64Lambda) + f64Lambda * f64ValorSiguiente
+                - tabla_q[sEstado][&sAccion];
+
+            *tabla_e.get_mut(sEstado).unwrap().get_mut(&sAccion).unwrap() = 1.0;
+            for (sEstadoTraza, hm_s_f64Trazas) in tabla_e.iter_mut() {
+                let hm_s_f64Q = tabla_q.get_mut(sEstadoTraza).unwrap();
+                for (sAccionTraza, f64Traza) in hm_s_f64Trazas.iter_mut() {
+                    if *f64Traza != 0.0 {
+                        *hm_s_f64Q.get_mut(sAccionTraza).unwrap() += f64Alpha * f64Delta * *f64Traza;
+                        *f64Traza *= f64Lambda * f64TrazaLambda;
+                    }
+                }
+            }
+
+            match opt_sAccionSiguiente {
+                Some(sAccionSiguiente) => {
+                    sEstado = transicion.sEstadoSiguiente;
+                    sAccion = sAccionSiguiente;
+                }
+                None => {
+                    f64RecompensaEpisodio += entorno.hm_s_f64Recompensas[ESTADO_META];
+                    break;
+                }
+            }
+        }
+
+        vec_uiPasosPorEpisodio.push(uiPasos);
+        vec_f64RecompensaPorEpisodio.push(f64RecompensaEpisodio);
+    }
+
+    ResultadoAprendizaje {
+        hm_s_sPolitica: politica_greedy(&tabla_q),
+        tabla_q,
+        vec_uiPasosPorEpisodio,
+        vec_f64RecompensaPorEpisodio,
+    }
+}
+
+/// Expected SARSA: updates Q(s,a) towards r + λ·Σ_a' π(a'|s')·Q(s',a'), where π is the
+/// epsilon-greedy behavior policy over the current Q-table.
+///
+/// Averaging over the next action instead of sampling it (SARSA) or maximizing over it (Q-learning)
+/// removes the variance due to the next action choice, so learning curves are usually smoother.
+///
+/// # Arguments
+///
+/// * `f64Lambda` - The discount factor (gamma).
+/// * `f64Alpha` - The learning rate.
+/// * `esquemaEpsilon` - Exploration probability of the epsilon-greedy policy per episode (see
+///   `EsquemaEpsilon`); a plain `f64` is a constant one.
+/// * `uiEpisodios` - Number of episodes.
+/// * `uiMaxPasos` - Step cap per episode.
+/// * `u64Semilla` - Seed for the random number generator.
+/// * `opt_hm_s_f64Potencial` - Optional shaping potential Φ (see `recompensa_modelada`).
+///
+/// # Returns
+///
+/// A `ResultadoAprendizaje` with the Q-table, greedy policy and per-episode learning curve.
+pub fn expected_sarsa(
+    f64Lambda: f64,
+    f64Alpha: f64,
+    esquemaEpsilon: impl Into<EsquemaEpsilon>,
+    uiEpisodios: usize,
+    uiMaxPasos: usize,
+    u64Semilla: u64,
+    opt_hm_s_f64Potencial: Option<HashMap<String, f64>>,
+) -> ResultadoAprendizaje {
+    let esquemaEpsilon: EsquemaEpsilon = esquemaEpsilon.into();
+    let entorno = EntornoAprendizaje {
+        opt_hm_s_f64Potencial,
+        ..Default::default()
+    };
+    let mut rng = StdRng::seed_from_u64(u64Semilla);
+    let vec_sIniciales = estados_iniciales_validos();
+    let mut tabla_q = tabla_q_inicial();
+
+    let mut vec_uiPasosPorEpisodio = Vec::with_capacity(uiEpisodios);
+    let mut vec_f64RecompensaPorEpisodio = Vec::with_capacity(uiEpisodios);
+
+    for uiEpisodio in 0..uiEpisodios {
+        let f64EpsilonGreedy = esquemaEpsilon.epsilon(uiEpisodio);
+        let mut sEstado: &'static str = vec_sIniciales.choose(&mut rng).unwrap();
+        let mut uiPasos = 0;
+        let mut f64RecompensaEpisodio = 0.0;
+
+        while uiPasos < uiMaxPasos {
+            let sAccion = accion_epsilon_greedy(&tabla_q, sEstado, f64EpsilonGreedy, &mut rng);
+            let transicion = paso_entorno(&entorno, sEstado, &sAccion, &mut rng);
+            uiPasos += 1;
+            f64RecompensaEpisodio += transicion.f64Recompensa;
+
+            let f64Objetivo = recompensa_modelada(&entorno, sEstado, &transicion, f64Lambda)
+                + f64Lambda * valor_esperado_epsilon_greedy(&entorno, &tabla_q, &transicion, f64EpsilonGreedy);
+            let f64Q = tabla_q.get_mut(sEstado).unwrap().get_mut(&sAccion).unwrap();
+            *f64Q += f64Alpha * (f64Objetivo - *f64Q);
+
+            if transicion.bTerminal {
+                f64RecompensaEpisodio += entorno.hm_s_f64Recompensas[ESTADO_META];
+                break;
+            }
+            sEstado = transicion.sEstadoSiguiente;
+        }
+
+        vec_uiPasosPorEpisodio.push(uiPasos);
+        vec_f64RecompensaPorEpisodio.push(f64RecompensaEpisodio);
+    }
+
+    ResultadoAprendizaje {
+        hm_s_sPolitica: politica_greedy(&tabla_q),
+        tabla_q,
+        vec_uiPasosPorEpisodio,
+        vec_f64RecompensaPorEpisodio,
+    }
+}
+
+/// Double Q-learning: two Q-tables, where each update picks one at random, selects the next
+/// action greedily with it and evaluates that action with the other one:
+/// Q_A(s,a) += α·(r + λ·Q_B(s', argmax Q_A(s',·)) − Q_A(s,a)).
+///
+/// Decoupling selection from evaluation removes most of the maximization bias of Q-learning, whose
+/// `max` over noisy estimates tends to inflate values next to the danger cells. Actions are chosen
+/// epsilon-greedily with respect to the average of both tables (equivalent to their sum).
+///
+/// # Arguments
+///
+/// * `f64Lambda` - The discount factor (gamma).
+/// * `f64Alpha` - The learning rate.
+/// * `esquemaEpsilon` - Exploration probability of the epsilon-greedy behavior policy per episode (see
+///   `EsquemaEpsilon`); a plain `f64` is a constant one.
+/// * `uiEpisodios` - Number of episodes.
+/// * `uiMaxPasos` - Step cap per episode.
+/// * `u64Semilla` - Seed for the random number generator.
+/// * `opt_hm_s_f64Potencial` - Optional shaping potential Φ (see `recompensa_modelada`).
+///
+/// # Returns
+///
+/// A `ResultadoAprendizaje` whose `tabla_q` is the average of the two tables, with its greedy policy
+/// and the per-episode learning curve.
+pub fn double_q_learning(
+    f64Lambda: f64,
+    f64Alpha: f64,
+    esquemaEpsilon: impl Into<EsquemaEpsilon>,
+    uiEpisodios: usize,
+    uiMaxPasos: usize,
+    u64Semilla: u64,
+    opt_hm_s_f64Potencial: Option<HashMap<String, f64>>,
+) -> ResultadoAprendizaje {
+    let esquemaEpsilon: EsquemaEpsilon = esquemaEpsilon.into();
+    let entorno = EntornoAprendizaje {
+        opt_hm_s_f64Potencial,
+        ..Default::default()
+    };
+    let mut rng = StdRng::seed_from_u64(u64Semilla);
+    let vec_sIniciales = estados_iniciales_validos();
+    let mut tabla_q_a = tabla_q_inicial();
+    let mut tabla_q_b = tabla_q_inicial();
+
+    let mut vec_uiPasosPorEpisodio = Vec::with_capacity(uiEpisodios);
+    let mut vec_f64RecompensaPorEpisodio = Vec::with_capacity(uiEpisodios);
+
+    for uiEpisodio in 0..uiEpisodios {
+        let f64EpsilonGreedy = esquemaEpsilon.epsilon(uiEpisodio);
+        let mut sEstado: &'static str = vec_sIniciales.choose(&mut rng).unwrap();
+        let mut uiPasos = 0;
+        let mut f64RecompensaEpisodio = 0.0;
+
+        while uiPasos < uiMaxPasos {
+            // Only the current state's row of the averaged table is needed to pick the action
+            let tabla_q_promedio =
+                TablaQ::from([(sEstado.to_string(), promedio_filas_q(&tabla_q_a[sEstado], &tabla_q_b[sEstado]))]);
+            let sAccion = accion_epsilon_greedy(&tabla_q_promedio, sEstado, f64EpsilonGreedy, &mut rng);
+            let transicion = paso_entorno(&entorno, sEstado, &sAccion, &mut rng);
+            uiPasos += 1;
+            f64RecompensaEpisodio += transicion.f64Recompensa;
+
+            // Update one table, evaluating its own greedy action with the other
+            let (tabla_q_actualizar, tabla_q_evaluar) = if rng.gen::<bool>() {
+                (&mut tabla_q_a, &tabla_q_b)
+            } else {
+                (&mut tabla_q_b, &tabla_q_a)
+            };
+            let f64ValorSiguiente = if transicion.bTerminal {
+                entorno.hm_s_f64Recompensas[ESTADO_META]
+            } else {
+                let sAccionSiguiente = accion_greedy(tabla_q_actualizar, transicion.sEstadoSiguiente);
+                tabla_q_evaluar[transicion.sEstadoSiguiente][&sAccionSiguiente]
+            };
+            let f64Objetivo = recompensa_modelada(&entorno, sEstado, &transicion, f64Lambda) + f64Lambda * f64ValorSiguiente;
+            let f64Q = tabla_q_actualizar.get_mut(sEstado).unwrap().get_mut(&sAccion).unwrap();
+            *f64Q += f64Alpha * (f64Objetivo - *f64Q);
+
+            if transicion.bTerminal {
+                f64RecompensaEpisodio += entorno.hm_s_f64Recompensas[ESTADO_META];
+                break;
+            }
+            sEstado = transicion.sEstadoSiguiente;
+        }
+
+        vec_uiPasosPorEpisodio.push(uiPasos);
+        vec_f64RecompensaPorEpisodio.push(f64RecompensaEpisodio);
+    }
+
+    let tabla_q = promedio_tablas_q(&tabla_q_a, &tabla_q_b);
+    ResultadoAprendizaje {
+        hm_s_sPolitica: politica_greedy(&tabla_q),
+        tabla_q,
+        vec_uiPasosPorEpisodio,
+        vec_f64RecompensaPorEpisodio,
+    }
+}
+
+/// First-visit Monte Carlo evaluation of a fixed policy: averages, for each state, the discounted
+/// return that follows its first visit in each episode.
+///
+/// Each episode starts from a random state of `estados_iniciales_validos` (so every state keeps being
+/// sampled) and follows the policy with the model's noise (`paso_entorno`) until the goal, a state
+/// without a policy entry or `uiMaxPasos` steps. Returns are computed without bootstrapping:
+/// G_t = r_t + λ·G_{t+1}, with G = R(goal) on reaching the goal (as the goal's fixed value in
+/// `mdp_model::evaluar_politica`) and 0 after a truncated episode. As episodes grow the estimates
+/// converge to `evaluar_politica`'s values, up to the truncation error λ^uiMaxPasos.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_sPolitica` - The policy (State -> Action) to evaluate.
+/// * `uiEpisodios` - Number of episodes.
+/// * `f64Lambda` - The discount factor (gamma).
+/// * `uiMaxPasos` - Step cap per episode.
+/// * `u64Semilla` - Seed for the random number generator.
+///
+/// # Returns
+///
+/// The estimated value of every state visited at least once, plus the goal (worth R(goal)).
+pub fn monte_carlo_valor(
+    ref_hm_s_sPolitica: &HashMap<String, String>,
+    uiEpisodios: usize,
+    f64Lambda: f64,
+    uiMaxPasos: usize,
+    u64Semilla: u64,
+) -> HashMap<String, f64> {
+    let entorno = EntornoAprendizaje::default();
+    let f64RecompensaMeta = entorno.hm_s_f64Recompensas[ESTADO_META];
+    let mut rng = StdRng::seed_from_u64(u64Semilla);
+    let vec_sIniciales = estados_iniciales_validos();
+    // State -> (sum of first-visit returns, number of first visits)
+    let mut hm_s_tpl_f64_uiRetornos: HashMap<&'static str, (f64, usize)> = HashMap::new();
+
+    for _ in 0..uiEpisodios {
+        let mut sEstado: &'static str = vec_sIniciales.choose(&mut rng).unwrap();
+        // (state, reward) of each step, in order
+        let mut vec_tpl_s_f64Pasos: Vec<(&'static str, f64)> = Vec::new();
+        let mut f64Retorno = 0.0;
+        while vec_tpl_s_f64Pasos.len() < uiMaxPasos {
+            let Some(sAccion) = ref_hm_s_sPolitica.get(sEstado) else {
+                break;
+            };
+            let transicion = paso_entorno(&entorno, sEstado, sAccion, &mut rng);
+            vec_tpl_s_f64Pasos.push((sEstado, transicion.f64Recompensa));
+            if transicion.bTerminal {
+                f64Retorno = f64RecompensaMeta;
+                break;
+            }
+            sEstado = transicion.sEstadoSiguiente;
+        }
+
+        // Walk back accumulating G; the earliest occurrence of a state overwrites later ones
+        let mut hm_s_f64PrimeraVisita: HashMap<&'static str, f64> = HashMap::new();
+        for (sEstadoPaso, f64Recompensa) in vec_tpl_s_f64Pasos.iter().rev() {
+            f64Retorno = f64Recompensa + f64Lambda * f64Retorno;
+            hm_s_f64PrimeraVisita.insert(sEstadoPaso, f64Retorno);
+        }
+        for (sEstadoPaso, f64RetornoPrimeraVisita) in hm_s_f64PrimeraVisita {
+            let tpl_f64_uiAcumulado = hm_s_tpl_f64_uiRetornos.entry(sEstadoPaso).or_insert((0.0, 0));
+            tpl_f64_uiAcumulado.0 += f64RetornoPrimeraVisita;
+            tpl_f64_uiAcumulado.1 += 1;
+        }
+    }
+
+    let mut hm_s_f64Valores: HashMap<String, f64> = hm_s_tpl_f64_uiRetornos
+        .into_iter()
+        .map(|(sEstado, (f64Suma, uiVisitas))| (sEstado.to_string(), f64Suma / uiVisitas as f64))
+        .collect();
+    hm_s_f64Valores.insert(ESTADO_META.to_string(), f64RecompensaMeta);
+    hm_s_f64Valores
+}
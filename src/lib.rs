@@ -0,0 +1,28 @@
+/// Library crate for the MDP robot project.
+///
+/// Exposes the map configuration, the value iteration solver, the simulations,
+/// the robustness evaluation and the plotting utilities so they can be used both
+/// by the `proyecto_robotica` binary and, with the `pyo3` feature, from Python.
+// src/lib.rs
+pub mod baselines;
+pub mod config;
+pub mod curriculo;
+pub mod entorno;
+pub mod escalado;
+pub mod experimento;
+#[cfg(feature = "egui")]
+pub mod inspector;
+pub mod learning;
+pub mod lote;
+pub mod mdp_model;
+pub mod plot_utils;
+pub mod recogibles;
+pub mod reporte;
+pub mod robustness;
+pub mod simulation;
+pub mod snapshot;
+pub mod texto;
+pub mod transition_matrices;
+
+#[cfg(feature = "pyo3")]
+mod python;
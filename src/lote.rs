@@ -0,0 +1,95 @@
+/// Batch mode: solving a folder of maps and saving the results without the GUI.
+///
+/// Each map is a layout file as written by `guardar_mapa_csv`. The grid of state names is fixed in `config`,
+/// so a map file must keep the names of `MAPA_ESTADOS`; its legend column sets the type of every cell.
+// src/lote.rs
+use crate::config::{cargar_tipos_celda_csv, guardar_mapa_csv, mapa_estados_como_filas, validar_mapa};
+use crate::entorno::Entorno;
+use crate::mdp_model::{value_iteration_con_opciones, OpcionesValueIteration};
+use crate::snapshot::{crear_snapshot_con_entorno, guardar_snapshot};
+use crate::texto::mapa_politica_texto;
+use crate::transition_matrices::guardar_resumen_estados_csv_con_entorno;
+use std::fs;
+use std::path::Path;
+
+/// Convergence threshold used for every map of a batch (the same as `main`).
+const F64_EPSILON_LOTE: f64 = 0.001;
+
+/// Solves every map configuration in a directory and writes its results to an output directory.
+///
+/// Each `.csv` file of `sDirMapas` (in name order) is loaded with `cargar_tipos_celda_csv`, which checks its
+/// layout with `cargar_mapa_csv` (and so `validar_mapa`) and turns its legend into the cell types of its own
+/// `Entorno`. The map is then solved with `value_iteration_con_opciones` and saved as `<nombre>_politica.json` (a `SnapshotMdp`),
+/// `<nombre>_valores.csv` (`guardar_resumen_estados_csv_con_entorno`) and `<nombre>_politica.txt`
+/// (`mapa_politica_texto`), where `<nombre>` is the input file name without extension. The grid itself is
+/// saved once as `mapa.csv` (`guardar_mapa_csv`). A file that fails is logged and skipped. No shared state
+/// is modified, so each map only affects its own results. The grid is checked with `validar_mapa` first,
+/// and nothing is solved if it fails. `sDirSalida` must not be `sDirMapas`, or the next run would read the
+/// written `mapa.csv` and `<nombre>_valores.csv` files as maps.
+///
+/// # Arguments
+///
+/// * `sDirMapas` - Directory holding the map files.
+/// * `sDirSalida` - Output directory; created if it does not exist.
+/// * `f64Lambda` - The discount factor (gamma).
+///
+/// # Returns
+///
+/// The number of maps solved and saved, or an error if the grid is invalid, both directories are the same, or
+/// a directory cannot be read or created.
+pub fn procesar_directorio(
+    sDirMapas: &str,
+    sDirSalida: &str,
+    f64Lambda: f64,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    validar_mapa(&mapa_estados_como_filas()).map_err(|vec_sErrores| format!("Mapa inválido: {}", vec_sErrores.join("; ")))?;
+    fs::create_dir_all(sDirSalida)?;
+    if fs::canonicalize(sDirSalida)? == fs::canonicalize(sDirMapas)? {
+        return Err(format!("El directorio de salida '{}' no puede ser el de los mapas", sDirSalida).into());
+    }
+    guardar_mapa_csv(&Path::new(sDirSalida).join("mapa.csv").to_string_lossy())?;
+    let mut vec_pbMapas: Vec<_> = fs::read_dir(sDirMapas)?
+        .filter_map(|resEntrada| resEntrada.ok().map(|entrada| entrada.path()))
+        .filter(|pbRuta| pbRuta.extension().is_some_and(|osExtension| osExtension == "csv"))
+        .collect();
+    vec_pbMapas.sort();
+
+    let mut uiProcesados = 0;
+    for pbMapa in &vec_pbMapas {
+        let sNombre = pbMapa.file_stem().unwrap().to_string_lossy().to_string();
+        match procesar_mapa(&pbMapa.to_string_lossy(), &Path::new(sDirSalida).join(&sNombre), f64Lambda) {
+            Ok(()) => uiProcesados += 1,
+            Err(errBoxedError) => log::warn!("Error al procesar el mapa '{}': {:?}", pbMapa.display(), errBoxedError),
+        }
+    }
+
+    log::info!("Lote: {} de {} mapas procesados en '{}'.", uiProcesados, vec_pbMapas.len(), sDirSalida);
+    Ok(uiProcesados)
+}
+
+/// Loads, solves and saves one map; `pbPrefijo` is the output path without suffix.
+fn procesar_mapa(sRutaMapa: &str, pbPrefijo: &Path, f64Lambda: f64) -> Result<(), Box<dyn std::error::Error>> {
+    let opciones = OpcionesValueIteration {
+        entorno: Entorno {
+            hm_s_eTiposCelda: cargar_tipos_celda_csv(sRutaMapa)?,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let resultado = value_iteration_con_opciones(f64Lambda, F64_EPSILON_LOTE, None, &opciones);
+    let (hm_s_f64Valores, hm_s_sPolitica) = (resultado.hm_s_f64Valores, resultado.hm_s_sPolitica);
+
+    let sPrefijo = pbPrefijo.to_string_lossy();
+    guardar_snapshot(
+        &crear_snapshot_con_entorno(f64Lambda, F64_EPSILON_LOTE, &hm_s_f64Valores, &hm_s_sPolitica, &opciones.entorno),
+        &format!("{}_politica.json", sPrefijo),
+    )?;
+    guardar_resumen_estados_csv_con_entorno(
+        &hm_s_f64Valores,
+        &hm_s_sPolitica,
+        &opciones.entorno,
+        &format!("{}_valores.csv", sPrefijo),
+    )?;
+    fs::write(format!("{}_politica.txt", sPrefijo), mapa_politica_texto(&hm_s_sPolitica))?;
+    Ok(())
+}
@@ -7,34 +7,153 @@
 /// 4. A 1000-step simulation to gather performance statistics.
 /// 5. Generation and saving of transition matrices and result plots.
 // src/main.rs
-mod config;
-mod mdp_model;
-mod plot_utils;
-mod robustness;
-mod simulation;
-mod transition_matrices;
-
-use config::obtener_recompensas; // Assuming this is already updated
-use mdp_model::value_iteration; // Assuming this is already updated
-use plot_utils::graficar_resultados_finales; // Assuming this is already updated
-use robustness::evaluar_robustez; // Assuming this is already updated
-use simulation::{ejecutar_simulacion, simulacion_1000_pasos}; // Assuming these are already updated
-use transition_matrices::guardar_matrices_transicion_csv; // Assuming this is already updated
+use robotica::config::{
+    cargar_recompensas_csv, crear_directorio_ejecucion, guardar_mapa_csv, mapa_estados_como_filas, validar_mapa,
+}; // Assuming this is already updated
+use robotica::entorno::{recompensas_con_entorno, Entorno};
+use robotica::lote::procesar_directorio;
+use robotica::mdp_model::{
+    estados_contra_pared, politica_tiene_ciclo_sin_meta, tabla_q_desde_valores, value_iteration_con_opciones,
+    OpcionesValueIteration,
+}; // Assuming this is already updated
+use robotica::plot_utils::graficar_resultados_finales; // Assuming this is already updated
+use robotica::reporte::generar_reporte_markdown;
+use robotica::robustness::{
+    evaluar_robustez_con_entorno, lambda_minimo_busca_meta_con_entorno,
+}; // Assuming this is already updated
+use robotica::simulation::{
+    ejecutar_simulacion, es_estado_inicial_valido, simulacion_1000_pasos_con_opciones, MarcadorSesion,
+    OpcionesSimulacion, RecocidoSoftmax, TemaColores,
+}; // Assuming these are already updated
+use robotica::texto::mapa_politica_terminal;
+use robotica::transition_matrices::{
+    guardar_matrices_transicion_csv, guardar_resumen_estados_csv_con_entorno,
+}; // Assuming this is already updated
+use std::path::PathBuf;
 
 /// Orchestrates the MDP processing, simulation, and result generation.
 /// Iterates through predefined lambda values, performs value iteration,
 /// simulates robot behavior, evaluates policy robustness, and plots results.
 #[macroquad::main("Simulacion MDP Robot")]
 async fn main() {
+    // `--quiet` skips the per-lambda value/policy dumps and lowers the default log level to `warn`.
+    let bSilencioso = std::env::args().any(|sArg| sArg == "--quiet" || sArg == "-q");
+    // `--progreso` prints each robustness solve to stderr as it starts, even with `--quiet`.
+    let bProgreso = std::env::args().any(|sArg| sArg == "--progreso");
+
+    // Library progress and results are emitted through `log`; show `info` and above by default (override with RUST_LOG).
+    let sNivelLog = if bSilencioso { "warn" } else { "info" };
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(sNivelLog)).init();
+
+    // Catch typos in the map (repeated or blank names, missing special states) before using it.
+    if let Err(vec_sErrores) = validar_mapa(&mapa_estados_como_filas()) {
+        for sError in &vec_sErrores {
+            eprintln!("Mapa inválido: {}", sError);
+        }
+        return;
+    }
+
+    // `--recompensas <ruta>` overrides individual state rewards from an `estado,recompensa` CSV.
+    // The overrides travel in the `Entorno` shared by the solver, the simulations and the analyses below.
+    let vec_sArgs: Vec<String> = std::env::args().collect();
+    let mut entorno = Entorno::default();
+    if let Some(uiIndice) = vec_sArgs.iter().position(|sArg| sArg == "--recompensas") {
+        match vec_sArgs.get(uiIndice + 1).map(|sRuta| cargar_recompensas_csv(sRuta)) {
+            Some(Ok(hm_s_f64Recompensas)) => entorno.hm_s_f64RecompensasPersonalizadas = hm_s_f64Recompensas,
+            Some(Err(errBoxedError)) => eprintln!("Error al cargar las recompensas: {:?}", errBoxedError),
+            None => eprintln!("--recompensas requiere la ruta de un CSV"),
+        }
+    }
+
+    // `--lote <dir_mapas> <dir_salida>` solves every map configuration of a directory (λ = 0.9) and exits.
+    if let Some(uiIndice) = vec_sArgs.iter().position(|sArg| sArg == "--lote") {
+        match (vec_sArgs.get(uiIndice + 1), vec_sArgs.get(uiIndice + 2)) {
+            (Some(sDirMapas), Some(sDirSalida)) => {
+                if let Err(errBoxedError) = procesar_directorio(sDirMapas, sDirSalida, 0.9) {
+                    eprintln!("Error en el modo lote: {:?}", errBoxedError);
+                }
+            }
+            _ => eprintln!("--lote requiere un directorio de mapas y uno de salida"),
+        }
+        return;
+    }
+
+    // `--salida <dir>` writes every output file into a new timestamped directory inside `<dir>` instead of the
+    // current directory, so consecutive runs do not overwrite each other.
+    let pbDirSalida = match vec_sArgs.iter().position(|sArg| sArg == "--salida") {
+        Some(uiIndice) => match vec_sArgs.get(uiIndice + 1).map(|sDir| crear_directorio_ejecucion(sDir)) {
+            Some(Ok(pbDirectorio)) => pbDirectorio,
+            Some(Err(errBoxedError)) => {
+                eprintln!("Error al crear el directorio de salida: {:?}", errBoxedError);
+                return;
+            }
+            None => {
+                eprintln!("--salida requiere un directorio");
+                return;
+            }
+        },
+        None => PathBuf::from("."),
+    };
+    let sDirSalida = pbDirSalida.to_string_lossy().to_string();
+    let ruta_salida = |sNombre: &str| pbDirSalida.join(sNombre).to_string_lossy().to_string();
+
+    // `--inicio <estado>` starts every simulated rollout from that state instead of a random one.
+    let opt_sEstadoInicial: Option<String> = match vec_sArgs.iter().position(|sArg| sArg == "--inicio") {
+        Some(uiIndice) => match vec_sArgs.get(uiIndice + 1) {
+            Some(sEstado) if es_estado_inicial_valido(sEstado) => Some(sEstado.clone()),
+            Some(sEstado) => {
+                eprintln!("Estado inicial no válido (obstáculo, meta o fuera del mapa): {}", sEstado);
+                return;
+            }
+            None => {
+                eprintln!("--inicio requiere el nombre de un estado");
+                return;
+            }
+        },
+        None => None,
+    };
+    // `--daltonico` draws the visual simulation with the colorblind-safe palette.
+    let temaColores = if vec_sArgs.iter().any(|sArg| sArg == "--daltonico") {
+        TemaColores::daltonico()
+    } else {
+        TemaColores::default()
+    };
+    // `--recocido` drives the visual simulation with a softmax agent whose temperature anneals from 5 to 0.05.
+    let bRecocido = vec_sArgs.iter().any(|sArg| sArg == "--recocido");
+    let opcionesSimulacion = OpcionesSimulacion {
+        opt_sEstadoInicial: opt_sEstadoInicial.clone(),
+        entorno: entorno.clone(),
+        ..Default::default()
+    };
+    if let Err(sError) = opcionesSimulacion.validar() {
+        eprintln!("Opciones de simulación no válidas: {}", sError);
+        return;
+    }
+    let opcionesValueIteration = OpcionesValueIteration {
+        entorno: entorno.clone(),
+        ..Default::default()
+    };
+
     let vec_f64FactoresLanda = vec![0.86, 0.90, 0.94, 0.98];
     // Initialize vectors to store results for final plotting
     let mut vec_tpl_f64_vec_tpl_s_uiGraficosRobustez = vec![];
     let mut vec_tpl_f64_ui_uiResumen1000Pasos = vec![];
+    let mut vec_tpl_f64_hm_s_sPoliticas = vec![];
 
     // Load the base reward map
     // Note: This map is modified by `ejecutar_simulacion` if the goal is reached.
     // This could affect subsequent iterations if not intended.
-    let mut hm_s_f64RecompensasMap = obtener_recompensas();
+    let mut hm_s_f64RecompensasMap = recompensas_con_entorno(&entorno);
+    // Below this discount factor the living penalty outweighs the distant goal and the policy stops seeking it
+    if !bSilencioso {
+        match lambda_minimo_busca_meta_con_entorno(0.001, &entorno) {
+            Some(f64LambdaMinimo) => println!("λ mínimo para que la política busque la meta: {:.3}", f64LambdaMinimo),
+            None => println!("⚠️ Ningún λ ≤ 0.99 lleva a la meta desde todos los estados iniciales"),
+        }
+    }
+
+    // Scoreboard of the visual rollouts, kept across all lambdas
+    let mut marcadorSesion = MarcadorSesion::default();
 
     // Main loop: Iterate through each lambda factor
     for f64LandaRef in &vec_f64FactoresLanda {
@@ -44,57 +163,133 @@ async fn main() {
         );
 
         // Perform Value Iteration to get optimal values and policy
-        // value_iteration(f64Lambda, f64Epsilon, opt_hm_s_hm_s_f64ProbTransExt)
-        let (hm_s_f64ValoresEstados, mut hm_s_sPoliticaOptima) =
-            value_iteration(*f64LandaRef, 0.001, None);
+        // value_iteration_con_opciones(f64Lambda, f64Epsilon, opt_hm_s_hm_s_f64ProbTransExt, ref_opciones)
+        let resultadoVi = value_iteration_con_opciones(*f64LandaRef, 0.001, None, &opcionesValueIteration);
+        let (hm_s_f64ValoresEstados, hm_s_sPoliticaOptima) =
+            (resultadoVi.hm_s_f64Valores, resultadoVi.hm_s_sPolitica);
 
         // Print state values and optimal policy
-        println!("\nValor de los estados:");
-        let mut vec_sKeysValores: Vec<_> = hm_s_f64ValoresEstados.keys().collect();
-        vec_sKeysValores.sort();
-        for sKeyEstadoRef in vec_sKeysValores {
-            println!(
-                "{}: {:.2}",
-                sKeyEstadoRef, hm_s_f64ValoresEstados[sKeyEstadoRef]
-            );
+        if !bSilencioso {
+            println!("\nValor de los estados:");
+            let mut vec_sKeysValores: Vec<_> = hm_s_f64ValoresEstados.keys().collect();
+            vec_sKeysValores.sort();
+            for sKeyEstadoRef in vec_sKeysValores {
+                println!(
+                    "{}: {:.2}",
+                    sKeyEstadoRef, hm_s_f64ValoresEstados[sKeyEstadoRef]
+                );
+            }
+
+            println!("\nPolítica óptima:");
+            let mut vec_sKeysPolitica: Vec<_> = hm_s_sPoliticaOptima.keys().collect();
+            vec_sKeysPolitica.sort();
+            for sKeyEstadoStrRef in vec_sKeysPolitica {
+                println!(
+                    "{}: {:?}",
+                    sKeyEstadoStrRef, hm_s_sPoliticaOptima[sKeyEstadoStrRef]
+                );
+            }
+
+            println!("\nMapa de la política:");
+            println!("{}", mapa_politica_terminal(&hm_s_sPoliticaOptima));
         }
 
-        println!("\nPolítica óptima:");
-        let mut vec_sKeysPolitica: Vec<_> = hm_s_sPoliticaOptima.keys().collect();
-        vec_sKeysPolitica.sort();
-        for sKeyEstadoStrRef in vec_sKeysPolitica {
-            println!(
-                "{}: {:?}",
-                sKeyEstadoStrRef, hm_s_sPoliticaOptima[sKeyEstadoStrRef]
-            );
+        // One-file overview of the solve (state, position, reward, value, action)
+        if let Err(errBoxedError) = guardar_resumen_estados_csv_con_entorno(
+            &hm_s_f64ValoresEstados,
+            &hm_s_sPoliticaOptima,
+            &entorno,
+            &ruta_salida(&format!("resumen_estados_{:.2}.csv", *f64LandaRef)),
+        ) {
+            eprintln!("Error al guardar el resumen de estados: {:?}", errBoxedError);
+        }
+
+        // Diagnostic: a policy loop that never reaches the goal lowers the simulation success rate
+        if let Some(vec_sCiclo) = politica_tiene_ciclo_sin_meta(&hm_s_sPoliticaOptima) {
+            println!("⚠️ La política tiene un ciclo sin meta: {}", vec_sCiclo.join(" → "));
+        }
+        // Diagnostic: states that prefer bumping into a wall over every real move
+        if !bSilencioso {
+            let vec_sContraPared = estados_contra_pared(&hm_s_sPoliticaOptima);
+            if !vec_sContraPared.is_empty() {
+                println!("⚠️ Estados cuya acción óptima choca contra una pared: {}", vec_sContraPared.join(", "));
+            }
         }
 
         // Run visual simulation (Macroquad)
-        // ejecutar_simulacion(ref_mut_hm_s_sPolitica, uiPasos, ref_mut_hm_s_f64RecompensasMap)
+        // ejecutar_simulacion(ref_hm_s_sPolitica, ref_hm_s_f64Valores, f64Lambda, ref_entorno, uiPasos,
+        //                     ref_mut_hm_s_f64RecompensasMap, opt_sEstadoInicial, ref_mut_marcador, ref_tema, opt_recocido)
         println!("\n→ Iniciando simulación visual...");
-        ejecutar_simulacion(&mut hm_s_sPoliticaOptima, 70, &mut hm_s_f64RecompensasMap).await;
+        let opt_recocido = bRecocido.then(|| RecocidoSoftmax {
+            tabla_q: tabla_q_desde_valores(&hm_s_f64ValoresEstados, *f64LandaRef, None),
+            f64TemperaturaInicial: 5.0,
+            f64TemperaturaFinal: 0.05,
+        });
+        if let Err(sError) = ejecutar_simulacion(
+            &hm_s_sPoliticaOptima,
+            &hm_s_f64ValoresEstados,
+            *f64LandaRef,
+            &entorno,
+            70,
+            &mut hm_s_f64RecompensasMap,
+            opt_sEstadoInicial.clone(),
+            &mut marcadorSesion,
+            &temaColores,
+            opt_recocido.as_ref(),
+        )
+        .await
+        {
+            eprintln!("Error en la simulación visual: {}", sError);
+            return;
+        }
 
         // Evaluate robustness of the current policy
-        // evaluar_robustez(ref_hm_s_sPoliticaBase, f64Lambda)
-        let vec_tpl_s_uiResultadosRobustez = evaluar_robustez(&hm_s_sPoliticaOptima, *f64LandaRef);
+        // evaluar_robustez_con_entorno(ref_hm_s_sPoliticaBase, f64Lambda, bProgreso, ref_entorno)
+        let vec_tpl_s_uiResultadosRobustez =
+            evaluar_robustez_con_entorno(&hm_s_sPoliticaOptima, *f64LandaRef, bProgreso, &entorno);
         vec_tpl_f64_vec_tpl_s_uiGraficosRobustez
             .push((*f64LandaRef, vec_tpl_s_uiResultadosRobustez));
 
         // Run 1000-step simulation for performance statistics
-        // simulacion_1000_pasos(ref_hm_s_sPolitica, uiMaxPasos)
-        let (uiMetasAlcanzadas, uiPozosCaidos) = simulacion_1000_pasos(&hm_s_sPoliticaOptima, 1000);
+        // simulacion_1000_pasos_con_opciones(ref_hm_s_sPolitica, uiMaxPasos, ref_opciones)
+        let (uiMetasAlcanzadas, uiPozosCaidos, _) =
+            match simulacion_1000_pasos_con_opciones(&hm_s_sPoliticaOptima, 1000, &opcionesSimulacion) {
+                Ok(tpl_uix3Resultado) => tpl_uix3Resultado,
+                Err(sError) => {
+                    eprintln!("Opciones de simulación no válidas: {}", sError);
+                    return;
+                }
+            };
         vec_tpl_f64_ui_uiResumen1000Pasos.push((*f64LandaRef, uiMetasAlcanzadas, uiPozosCaidos));
+        vec_tpl_f64_hm_s_sPoliticas.push((*f64LandaRef, hm_s_sPoliticaOptima));
     }
 
     // Generate and save final plots
-    // graficar_resultados_finales(ref_vec_tpl_f64_vec_tpl_s_uiGraficosRobustez, ref_vec_tpl_f64_ui_uiResumen1000Pasos)
+    // graficar_resultados_finales(ref_vec_tpl_f64_vec_tpl_s_uiGraficosRobustez, ref_vec_tpl_f64_ui_uiResumen1000Pasos,
+    //                             sDirSalida)
     if let Err(errBoxedError) = graficar_resultados_finales(
         &vec_tpl_f64_vec_tpl_s_uiGraficosRobustez,
         &vec_tpl_f64_ui_uiResumen1000Pasos,
+        &sDirSalida,
     ) {
         eprintln!("Error al graficar resultados: {:?}", errBoxedError);
     }
 
     // Save transition matrices to CSV files
-    guardar_matrices_transicion_csv();
+    guardar_matrices_transicion_csv(&sDirSalida);
+
+    // Record the grid the results were produced on
+    if let Err(errBoxedError) = guardar_mapa_csv(&ruta_salida("mapa.csv")) {
+        eprintln!("Error al guardar el mapa: {:?}", errBoxedError);
+    }
+
+    // Summarize the run in a Markdown report
+    if let Err(errBoxedError) = generar_reporte_markdown(
+        &vec_tpl_f64_ui_uiResumen1000Pasos,
+        &vec_tpl_f64_vec_tpl_s_uiGraficosRobustez,
+        &vec_tpl_f64_hm_s_sPoliticas,
+        &ruta_salida("reporte.md"),
+    ) {
+        eprintln!("Error al generar el reporte: {:?}", errBoxedError);
+    }
 }
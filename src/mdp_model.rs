@@ -1,9 +1,20 @@
 /// Core logic for the Markov Decision Process (MDP), including state/position lookups, movement, and the value iteration algorithm.
 use crate::config::{
-    acciones, obtener_recompensas, prob_transicion, COLUMNAS_MAPA, ESTADO_META, FILAS_MAPA,
+    acciones, obtener_recompensas, prob_transicion, COLUMNAS_MAPA, ESTADOS_PELIGRO, ESTADO_META, FILAS_MAPA,
     MAPA_ESTADOS, OBSTACULOS,
 };
-use std::collections::HashMap;
+use crate::entorno::{
+    es_peligro_con_entorno, es_peligro_terminal, paga_choque, probabilidades_con_fallo, probabilidades_con_viento,
+    recompensa_entrada, recompensas_con_entorno, resolver_destino, tipo_celda_con_entorno, valor_meta,
+    vecinos_con_entorno, Entorno,
+};
+use crate::learning::{estados_iniciales_validos, TablaQ};
+use crate::texto::TipoCelda;
+use ::rand::rngs::StdRng;
+use ::rand::seq::SliceRandom;
+use ::rand::SeedableRng;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
 
 /// Finds the (row, column) coordinates of a given state name in `MAPA_ESTADOS`.
 ///
@@ -71,8 +82,315 @@ pub fn mover(uiFila: usize, uiCol: usize, sAccion: &str) -> (isize, isize) {
     }
 }
 
+/// Returns the default transition model from `config::prob_transicion()` with owned keys.
+///
+/// This is the same shape as the external models accepted by `value_iteration`
+/// (e.g. those built by `robustness::construir_modelo_ruido`).
+///
+/// # Returns
+///
+/// A `HashMap<String, HashMap<String, f64>>` mapping each action to its resulting directions and probabilities.
+pub fn modelo_transicion_base() -> HashMap<String, HashMap<String, f64>> {
+    prob_transicion()
+        .iter()
+        .map(|(sKey, hmInnerValue)| {
+            (
+                sKey.to_string(),
+                hmInnerValue.iter().map(|(sKey2, f64Value2)| (sKey2.to_string(), *f64Value2)).collect(),
+            )
+        })
+        .collect()
+}
+
+/// Initial state values used by `value_iteration_con_opciones`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InicializacionValores {
+    /// V(s) = 0 for every state.
+    #[default]
+    Cero,
+    /// V(s) = discounted value of walking the shortest (BFS) path to the goal with no noise,
+    /// paying the state's own reward at every step. The path follows the movement rules of
+    /// `OpcionesValueIteration::entorno` (see `distancias_a_meta_con_entorno`). Gives the solver a
+    /// head start for high lambda.
+    DistanciaMeta,
+}
+
+/// Optional settings for `value_iteration_con_opciones`.
+///
+/// `OpcionesValueIteration::default()` reproduces `value_iteration` exactly.
+#[derive(Debug, Clone, Default)]
+pub struct OpcionesValueIteration {
+    /// How state values are initialized before the first sweep.
+    pub eInicializacion: InicializacionValores,
+    /// Wall-clock budget. If set, no new sweep starts once it has elapsed, even without convergence.
+    pub opt_durMaxima: Option<Duration>,
+    /// Extra movement rules (one-way passages, walls, ...) applied to every transition.
+    pub entorno: Entorno,
+    /// Seed for breaking ties between equally good actions at random. `None` keeps the
+    /// deterministic tie-break (first action in `acciones()` order); a given seed always yields
+    /// the same policy, and different seeds sample different optimal policies. The draw is made once,
+    /// over the final values (see `tabla_q_con_opciones`), not on every sweep.
+    pub opt_u64SemillaDesempate: Option<u64>,
+    /// Stopping rule. `None` stops when the largest change in a sweep is at most `f64Epsilon`
+    /// (the same as `Some(CriterioConvergencia::Absoluto(f64Epsilon))`); if set, it replaces `f64Epsilon`.
+    pub opt_eCriterio: Option<CriterioConvergencia>,
+    /// Transition models for specific origin states (heterogeneous terrain). A listed state uses its
+    /// own model for every action; the others use the global model passed to the solver.
+    pub hm_s_ModelosPorEstado: ModelosPorEstado,
+    /// If set to N, also stop once the greedy policy (the deterministic argmax of each sweep, before any
+    /// `opt_u64SemillaDesempate` draw) has come out the same for N consecutive sweeps, even
+    /// if the values have not met the stopping rule yet. Useful when only the policy matters; the returned
+    /// values are then less converged than `f64Epsilon` would give, so such a stop is reported with
+    /// `bConvergio = false` and `MotivoParada::PoliticaEstable`.
+    pub opt_uiBarridosPoliticaEstable: Option<usize>,
+}
+
+/// Transition model: action -> resulting direction -> probability.
+pub type ModeloTransicion = HashMap<String, HashMap<String, f64>>;
+
+/// Transition model per origin state: state -> action -> resulting direction -> probability.
+pub type ModelosPorEstado = HashMap<String, HashMap<String, HashMap<String, f64>>>;
+
+/// State values (V) and the optimal policy, as returned by `value_iteration`.
+pub type ValoresYPolitica = (HashMap<&'static str, f64>, HashMap<String, String>);
+
+/// Tolerance on the per-action probability sums checked by `validar_modelos_por_estado`.
+const F64_TOLERANCIA_SUMA_MODELO: f64 = 1e-9;
+
+/// Stopping rule for value iteration, applied to the largest value change Δ of a sweep.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CriterioConvergencia {
+    /// Stop when Δ ≤ threshold.
+    Absoluto(f64),
+    /// Stop when Δ / max|V| ≤ threshold, so convergence does not depend on the scale of the rewards.
+    Relativo(f64),
+}
+
+impl Default for CriterioConvergencia {
+    /// `Absoluto(0.001)`, the threshold used by `main`.
+    fn default() -> Self {
+        CriterioConvergencia::Absoluto(0.001)
+    }
+}
+
+impl CriterioConvergencia {
+    /// Returns `true` if a sweep whose largest change was `f64DeltaMax`, leaving values whose
+    /// largest magnitude is `f64MaxAbsV`, meets this criterion.
+    pub fn convergio(&self, f64DeltaMax: f64, f64MaxAbsV: f64) -> bool {
+        match *self {
+            CriterioConvergencia::Absoluto(f64Umbral) => f64DeltaMax <= f64Umbral,
+            CriterioConvergencia::Relativo(f64Umbral) => {
+                if f64MaxAbsV == 0.0 {
+                    f64DeltaMax == 0.0
+                } else {
+                    f64DeltaMax / f64MaxAbsV <= f64Umbral
+                }
+            }
+        }
+    }
+}
+
+/// Two action values closer than this are considered tied when `opt_u64SemillaDesempate` is set.
+const F64_TOLERANCIA_EMPATE: f64 = 1e-9;
+
+/// Why `value_iteration_con_opciones` stopped sweeping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MotivoParada {
+    /// The last sweep met the stopping rule (`f64Epsilon` or `opt_eCriterio`).
+    #[default]
+    Convergencia,
+    /// The greedy policy was stable for `opt_uiBarridosPoliticaEstable` sweeps before the values converged.
+    PoliticaEstable,
+    /// The time budget `opt_durMaxima` ran out before the values converged.
+    Tiempo,
+}
+
+/// Output of `value_iteration_con_opciones`.
+#[derive(Debug, Clone)]
+pub struct ResultadoValueIteration {
+    /// State values (V).
+    pub hm_s_f64Valores: HashMap<&'static str, f64>,
+    /// The optimal policy (mapping state names to action names).
+    pub hm_s_sPolitica: HashMap<String, String>,
+    /// Number of sweeps performed.
+    pub uiIteraciones: usize,
+    /// `true` only if the values met the stopping rule; `false` after an early stop (see `eMotivoParada`).
+    pub bConvergio: bool,
+    /// Why the solver stopped.
+    pub eMotivoParada: MotivoParada,
+    /// Time spent in the Bellman backups (computing every action's value).
+    pub durBarridos: Duration,
+    /// Time spent choosing and keeping the policy: the argmax over the action values, the policy updates,
+    /// the `opt_uiBarridosPoliticaEstable` checks and the `opt_u64SemillaDesempate` pass.
+    pub durPolitica: Duration,
+}
+
+/// Computes the shortest-path distance (in moves) from every reachable state to the goal.
+///
+/// Same as `distancias_a_meta_con_entorno` with `Entorno::default()`: a breadth-first search over the
+/// four-connected grid, skipping obstacles.
+///
+/// # Returns
+///
+/// A `HashMap<&'static str, usize>` with the distance of each state that can reach the goal.
+/// Obstacles and states with no path to the goal are absent.
+pub fn distancias_a_meta() -> HashMap<&'static str, usize> {
+    distancias_a_meta_con_entorno(&Entorno::default())
+}
+
+/// Computes the shortest-path distance (in noise-free moves) from every state to the goal under the
+/// movement rules of `ref_entorno`.
+///
+/// The search runs backwards from `ESTADO_META` over the moves of `vecinos_con_entorno`, so walls, one-way
+/// passages and teleporters are honored: a one-way passage only counts in its open direction.
+///
+/// # Returns
+///
+/// A `HashMap<&'static str, usize>` with the distance of each state that can reach the goal.
+/// Obstacles and states with no path to the goal are absent.
+pub fn distancias_a_meta_con_entorno(ref_entorno: &Entorno) -> HashMap<&'static str, usize> {
+    distancias_bfs(ref_entorno, &[ESTADO_META])
+}
+
+/// Computes the shortest-path distance (in moves) from every state to the nearest danger state.
+///
+/// Same search as `distancias_a_meta`, started from every state of `ESTADOS_PELIGRO` at once.
+/// Danger states have distance 0.
+///
+/// # Returns
+///
+/// A `HashMap<&'static str, usize>` with the distance of each state connected to a danger state.
+/// Obstacles and states with no path to a danger state are absent (the map is empty without dangers).
+pub fn distancias_a_peligro() -> HashMap<&'static str, usize> {
+    distancias_a_peligro_con_entorno(&Entorno::default())
+}
+
+/// Computes the shortest-path distance (in noise-free moves) from every state to the nearest danger
+/// state under the movement rules of `ref_entorno`, as `distancias_a_meta_con_entorno` does for the goal.
+/// The danger states are those of `entorno::es_peligro_con_entorno`, so cell edits are honored.
+pub fn distancias_a_peligro_con_entorno(ref_entorno: &Entorno) -> HashMap<&'static str, usize> {
+    let vec_sPeligros: Vec<&'static str> = MAPA_ESTADOS
+        .iter()
+        .flatten()
+        .copied()
+        .filter(|sEstado| es_peligro_con_entorno(ref_entorno, sEstado))
+        .collect();
+    distancias_bfs(ref_entorno, &vec_sPeligros)
+}
+
+/// Lists, for every state, the `(origin, action)` pairs whose noise-free move under `ref_entorno` lands in it
+/// (the reverse of `vecinos_con_entorno`). Obstacles, the `Obstaculo` cells of `Entorno::hm_s_eTiposCelda`
+/// included, are not origins; origins come in map order.
+pub fn vecinos_inversos(ref_entorno: &Entorno) -> HashMap<&'static str, Vec<(&'static str, &'static str)>> {
+    let mut hm_s_vec_tpl_Inversos: HashMap<&'static str, Vec<(&'static str, &'static str)>> = HashMap::new();
+    for &sOrigen in MAPA_ESTADOS
+        .iter()
+        .flatten()
+        .filter(|sEstado| tipo_celda_con_entorno(ref_entorno, sEstado) != TipoCelda::Obstaculo)
+    {
+        for (sDestino, sAccion) in vecinos_con_entorno(ref_entorno, sOrigen) {
+            hm_s_vec_tpl_Inversos.entry(sDestino).or_default().push((sOrigen, sAccion));
+        }
+    }
+    hm_s_vec_tpl_Inversos
+}
+
+/// Multi-source breadth-first search backwards over the noise-free moves of `ref_entorno`.
+fn distancias_bfs(ref_entorno: &Entorno, ref_arr_sOrigenes: &[&'static str]) -> HashMap<&'static str, usize> {
+    let hm_s_vec_tpl_Inversos = vecinos_inversos(ref_entorno);
+    let mut hm_s_uiDistancias: HashMap<&'static str, usize> =
+        ref_arr_sOrigenes.iter().map(|sOrigen| (*sOrigen, 0)).collect();
+    let mut vdq_sPendientes: VecDeque<&'static str> = ref_arr_sOrigenes.iter().copied().collect();
+
+    while let Some(sEstado) = vdq_sPendientes.pop_front() {
+        let uiDistancia = hm_s_uiDistancias[sEstado];
+        // Walls, one-way passages and teleporters make moves irreversible, so follow them backwards.
+        for &(sPredecesor, _) in hm_s_vec_tpl_Inversos.get(sEstado).into_iter().flatten() {
+            if !hm_s_uiDistancias.contains_key(sPredecesor) {
+                hm_s_uiDistancias.insert(sPredecesor, uiDistancia + 1);
+                vdq_sPendientes.push_back(sPredecesor);
+            }
+        }
+    }
+
+    hm_s_uiDistancias
+}
+
+/// Weight of the normalized farthest-cell distance in `dificultad_mapa`.
+pub(crate) const F64_PESO_DIFICULTAD_DISTANCIA: f64 = 0.5;
+/// Weight of the obstacle fraction in `dificultad_mapa`.
+pub(crate) const F64_PESO_DIFICULTAD_OBSTACULOS: f64 = 0.2;
+/// Weight of the fraction of danger cells along the longest shortest path in `dificultad_mapa`.
+pub(crate) const F64_PESO_DIFICULTAD_PELIGRO: f64 = 0.3;
+
+/// Scores how hard the map is to navigate, in [0, 1], so maps can be ranked from easy to hard.
+///
+/// The score is the weighted sum of three features, each normalized to [0, 1]:
+/// - 0.5 × the BFS distance from the farthest cell that can reach the goal, divided by the number
+///   of such cells minus one (the longest possible path through them);
+/// - 0.2 × the fraction of map cells that are obstacles;
+/// - 0.3 × the fraction of danger cells lying on, or four-adjacent to, the shortest path from that
+///   farthest cell (0 if the map has no danger cells).
+///
+/// The path is the one obtained by always moving to the first neighbor, in `acciones()` order, that is
+/// one step closer to the goal. Transition noise and rewards are not taken into account.
+pub fn dificultad_mapa() -> f64 {
+    let hm_s_uiDistancias = distancias_a_meta();
+    let vec_sCeldas: Vec<&'static str> = MAPA_ESTADOS.iter().flatten().copied().collect();
+
+    // Farthest cell from the goal; ties go to the first one in map order.
+    let mut sMasLejano = ESTADO_META;
+    for sEstado in &vec_sCeldas {
+        if let Some(uiDistancia) = hm_s_uiDistancias.get(sEstado) {
+            if *uiDistancia > hm_s_uiDistancias[sMasLejano] {
+                sMasLejano = sEstado;
+            }
+        }
+    }
+    let uiDistanciaMax = hm_s_uiDistancias[sMasLejano];
+    let f64Distancia = if hm_s_uiDistancias.len() > 1 {
+        uiDistanciaMax as f64 / (hm_s_uiDistancias.len() - 1) as f64
+    } else {
+        0.0
+    };
+
+    let uiObstaculos = vec_sCeldas.iter().filter(|sEstado| OBSTACULOS.contains(*sEstado)).count();
+    let f64Obstaculos = uiObstaculos as f64 / vec_sCeldas.len() as f64;
+
+    // Walk the shortest path from the farthest cell, collecting danger cells on or next to it.
+    let mut hs_sPeligrosCercanos: HashSet<&'static str> = HashSet::new();
+    let mut sActual = sMasLejano;
+    loop {
+        if ESTADOS_PELIGRO.contains(&sActual) {
+            hs_sPeligrosCercanos.insert(sActual);
+        }
+        let vec_sVecinos: Vec<&'static str> =
+            vecinos_con_entorno(&Entorno::default(), sActual).into_iter().map(|(sVecino, _)| sVecino).collect();
+        hs_sPeligrosCercanos.extend(vec_sVecinos.iter().filter(|sVecino| ESTADOS_PELIGRO.contains(*sVecino)));
+        if sActual == ESTADO_META {
+            break;
+        }
+        sActual = *vec_sVecinos
+            .iter()
+            .find(|sVecino| hm_s_uiDistancias.get(*sVecino).is_some_and(|uiD| uiD + 1 == hm_s_uiDistancias[sActual]))
+            .unwrap();
+    }
+    let uiPeligros = vec_sCeldas.iter().filter(|sEstado| ESTADOS_PELIGRO.contains(*sEstado)).count();
+    let f64Peligro = if uiPeligros > 0 {
+        hs_sPeligrosCercanos.len() as f64 / uiPeligros as f64
+    } else {
+        0.0
+    };
+
+    F64_PESO_DIFICULTAD_DISTANCIA * f64Distancia
+        + F64_PESO_DIFICULTAD_OBSTACULOS * f64Obstaculos
+        + F64_PESO_DIFICULTAD_PELIGRO * f64Peligro
+}
+
 /// Performs the value iteration algorithm to find the optimal state values and policy.
 ///
+/// Equivalent to `value_iteration_con_opciones` with `OpcionesValueIteration::default()`.
+///
 /// # Arguments
 ///
 /// * `f64Lambda` - The discount factor (gamma).
@@ -89,10 +407,47 @@ pub fn value_iteration(
     f64Epsilon: f64,
     opt_hm_s_hm_s_f64ProbTransExt: Option<&HashMap<String, HashMap<String, f64>>>,
 ) -> (HashMap<&'static str, f64>, HashMap<String, String>) {
+    let resultado = value_iteration_con_opciones(
+        f64Lambda,
+        f64Epsilon,
+        opt_hm_s_hm_s_f64ProbTransExt,
+        &OpcionesValueIteration::default(),
+    );
+    (resultado.hm_s_f64Valores, resultado.hm_s_sPolitica)
+}
+
+/// Performs value iteration with the optional settings in `ref_opciones`.
+///
+/// The converged values do not depend on the initialization (up to `f64Epsilon`);
+/// only the number of sweeps does.
+///
+/// # Arguments
+///
+/// * `f64Lambda` - The discount factor (gamma).
+/// * `f64Epsilon` - The convergence threshold. Iteration stops when the maximum change in value is less than this.
+/// * `opt_hm_s_hm_s_f64ProbTransExt` - An optional external transition probability model. If `None`, uses default probabilities from `config::prob_transicion()`.
+/// * `ref_opciones` - Optional settings (see `OpcionesValueIteration`).
+///
+/// # Returns
+///
+/// A `ResultadoValueIteration` with the values, the policy, the number of sweeps and whether it converged.
+pub fn value_iteration_con_opciones(
+    f64Lambda: f64,
+    f64Epsilon: f64,
+    opt_hm_s_hm_s_f64ProbTransExt: Option<&HashMap<String, HashMap<String, f64>>>,
+    ref_opciones: &OpcionesValueIteration,
+) -> ResultadoValueIteration {
     let mut hm_s_f64V: HashMap<&'static str, f64> = HashMap::new();
     let mut hm_s_sPolitica: HashMap<String, String> = HashMap::new();
+    let mut uiIteraciones = 0;
+    let mut bConvergio = true;
+    let mut eMotivoParada = MotivoParada::Convergencia;
+    let instInicio = Instant::now();
+    let mut durBarridos = Duration::ZERO;
+    let mut durPolitica = Duration::ZERO;
 
-    let hm_s_f64RecompensasMap = obtener_recompensas();
+    let hm_s_f64RecompensasMap = recompensas_con_entorno(&ref_opciones.entorno);
+    let f64RecompensaMeta = hm_s_f64RecompensasMap[ESTADO_META];
     // Initialize state values V(s) to 0 for all states.
     for arr_sFilaEstados in MAPA_ESTADOS.iter() {
         for sEstado in arr_sFilaEstados.iter() {
@@ -100,93 +455,1909 @@ pub fn value_iteration(
         }
     }
 
+    // Heuristic head start: value of following the shortest path to the goal without noise.
+    if ref_opciones.eInicializacion == InicializacionValores::DistanciaMeta {
+        for (sEstado, uiDistancia) in distancias_a_meta_con_entorno(&ref_opciones.entorno) {
+            let f64Descuento = f64Lambda.powi(uiDistancia as i32);
+            let f64CostoCamino = if (1.0 - f64Lambda).abs() < 1e-12 {
+                hm_s_f64RecompensasMap[sEstado] * uiDistancia as f64
+            } else {
+                hm_s_f64RecompensasMap[sEstado] * (1.0 - f64Descuento) / (1.0 - f64Lambda)
+            };
+            hm_s_f64V.insert(sEstado, f64CostoCamino + f64Descuento * f64RecompensaMeta);
+        }
+    }
+
     // Prepare the base transition model if no external one is provided.
-    let opt_hm_s_hm_s_f64ModeloBase: Option<HashMap<String, HashMap<String, f64>>> =
-        if opt_hm_s_hm_s_f64ProbTransExt.is_none() {
-            Some(
-                prob_transicion()
-                    .iter()
-                    .map(|(sKey, hmInnerValue)| {
-                        (
-                            sKey.to_string(),
-                            hmInnerValue.iter().map(|(sKey2, f64Value2)| (sKey2.to_string(), *f64Value2)).collect(),
-                        )
-                    })
-                    .collect(),
-            )
-        } else {
-            None
-        };
+    let hm_s_hm_s_f64ModeloBase;
+    let ref_hm_s_hm_s_f64Modelo = match opt_hm_s_hm_s_f64ProbTransExt {
+        Some(ref_hm_s_hm_s_f64ModeloExt) => ref_hm_s_hm_s_f64ModeloExt,
+        None => {
+            hm_s_hm_s_f64ModeloBase = modelo_transicion_base();
+            &hm_s_hm_s_f64ModeloBase
+        }
+    };
+
+    let eCriterio = ref_opciones.opt_eCriterio.unwrap_or(CriterioConvergencia::Absoluto(f64Epsilon));
+
+    // Consecutive sweeps whose greedy policy matched the previous sweep's
+    let mut uiBarridosPoliticaEstable = 0;
 
     let mut bCambios = true;
     // Main loop of the value iteration algorithm. Continues until convergence.
     while bCambios {
-        bCambios = false;
+        uiIteraciones += 1;
+        let instPolitica = Instant::now();
+        let hm_s_sPoliticaPrevia = ref_opciones.opt_uiBarridosPoliticaEstable.map(|_| hm_s_sPolitica.clone());
+        durPolitica += instPolitica.elapsed();
+        // Largest value change in this sweep
+        let mut f64DeltaMax: f64 = 0.0;
         // Create a new map for the updated values in this iteration.
         let mut hm_s_f64VNuevo = hm_s_f64V.clone();
 
+        // Backups: the value of every action of every state whose value is not fixed.
+        let instBarrido = Instant::now();
+        let mut vec_tpl_s_vec_ValoresAccion: Vec<(&'static str, Vec<(&'static str, f64)>)> =
+            Vec::with_capacity(FILAS_MAPA * COLUMNAS_MAPA);
         for arr_sFilaEstados in MAPA_ESTADOS.iter() {
             for sEstado in arr_sFilaEstados.iter() {
-                // Goal state value is fixed to its reward and does not change.
-                if *sEstado == ESTADO_META {
+                // Goal state value is fixed (to its reward, or to 0 if the reward is paid on entry) and does
+                // not change unless the goal is non-absorbing; a lethal danger state's is fixed to its reward.
+                if *sEstado == ESTADO_META && ref_opciones.entorno.bMetaAbsorbente {
+                    hm_s_f64VNuevo.insert(*sEstado, valor_meta(&ref_opciones.entorno, f64RecompensaMeta));
+                    continue;
+                }
+                if es_peligro_terminal(&ref_opciones.entorno, sEstado) {
                     hm_s_f64VNuevo.insert(*sEstado, *hm_s_f64RecompensasMap.get(sEstado).unwrap());
                     continue;
                 }
 
-                // Find the best action from the current state.
-                let mut f64MejorValor = f64::NEG_INFINITY;
-                let mut sMejorAccion = String::new();
+                vec_tpl_s_vec_ValoresAccion.push((
+                    *sEstado,
+                    valores_acciones(
+                        ref_opciones,
+                        ref_hm_s_hm_s_f64Modelo,
+                        &hm_s_f64RecompensasMap,
+                        sEstado,
+                        &hm_s_f64V,
+                        f64Lambda,
+                    ),
+                ));
+            }
+        }
+        durBarridos += instBarrido.elapsed();
 
-                for sAccion in acciones().iter() {
-                    // Get transition probabilities for the current action, either from external or base model.
-                    let hm_s_f64ProbAccion = match opt_hm_s_hm_s_f64ProbTransExt {
-                        Some(ref_hmOuterProbTransExt) => ref_hmOuterProbTransExt.get(&sAccion.to_string()).unwrap(),
-                        None => opt_hm_s_hm_s_f64ModeloBase
-                            .as_ref()
-                            .unwrap()
-                            .get(&sAccion.to_string())
-                            .unwrap(),
-                    };
-
-                    // Get current position of the state.
-                    let (uiFilaActual, uiColActual) = obtener_posicion(sEstado).unwrap();
-                    // Calculate the expected value sum(P(s'|s,a) * V(s')).
-                    let mut f64ValorEsperado = 0.0;
+        // Argmax: the best action of each state (the first one in `acciones()` order on ties).
+        let instPolitica = Instant::now();
+        for (sEstado, vec_tpl_s_f64ValoresAccion) in vec_tpl_s_vec_ValoresAccion {
+            let mut f64MejorValor = f64::NEG_INFINITY;
+            let mut sMejorAccion = "";
+            for (sAccion, f64ValorTotal) in vec_tpl_s_f64ValoresAccion {
+                if f64ValorTotal > f64MejorValor {
+                    f64MejorValor = f64ValorTotal;
+                    sMejorAccion = sAccion;
+                }
+            }
 
-                    for (sResultado, f64Probabilidad) in hm_s_f64ProbAccion.iter() {
-                        // Determine the resulting state if this outcome occurs.
-                        let (iNuevaFila, iNuevaCol) = mover(uiFilaActual, uiColActual, sResultado);
-                        let sEstadoDestino = match obtener_estado(iNuevaFila, iNuevaCol) {
-                            Some(sEstadoObtenido) => sEstadoObtenido,
-                            None => *sEstado, // If move is invalid (hits wall/obstacle), it stays in the current state.
-                        };
-                        f64ValorEsperado += f64Probabilidad * hm_s_f64V.get(sEstadoDestino).unwrap();
-                    }
+            hm_s_f64VNuevo.insert(sEstado, f64MejorValor);
+            f64DeltaMax = f64DeltaMax.max((hm_s_f64V[sEstado] - f64MejorValor).abs());
+
+            // Update the policy for the current state.
+            hm_s_sPolitica.insert(sEstado.to_string(), sMejorAccion.to_string());
+        }
+        durPolitica += instPolitica.elapsed();
 
-                    // Bellman equation: R(s) + lambda * sum(P(s'|s,a) * V(s')).
-                    let f64ValorTotal =
-                        hm_s_f64RecompensasMap.get(sEstado).unwrap() + f64Lambda * f64ValorEsperado;
+        // Check for convergence: keep iterating until the sweep meets the stopping rule.
+        let f64MaxAbsV = hm_s_f64VNuevo.values().fold(0.0_f64, |f64Max, f64Valor| f64Max.max(f64Valor.abs()));
+        bCambios = !eCriterio.convergio(f64DeltaMax, f64MaxAbsV);
 
-                    if f64ValorTotal > f64MejorValor {
-                        f64MejorValor = f64ValorTotal;
-                        sMejorAccion = sAccion.to_string();
-                    }
-                }
+        // Update the value map for the next iteration.
+        hm_s_f64V = hm_s_f64VNuevo;
 
-                hm_s_f64VNuevo.insert(*sEstado, f64MejorValor);
-                // Check for convergence: if change in value is greater than epsilon, continue iterating.
-                if (hm_s_f64V.get(sEstado).unwrap() - f64MejorValor).abs() > f64Epsilon {
-                    bCambios = true;
+        // Policy-stability mode: stop once the policy has not changed for the requested number of sweeps.
+        if let (Some(uiBarridosRequeridos), Some(hm_s_sPoliticaPrevia)) =
+            (ref_opciones.opt_uiBarridosPoliticaEstable, hm_s_sPoliticaPrevia)
+        {
+            let instPolitica = Instant::now();
+            let bPoliticaIgual = hm_s_sPoliticaPrevia == hm_s_sPolitica;
+            durPolitica += instPolitica.elapsed();
+            if bPoliticaIgual {
+                uiBarridosPoliticaEstable += 1;
+            } else {
+                uiBarridosPoliticaEstable = 0;
+            }
+            if bCambios && uiBarridosPoliticaEstable >= uiBarridosRequeridos {
+                log::debug!(
+                    "value_iteration: política estable durante {} barridos, parada anticipada",
+                    uiBarridosPoliticaEstable
+                );
+                bCambios = false;
+                bConvergio = false;
+                eMotivoParada = MotivoParada::PoliticaEstable;
+            }
+        }
+
+        // Anytime mode: stop at a sweep boundary once the time budget is spent.
+        if let Some(durMaxima) = ref_opciones.opt_durMaxima {
+            if bCambios && instInicio.elapsed() >= durMaxima {
+                log::warn!(
+                    "value_iteration: presupuesto de {:?} agotado sin converger ({} barridos)",
+                    durMaxima, uiIteraciones
+                );
+                bConvergio = false;
+                eMotivoParada = MotivoParada::Tiempo;
+                break;
+            }
+        }
+    }
+
+    // Seeded random tie-break among the actions within tolerance of the best one. It is applied once, to the
+    // final values, so the sweeps (and the policy-stability rule) only ever see the deterministic argmax.
+    if let Some(u64SemillaDesempate) = ref_opciones.opt_u64SemillaDesempate {
+        let instPolitica = Instant::now();
+        let mut rngDesempate = StdRng::seed_from_u64(u64SemillaDesempate);
+        let tabla_q = tabla_q_con_opciones(&hm_s_f64V, f64Lambda, Some(ref_hm_s_hm_s_f64Modelo), ref_opciones);
+        for sEstado in MAPA_ESTADOS.iter().flatten() {
+            let (Some(hm_s_f64Q), Some(ref_mut_sAccion)) = (tabla_q.get(*sEstado), hm_s_sPolitica.get_mut(*sEstado))
+            else {
+                continue;
+            };
+            let f64MejorValor = hm_s_f64Q.values().copied().fold(f64::NEG_INFINITY, f64::max);
+            let vec_sEmpatadas: Vec<&str> = acciones()
+                .iter()
+                .copied()
+                .filter(|sAccion| f64MejorValor - hm_s_f64Q[*sAccion] <= F64_TOLERANCIA_EMPATE)
+                .collect();
+            *ref_mut_sAccion = vec_sEmpatadas.choose(&mut rngDesempate).unwrap().to_string();
+        }
+        durPolitica += instPolitica.elapsed();
+    }
+
+    log::debug!(
+        "value_iteration: λ = {:.2}, {} barridos en {:?}",
+        f64Lambda,
+        uiIteraciones,
+        instInicio.elapsed()
+    );
+
+    ResultadoValueIteration {
+        hm_s_f64Valores: hm_s_f64V,
+        hm_s_sPolitica,
+        uiIteraciones,
+        bConvergio,
+        eMotivoParada,
+        durBarridos,
+        durPolitica,
+    }
+}
+
+/// Values of every action of `sEstado`, in `acciones()` order, in a Bellman backup of
+/// `value_iteration_con_opciones` against `ref_hm_s_f64V`. Each action uses the state's own model from
+/// `hm_s_ModelosPorEstado` if it has one, otherwise `ref_hm_s_hm_s_f64Modelo`; a non-absorbing goal earns
+/// its `valor_meta` instead of R(s).
+fn valores_acciones(
+    ref_opciones: &OpcionesValueIteration,
+    ref_hm_s_hm_s_f64Modelo: &ModeloTransicion,
+    ref_hm_s_f64Recompensas: &HashMap<&'static str, f64>,
+    sEstado: &str,
+    ref_hm_s_f64V: &HashMap<&'static str, f64>,
+    f64Lambda: f64,
+) -> Vec<(&'static str, f64)> {
+    let f64RecompensaMeta = ref_hm_s_f64Recompensas[ESTADO_META];
+    let f64RecompensaEstado = if sEstado == ESTADO_META {
+        valor_meta(&ref_opciones.entorno, f64RecompensaMeta)
+    } else {
+        ref_hm_s_f64Recompensas[sEstado]
+    };
+    // Terrain: a per-state model overrides the global one.
+    let ref_hm_s_hm_s_f64ModeloEstado =
+        ref_opciones.hm_s_ModelosPorEstado.get(sEstado).unwrap_or(ref_hm_s_hm_s_f64Modelo);
+
+    acciones()
+        .iter()
+        .map(|sAccion| {
+            let f64ValorTotal = valor_accion(
+                &ref_opciones.entorno,
+                sEstado,
+                sAccion,
+                &ref_hm_s_hm_s_f64ModeloEstado[*sAccion],
+                f64RecompensaEstado,
+                f64RecompensaMeta,
+                ref_hm_s_f64V,
+                f64Lambda,
+            );
+            (*sAccion, f64ValorTotal)
+        })
+        .collect()
+}
+
+/// Value of taking `sAccion` in `sEstado` in a Bellman backup of `value_iteration_con_opciones`:
+/// R(s) − expected bump penalty + expected goal entry reward + λ·Σ P(s'|s,a)·V(s'), with the failure mode,
+/// wind and movement rules of `ref_entorno` applied to the action's row of the model.
+#[allow(clippy::too_many_arguments)]
+fn valor_accion(
+    ref_entorno: &Entorno,
+    sEstado: &str,
+    sAccion: &str,
+    ref_hm_s_f64ProbAccion: &HashMap<String, f64>,
+    f64RecompensaEstado: f64,
+    f64RecompensaMeta: f64,
+    ref_hm_s_f64V: &HashMap<&'static str, f64>,
+    f64Lambda: f64,
+) -> f64 {
+    // Under `ModoFallo::SinMovimiento` the failure mass stays in place; wind cells then mix the
+    // wind's direction into the action's outcomes.
+    let hm_s_f64ProbFallo = probabilidades_con_fallo(ref_entorno, sAccion, ref_hm_s_f64ProbAccion);
+    let hm_s_f64ProbAccion = probabilidades_con_viento(ref_entorno, sEstado, &hm_s_f64ProbFallo);
+
+    // Calculate the expected value sum(P(s'|s,a) * V(s')), the expected bump penalty and the
+    // expected reward paid on entering the goal (`ModoRecompensaMeta::AlEntrar` only).
+    let mut f64ValorEsperado = 0.0;
+    let mut f64CostoChoqueEsperado = 0.0;
+    let mut f64RecompensaEntradaEsperada = 0.0;
+
+    for (sResultado, f64Probabilidad) in hm_s_f64ProbAccion.iter() {
+        // Determine the resulting state if this outcome occurs.
+        // If the move is invalid (hits wall/obstacle/blocked passage), it stays in the current state.
+        let sEstadoDestino = resolver_destino(ref_entorno, sEstado, sResultado).unwrap();
+        f64ValorEsperado += f64Probabilidad * ref_hm_s_f64V.get(sEstadoDestino).unwrap();
+        if paga_choque(sEstado, sResultado, sEstadoDestino) {
+            f64CostoChoqueEsperado += f64Probabilidad * ref_entorno.f64CostoChoque;
+        }
+        f64RecompensaEntradaEsperada +=
+            f64Probabilidad * recompensa_entrada(ref_entorno, sEstadoDestino, f64RecompensaMeta);
+    }
+
+    // Bellman equation: R(s) - bump penalty + goal entry reward + lambda * sum(P(s'|s,a) * V(s')).
+    f64RecompensaEstado - f64CostoChoqueEsperado + f64RecompensaEntradaEsperada + f64Lambda * f64ValorEsperado
+}
+
+/// Performs value iteration with a different transition model for some origin states.
+///
+/// States listed in `ref_hm_s_ModelosPorEstado` use their own model for every action (e.g. extra
+/// slippery cells next to danger); the others fall back to `opt_hm_s_hm_s_f64ProbTransExt`, or to the
+/// base model if that is `None`.
+///
+/// # Arguments
+///
+/// * `f64Lambda` - The discount factor (gamma).
+/// * `f64Epsilon` - The convergence threshold.
+/// * `ref_hm_s_ModelosPorEstado` - The per-state models, checked with `validar_modelos_por_estado`.
+/// * `opt_hm_s_hm_s_f64ProbTransExt` - The default model for unlisted states. If `None`, uses `modelo_transicion_base()`.
+///
+/// # Returns
+///
+/// A tuple with the state values and the optimal policy, as in `value_iteration`, or the errors of
+/// `validar_modelos_por_estado` (nothing is solved) if a per-state model is invalid.
+pub fn value_iteration_por_estado(
+    f64Lambda: f64,
+    f64Epsilon: f64,
+    ref_hm_s_ModelosPorEstado: &ModelosPorEstado,
+    opt_hm_s_hm_s_f64ProbTransExt: Option<&HashMap<String, HashMap<String, f64>>>,
+) -> Result<ValoresYPolitica, Vec<String>> {
+    validar_modelos_por_estado(ref_hm_s_ModelosPorEstado)?;
+    let opciones = OpcionesValueIteration {
+        hm_s_ModelosPorEstado: ref_hm_s_ModelosPorEstado.clone(),
+        ..Default::default()
+    };
+    let resultado = value_iteration_con_opciones(f64Lambda, f64Epsilon, opt_hm_s_hm_s_f64ProbTransExt, &opciones);
+    Ok((resultado.hm_s_f64Valores, resultado.hm_s_sPolitica))
+}
+
+/// Checks per-state transition models before solving with them.
+///
+/// Every model must define all of the `acciones()`, and each action's probabilities must sum to 1.
+/// States that are not on the map are ignored by the solver and only logged here.
+///
+/// # Returns
+///
+/// `Ok(())`, or `Err` with one message per problem, states in name order.
+pub fn validar_modelos_por_estado(ref_hm_s_ModelosPorEstado: &ModelosPorEstado) -> Result<(), Vec<String>> {
+    let mut vec_sErrores: Vec<String> = Vec::new();
+    let mut vec_sEstados: Vec<&String> = ref_hm_s_ModelosPorEstado.keys().collect();
+    vec_sEstados.sort();
+    for sEstado in vec_sEstados {
+        if obtener_posicion(sEstado).is_none() {
+            log::warn!("Modelo de transición para un estado que no está en el mapa: {}", sEstado);
+        }
+        for sAccion in acciones() {
+            let Some(hm_s_f64Prob) = ref_hm_s_ModelosPorEstado[sEstado].get(sAccion) else {
+                vec_sErrores.push(format!("El modelo del estado {} no define la acción {}", sEstado, sAccion));
+                continue;
+            };
+            let f64Suma: f64 = hm_s_f64Prob.values().sum();
+            if (f64Suma - 1.0).abs() > F64_TOLERANCIA_SUMA_MODELO {
+                vec_sErrores.push(format!(
+                    "Las probabilidades de {} en el estado {} suman {} (deben sumar 1)",
+                    sAccion, sEstado, f64Suma
+                ));
+            }
+        }
+    }
+
+    if vec_sErrores.is_empty() {
+        Ok(())
+    } else {
+        Err(vec_sErrores)
+    }
+}
+
+/// Runs value iteration until convergence or until `durMaxima` has elapsed, whichever comes first.
+///
+/// The clock is checked between sweeps, so the last sweep may overrun the budget slightly.
+/// Useful for re-solving interactively on large maps; `value_iteration` is unaffected.
+///
+/// # Arguments
+///
+/// * `f64Lambda` - The discount factor (gamma).
+/// * `f64Epsilon` - The convergence threshold.
+/// * `opt_hm_s_hm_s_f64ProbTransExt` - An optional external transition probability model.
+/// * `durMaxima` - The wall-clock budget.
+///
+/// # Returns
+///
+/// A `ResultadoValueIteration` with the best values/policy found; `bConvergio` tells whether it converged.
+pub fn value_iteration_con_tiempo(
+    f64Lambda: f64,
+    f64Epsilon: f64,
+    opt_hm_s_hm_s_f64ProbTransExt: Option<&HashMap<String, HashMap<String, f64>>>,
+    durMaxima: Duration,
+) -> ResultadoValueIteration {
+    let opciones = OpcionesValueIteration {
+        opt_durMaxima: Some(durMaxima),
+        ..Default::default()
+    };
+    value_iteration_con_opciones(f64Lambda, f64Epsilon, opt_hm_s_hm_s_f64ProbTransExt, &opciones)
+}
+
+/// Timing breakdown of a solve, as returned by `value_iteration_perfilado`.
+#[derive(Debug, Clone, Copy)]
+pub struct PerfilValueIteration {
+    /// Time spent in the Bellman backups of the sweeps (`ResultadoValueIteration::durBarridos`).
+    pub durBarridos: Duration,
+    /// Time spent on the argmax and policy bookkeeping of the solve (`ResultadoValueIteration::durPolitica`).
+    pub durPolitica: Duration,
+    /// Number of sweeps performed.
+    pub uiBarridos: usize,
+    /// Average duration of one sweep (`durBarridos / uiBarridos`).
+    pub durPromedioBarrido: Duration,
+}
+
+/// Runs `value_iteration_con_opciones` and measures where the time goes.
+///
+/// Both phases are timed inside the solve itself, under `ref_opciones`: the backups of every sweep and the
+/// argmax and policy bookkeeping that follows each one (including the `opt_u64SemillaDesempate` pass).
+/// Comparing `durPromedioBarrido` across map sizes shows whether the sweeps are expensive enough to be worth
+/// parallelizing.
+///
+/// # Arguments
+///
+/// * `f64Lambda` - The discount factor (gamma).
+/// * `f64Epsilon` - The convergence threshold.
+/// * `opt_hm_s_hm_s_f64ProbTransExt` - An optional external transition probability model.
+/// * `ref_opciones` - Optional settings (see `OpcionesValueIteration`).
+///
+/// # Returns
+///
+/// The `ResultadoValueIteration` of the solve and its `PerfilValueIteration`.
+pub fn value_iteration_perfilado(
+    f64Lambda: f64,
+    f64Epsilon: f64,
+    opt_hm_s_hm_s_f64ProbTransExt: Option<&HashMap<String, HashMap<String, f64>>>,
+    ref_opciones: &OpcionesValueIteration,
+) -> (ResultadoValueIteration, PerfilValueIteration) {
+    let resultado = value_iteration_con_opciones(f64Lambda, f64Epsilon, opt_hm_s_hm_s_f64ProbTransExt, ref_opciones);
+    let (durBarridos, uiBarridos) = (resultado.durBarridos, resultado.uiIteraciones);
+    let perfil = PerfilValueIteration {
+        durBarridos,
+        durPolitica: resultado.durPolitica,
+        uiBarridos,
+        durPromedioBarrido: durBarridos / uiBarridos.max(1) as u32,
+    };
+    (resultado, perfil)
+}
+
+/// Re-solves after a local change (a cell's reward or movement rules), starting from the previous solution.
+///
+/// Instead of sweeping the whole map, this runs prioritized sweeping: the changed states and the states
+/// that can move into them are queued by Bellman error |backup(s) − V(s)|, the worst one is backed up,
+/// and its predecessors are re-queued. Only the part of the map the change actually reaches is updated.
+/// It stops once no state has an error above ε·(1−λ), so every value is within `f64Epsilon` of the fixed
+/// point, as with a cold `value_iteration` solve. The policy is then extracted greedily over all states
+/// (first action in `acciones()` order on ties).
+///
+/// Backups are those of `value_iteration_con_opciones` under `ref_opciones.entorno` and
+/// `ref_opciones.hm_s_ModelosPorEstado`, so both converge to the same values and policy. The other
+/// settings of `ref_opciones` (initialization, time budget, stopping rule, seeded tie-break and
+/// policy-stable stop) only apply to the full sweeps and are ignored here.
+///
+/// The previous solution is given as state values; a Q-table can be turned into values with its row maxima,
+/// and `tabla_q_desde_valores` goes the other way.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_f64ValoresPrevios` - The values of the previous solve (as returned by `value_iteration`).
+///   States missing from it start at 0.
+/// * `ref_arr_sModificados` - The states whose reward or transitions changed.
+/// * `f64Lambda` - The discount factor (gamma); must be below 1.
+/// * `f64Epsilon` - Maximum distance of each value from the fixed point.
+/// * `opt_hm_s_hm_s_f64ProbTransExt` - An optional external transition probability model. If `None`, uses `modelo_transicion_base()`.
+/// * `ref_opciones` - The environment and per-state models to solve with (see `OpcionesValueIteration`).
+///
+/// # Returns
+///
+/// A `ResultadoValueIteration`; here `uiIteraciones` counts single-state backups, not sweeps.
+///
+/// # Panics
+///
+/// If `f64Lambda` is not below 1 (the error bound needs a discount).
+pub fn resolver_incremental(
+    ref_hm_s_f64ValoresPrevios: &HashMap<&'static str, f64>,
+    ref_arr_sModificados: &[&str],
+    f64Lambda: f64,
+    f64Epsilon: f64,
+    opt_hm_s_hm_s_f64ProbTransExt: Option<&HashMap<String, HashMap<String, f64>>>,
+    ref_opciones: &OpcionesValueIteration,
+) -> ResultadoValueIteration {
+    assert!(f64Lambda < 1.0, "resolver_incremental requiere λ < 1, recibido {}", f64Lambda);
+    let instInicio = Instant::now();
+    let hm_s_hm_s_f64ModeloBase;
+    let ref_hm_s_hm_s_f64Modelo = match opt_hm_s_hm_s_f64ProbTransExt {
+        Some(ref_hm_s_hm_s_f64ModeloExt) => ref_hm_s_hm_s_f64ModeloExt,
+        None => {
+            hm_s_hm_s_f64ModeloBase = modelo_transicion_base();
+            &hm_s_hm_s_f64ModeloBase
+        }
+    };
+    let ref_entorno = &ref_opciones.entorno;
+    let hm_s_f64Recompensas = recompensas_con_entorno(ref_entorno);
+    let f64RecompensaMeta = hm_s_f64Recompensas[ESTADO_META];
+    // Terrain: a per-state model overrides the global one.
+    let modelo_de = |sEstado: &str| ref_opciones.hm_s_ModelosPorEstado.get(sEstado).unwrap_or(ref_hm_s_hm_s_f64Modelo);
+    // States whose value is fixed: an absorbing goal and the lethal dangers.
+    let es_fijo = |sEstado: &str| {
+        (sEstado == ESTADO_META && ref_entorno.bMetaAbsorbente) || es_peligro_terminal(ref_entorno, sEstado)
+    };
+
+    let mut hm_s_f64V: HashMap<&'static str, f64> = MAPA_ESTADOS
+        .iter()
+        .flatten()
+        .map(|&sEstado| (sEstado, ref_hm_s_f64ValoresPrevios.get(sEstado).copied().unwrap_or(0.0)))
+        .collect();
+
+    // Predecessors: the states with some action outcome landing in each state, after the failure mode,
+    // wind and movement rules of the environment (fixed states have no outgoing transitions).
+    let mut hm_s_vec_sPredecesores: HashMap<&'static str, Vec<&'static str>> = HashMap::new();
+    for &sOrigen in MAPA_ESTADOS.iter().flatten().filter(|sEstado| !es_fijo(sEstado)) {
+        for sAccion in acciones().iter() {
+            let hm_s_f64ProbFallo = probabilidades_con_fallo(ref_entorno, sAccion, &modelo_de(sOrigen)[*sAccion]);
+            let hm_s_f64ProbAccion = probabilidades_con_viento(ref_entorno, sOrigen, &hm_s_f64ProbFallo);
+            for (sResultado, f64Probabilidad) in hm_s_f64ProbAccion.iter() {
+                if *f64Probabilidad <= 0.0 {
+                    continue;
                 }
+                let sDestino = resolver_destino(ref_entorno, sOrigen, sResultado).unwrap();
+                let vec_sOrigenes = hm_s_vec_sPredecesores.entry(sDestino).or_default();
+                if !vec_sOrigenes.contains(&sOrigen) {
+                    vec_sOrigenes.push(sOrigen);
+                }
+            }
+        }
+    }
 
-                // Update the policy for the current state.
-                hm_s_sPolitica.insert(sEstado.to_string(), sMejorAccion);
+    // Bellman backup, as in `value_iteration_con_opciones`: (best value, best action); fixed states keep
+    // their value and have no action.
+    let respaldo = |hm_s_f64V: &HashMap<&'static str, f64>, sEstado: &'static str| -> (f64, Option<String>) {
+        if sEstado == ESTADO_META && ref_entorno.bMetaAbsorbente {
+            return (valor_meta(ref_entorno, f64RecompensaMeta), None);
+        }
+        if es_peligro_terminal(ref_entorno, sEstado) {
+            return (hm_s_f64Recompensas[sEstado], None);
+        }
+        let f64RecompensaEstado = if sEstado == ESTADO_META {
+            valor_meta(ref_entorno, f64RecompensaMeta)
+        } else {
+            hm_s_f64Recompensas[sEstado]
+        };
+        let mut tpl_f64_sMejor = (f64::NEG_INFINITY, None);
+        for sAccion in acciones().iter() {
+            let f64ValorTotal = valor_accion(
+                ref_entorno,
+                sEstado,
+                sAccion,
+                &modelo_de(sEstado)[*sAccion],
+                f64RecompensaEstado,
+                f64RecompensaMeta,
+                hm_s_f64V,
+                f64Lambda,
+            );
+            if f64ValorTotal > tpl_f64_sMejor.0 {
+                tpl_f64_sMejor = (f64ValorTotal, Some(sAccion.to_string()));
             }
         }
+        tpl_f64_sMejor
+    };
 
-        // Update the value map for the next iteration.
-        hm_s_f64V = hm_s_f64VNuevo;
+    // A residual of θ everywhere bounds the distance to the fixed point by θ / (1 − λ).
+    let f64Umbral = f64Epsilon * (1.0 - f64Lambda);
+    // Errors are non-negative, so their bit patterns order like the values and can key the max-heap.
+    let mut bh_tpl_Pendientes: BinaryHeap<(u64, &'static str)> = BinaryHeap::new();
+    let mut hs_sSemilla: HashSet<&'static str> = HashSet::new();
+    for sModificado in ref_arr_sModificados {
+        match obtener_posicion(sModificado) {
+            Some((uiFila, uiCol)) => {
+                let sEstado = MAPA_ESTADOS[uiFila][uiCol];
+                hs_sSemilla.insert(sEstado);
+                hs_sSemilla.extend(hm_s_vec_sPredecesores.get(sEstado).into_iter().flatten());
+            }
+            None => log::warn!("Estado fuera del mapa en resolver_incremental: {}", sModificado),
+        }
+    }
+    for sEstado in hs_sSemilla {
+        let f64Error = (respaldo(&hm_s_f64V, sEstado).0 - hm_s_f64V[sEstado]).abs();
+        if f64Error > f64Umbral {
+            bh_tpl_Pendientes.push((f64Error.to_bits(), sEstado));
+        }
     }
 
-    (hm_s_f64V, hm_s_sPolitica)
+    let instRespaldos = Instant::now();
+    let mut uiRespaldos = 0;
+    while let Some((_, sEstado)) = bh_tpl_Pendientes.pop() {
+        // Entries go stale when the state was already backed up through another one.
+        let (f64Valor, _) = respaldo(&hm_s_f64V, sEstado);
+        if (f64Valor - hm_s_f64V[sEstado]).abs() <= f64Umbral {
+            continue;
+        }
+        hm_s_f64V.insert(sEstado, f64Valor);
+        uiRespaldos += 1;
+
+        for &sPredecesor in hm_s_vec_sPredecesores.get(sEstado).into_iter().flatten() {
+            let f64Error = (respaldo(&hm_s_f64V, sPredecesor).0 - hm_s_f64V[sPredecesor]).abs();
+            if f64Error > f64Umbral {
+                bh_tpl_Pendientes.push((f64Error.to_bits(), sPredecesor));
+            }
+        }
+    }
+
+    let durBarridos = instRespaldos.elapsed();
+
+    let instPolitica = Instant::now();
+    let hm_s_sPolitica: HashMap<String, String> = MAPA_ESTADOS
+        .iter()
+        .flatten()
+        .filter_map(|&sEstado| respaldo(&hm_s_f64V, sEstado).1.map(|sAccion| (sEstado.to_string(), sAccion)))
+        .collect();
+    let durPolitica = instPolitica.elapsed();
+
+    log::debug!(
+        "resolver_incremental: λ = {:.2}, {} respaldos en {:?}",
+        f64Lambda,
+        uiRespaldos,
+        instInicio.elapsed()
+    );
+
+    ResultadoValueIteration {
+        hm_s_f64Valores: hm_s_f64V,
+        hm_s_sPolitica,
+        uiIteraciones: uiRespaldos,
+        bConvergio: true,
+        eMotivoParada: MotivoParada::Convergencia,
+        durBarridos,
+        durPolitica,
+    }
+}
+
+/// Computes the reverse transitions of the model: which (state, action) pairs can lead into each state.
+///
+/// The transitions are those of `value_iteration_con_opciones` under `ref_opciones`: each origin uses its
+/// model from `hm_s_ModelosPorEstado` if it has one, the failure mode and wind of the `Entorno` are applied to
+/// the action's outcomes, and every outcome is resolved with `entorno::resolver_destino` (walls, one-way
+/// passages, teleporters, edited cells). An outcome that leaves the map or is blocked keeps the agent in
+/// place, so it shows up as a self-loop of the origin. Outcomes of the same action that land in the same
+/// state are merged into one entry with their probabilities added. Origins are all cells but those whose
+/// value the solver fixes (an absorbing goal and lethal danger states); obstacles are included, as
+/// `value_iteration` assigns them values too. Entries are listed in map order of the origin, then
+/// `acciones()` order.
+///
+/// # Arguments
+///
+/// * `opt_hm_s_hm_s_f64ProbTransExt` - An optional external transition probability model. If `None`, uses `modelo_transicion_base()`.
+/// * `ref_opciones` - The solver options whose rules and per-state models the transitions follow.
+///
+/// # Returns
+///
+/// A `HashMap` from each reachable state to its `(estado_origen, accion, probabilidad)` entries.
+/// Outcomes with probability 0 are left out.
+pub fn predecesores(
+    opt_hm_s_hm_s_f64ProbTransExt: Option<&HashMap<String, HashMap<String, f64>>>,
+    ref_opciones: &OpcionesValueIteration,
+) -> HashMap<String, Vec<(String, String, f64)>> {
+    let hm_s_hm_s_f64ModeloBase;
+    let ref_hm_s_hm_s_f64Modelo = match opt_hm_s_hm_s_f64ProbTransExt {
+        Some(ref_hm_s_hm_s_f64ModeloExt) => ref_hm_s_hm_s_f64ModeloExt,
+        None => {
+            hm_s_hm_s_f64ModeloBase = modelo_transicion_base();
+            &hm_s_hm_s_f64ModeloBase
+        }
+    };
+
+    let ref_entorno = &ref_opciones.entorno;
+    // States whose value is fixed have no outgoing transitions.
+    let es_fijo = |sEstado: &str| {
+        (sEstado == ESTADO_META && ref_entorno.bMetaAbsorbente) || es_peligro_terminal(ref_entorno, sEstado)
+    };
+
+    let mut hm_s_vec_tpl_Predecesores: HashMap<String, Vec<(String, String, f64)>> = HashMap::new();
+    for &sOrigen in MAPA_ESTADOS.iter().flatten().filter(|sEstado| !es_fijo(sEstado)) {
+        // Terrain: a per-state model overrides the global one.
+        let ref_hm_s_hm_s_f64ModeloOrigen =
+            ref_opciones.hm_s_ModelosPorEstado.get(sOrigen).unwrap_or(ref_hm_s_hm_s_f64Modelo);
+        for sAccion in acciones().iter() {
+            let hm_s_f64ProbFallo =
+                probabilidades_con_fallo(ref_entorno, sAccion, &ref_hm_s_hm_s_f64ModeloOrigen[*sAccion]);
+            let hm_s_f64ProbAccion = probabilidades_con_viento(ref_entorno, sOrigen, &hm_s_f64ProbFallo);
+            // Destination -> probability, kept in first-seen order for a stable listing.
+            let mut vec_tpl_s_f64Destinos: Vec<(&'static str, f64)> = Vec::new();
+            for (sResultado, f64Probabilidad) in hm_s_f64ProbAccion.iter() {
+                if *f64Probabilidad <= 0.0 {
+                    continue;
+                }
+                let sDestino = resolver_destino(ref_entorno, sOrigen, sResultado).unwrap();
+                match vec_tpl_s_f64Destinos.iter_mut().find(|(sVisto, _)| *sVisto == sDestino) {
+                    Some((_, f64Acumulada)) => *f64Acumulada += f64Probabilidad,
+                    None => vec_tpl_s_f64Destinos.push((sDestino, *f64Probabilidad)),
+                }
+            }
+            for (sDestino, f64Probabilidad) in vec_tpl_s_f64Destinos {
+                hm_s_vec_tpl_Predecesores.entry(sDestino.to_string()).or_default().push((
+                    sOrigen.to_string(),
+                    sAccion.to_string(),
+                    f64Probabilidad,
+                ));
+            }
+        }
+    }
+    hm_s_vec_tpl_Predecesores
+}
+
+/// Solves the MDP by prioritized sweeping instead of full sweeps.
+///
+/// Starts from V = 0 with every state queued, then always backs up the state with the largest Bellman
+/// error and re-queues its predecessors (see `resolver_incremental`, which this is with no previous
+/// solution). It reaches the same fixed point as `value_iteration`, within `f64Epsilon`, usually with
+/// fewer backups: compare `uiIteraciones` here with `uiIteraciones × (cells − 1)` of `value_iteration_con_opciones`,
+/// which backs up every cell but the goal on each sweep.
+///
+/// # Arguments
+///
+/// * `f64Lambda` - The discount factor (gamma); must be below 1.
+/// * `f64Epsilon` - Maximum distance of each value from the fixed point.
+/// * `opt_hm_s_hm_s_f64ProbTransExt` - An optional external transition probability model. If `None`, uses `modelo_transicion_base()`.
+/// * `ref_opciones` - The environment and per-state models to solve with (see `resolver_incremental`).
+///
+/// # Returns
+///
+/// A `ResultadoValueIteration`; `uiIteraciones` counts single-state backups.
+///
+/// # Panics
+///
+/// If `f64Lambda` is not below 1.
+pub fn prioritized_sweeping(
+    f64Lambda: f64,
+    f64Epsilon: f64,
+    opt_hm_s_hm_s_f64ProbTransExt: Option<&HashMap<String, HashMap<String, f64>>>,
+    ref_opciones: &OpcionesValueIteration,
+) -> ResultadoValueIteration {
+    let vec_sEstados: Vec<&str> = MAPA_ESTADOS.iter().flatten().copied().collect();
+    resolver_incremental(
+        &HashMap::new(),
+        &vec_sEstados,
+        f64Lambda,
+        f64Epsilon,
+        opt_hm_s_hm_s_f64ProbTransExt,
+        ref_opciones,
+    )
+}
+
+/// Resolves the state reached by taking an action with no noise.
+///
+/// If the move leaves the map or hits an obstacle, the agent stays in `sEstado`.
+///
+/// # Arguments
+///
+/// * `sEstado` - The origin state.
+/// * `sAccion` - The action taken ("N", "S", "E", "O").
+///
+/// # Returns
+///
+/// The destination state name, or `None` if `sEstado` is not on the map.
+pub fn siguiente_estado_determinista(sEstado: &str, sAccion: &str) -> Option<&'static str> {
+    resolver_destino(&Entorno::default(), sEstado, sAccion)
+}
+
+/// A move of the robot, typed counterpart of the action names used by policies and models.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Accion {
+    /// "N": one row up.
+    Norte,
+    /// "S": one row down.
+    Sur,
+    /// "E": one column right.
+    Este,
+    /// "O": one column left.
+    Oeste,
+}
+
+impl Accion {
+    /// Parses an action name ("N", "S", "E", "O"); any other string gives `None`.
+    pub fn desde_str(sAccion: &str) -> Option<Accion> {
+        match sAccion {
+            "N" => Some(Accion::Norte),
+            "S" => Some(Accion::Sur),
+            "E" => Some(Accion::Este),
+            "O" => Some(Accion::Oeste),
+            _ => None,
+        }
+    }
+
+    /// Returns the action's name, as found in `acciones()`, policies and transition models.
+    pub fn como_str(self) -> &'static str {
+        match self {
+            Accion::Norte => "N",
+            Accion::Sur => "S",
+            Accion::Este => "E",
+            Accion::Oeste => "O",
+        }
+    }
+}
+
+/// Looks up the action a solved policy takes in one state.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_sPolitica` - The policy (State -> Action), e.g. from `value_iteration`.
+/// * `sEstado` - The state to query.
+///
+/// # Returns
+///
+/// The typed action, or `None` for the goal and obstacles (whatever the policy holds for them: the solver
+/// writes an entry for every map cell), for states the policy has no action for, and for unknown action names.
+pub fn accion_optima(ref_hm_s_sPolitica: &HashMap<String, String>, sEstado: &str) -> Option<Accion> {
+    if sEstado == ESTADO_META || OBSTACULOS.contains(&sEstado) {
+        return None;
+    }
+    ref_hm_s_sPolitica.get(sEstado).and_then(|sAccion| Accion::desde_str(sAccion))
+}
+
+/// Returns the most likely state reached by taking an action under a transition model.
+///
+/// Each direction the action can end up moving in is resolved with `siguiente_estado_determinista`, and
+/// directions leading to the same state add up (e.g. two slips into walls both leave the robot in place).
+/// Ties go to the state reached first in `acciones()` order.
+///
+/// # Arguments
+///
+/// * `sEstado` - The origin state.
+/// * `eAccion` - The action taken.
+/// * `opt_hm_s_hm_s_f64ProbTransExt` - An optional external transition probability model. If `None`, uses `modelo_transicion_base()`.
+///
+/// # Returns
+///
+/// The name of the most likely next state; `sEstado` itself if it is not on the map.
+pub fn siguiente_estado_probable(
+    sEstado: &str,
+    eAccion: Accion,
+    opt_hm_s_hm_s_f64ProbTransExt: Option<&ModeloTransicion>,
+) -> String {
+    let hm_s_hm_s_f64ModeloBase;
+    let ref_hm_s_hm_s_f64Modelo = match opt_hm_s_hm_s_f64ProbTransExt {
+        Some(ref_hm_s_hm_s_f64ModeloExt) => ref_hm_s_hm_s_f64ModeloExt,
+        None => {
+            hm_s_hm_s_f64ModeloBase = modelo_transicion_base();
+            &hm_s_hm_s_f64ModeloBase
+        }
+    };
+    let Some(ref_hm_s_f64ProbAccion) = ref_hm_s_hm_s_f64Modelo.get(eAccion.como_str()) else {
+        return sEstado.to_string();
+    };
+
+    // Probability of each destination, in the order first reached.
+    let mut vec_tpl_s_f64Destinos: Vec<(&'static str, f64)> = Vec::new();
+    for sDireccion in acciones() {
+        let Some(f64Prob) = ref_hm_s_f64ProbAccion.get(sDireccion) else {
+            continue;
+        };
+        let Some(sDestino) = siguiente_estado_determinista(sEstado, sDireccion) else {
+            return sEstado.to_string();
+        };
+        match vec_tpl_s_f64Destinos.iter_mut().find(|(sVisto, _)| *sVisto == sDestino) {
+            Some((_, f64Acumulada)) => *f64Acumulada += f64Prob,
+            None => vec_tpl_s_f64Destinos.push((sDestino, *f64Prob)),
+        }
+    }
+    vec_tpl_s_f64Destinos
+        .iter()
+        .fold(None, |opt_tpl_Mejor: Option<(&str, f64)>, &(sDestino, f64Prob)| match opt_tpl_Mejor {
+            Some((_, f64Mejor)) if f64Prob <= f64Mejor => opt_tpl_Mejor,
+            _ => Some((sDestino, f64Prob)),
+        })
+        .map_or_else(|| sEstado.to_string(), |(sDestino, _)| sDestino.to_string())
+}
+
+/// Lists the states reachable from `sEstado` in one noise-free move on the plain map, with the action
+/// that reaches each.
+///
+/// Same as `entorno::vecinos_con_entorno` with `Entorno::default()`: in-bounds, non-obstacle cells
+/// four-adjacent to `sEstado`.
+///
+/// # Arguments
+///
+/// * `sEstado` - The origin state.
+///
+/// # Returns
+///
+/// `(neighbor, action)` pairs in `acciones()` order; empty if `sEstado` is not on the map.
+pub fn vecinos(sEstado: &str) -> Vec<(String, String)> {
+    vecinos_con_entorno(&Entorno::default(), sEstado)
+        .into_iter()
+        .map(|(sVecino, sAccion)| (sVecino.to_string(), sAccion.to_string()))
+        .collect()
+}
+
+/// Checks whether following the policy deterministically can get stuck in a loop that never reaches the goal.
+///
+/// From every non-obstacle state, the policy action is applied with no noise (its most likely
+/// destination) until the goal is reached or a state repeats. A repeated state closes a cycle;
+/// bumping into a wall forever counts as a cycle of length one.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_sPolitica` - The policy (State -> Action) to check.
+///
+/// # Returns
+///
+/// `Some(cycle)` with the states of the first cycle found (in visiting order), or `None`
+/// if every state's path reaches the goal or a state without a policy entry.
+pub fn politica_tiene_ciclo_sin_meta(ref_hm_s_sPolitica: &HashMap<String, String>) -> Option<Vec<String>> {
+    // States already known to end at the goal (or at a state without action)
+    let mut hs_sSinCiclo: HashSet<&'static str> = HashSet::new();
+
+    for arr_sFilaEstados in MAPA_ESTADOS.iter() {
+        for &sInicio in arr_sFilaEstados.iter() {
+            if OBSTACULOS.contains(&sInicio) || hs_sSinCiclo.contains(sInicio) {
+                continue;
+            }
+
+            let (vec_sCamino, eFin) =
+                recorrer_politica(ref_hm_s_sPolitica, sInicio, |sEstado| hs_sSinCiclo.contains(sEstado));
+            if let FinRecorrido::Ciclo(uiInicioCiclo) = eFin {
+                return Some(vec_sCamino[uiInicioCiclo..].iter().map(|s| s.to_string()).collect());
+            }
+            hs_sSinCiclo.extend(vec_sCamino);
+        }
+    }
+    None
+}
+
+/// Checks whether a policy can farm the goal reward by looping through a non-absorbing goal.
+///
+/// Same as `detectar_farmeo_meta_con_entorno` with the default `Entorno`, whose goal is absorbing;
+/// pass an `Entorno` with `bMetaAbsorbente = false` there to check a continuing-task policy.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_sPolitica` - The policy (State -> Action).
+/// * `opt_hm_s_hm_s_f64ProbTransExt` - An optional external transition probability model. If `None`, uses `modelo_transicion_base()`.
+///
+/// # Returns
+///
+/// `true` if the policy-induced chain has a positive-reward cycle through the goal.
+pub fn detectar_farmeo_meta(
+    ref_hm_s_sPolitica: &HashMap<String, String>,
+    opt_hm_s_hm_s_f64ProbTransExt: Option<&ModeloTransicion>,
+) -> bool {
+    detectar_farmeo_meta_con_entorno(ref_hm_s_sPolitica, opt_hm_s_hm_s_f64ProbTransExt, &Entorno::default())
+}
+
+/// Checks whether a policy can farm the goal reward by looping through a non-absorbing goal.
+///
+/// The chain induced by the policy has an edge s -> s' for every outcome of π(s) with positive
+/// probability (wind included), weighted with the reward of that step as the solver counts it: R(s)
+/// (the goal's from `entorno::valor_meta`), minus `f64CostoChoque` on a bump, plus the goal entry reward.
+/// Lethal danger states (`entorno::es_peligro_terminal`) end the episode and have no outgoing edge.
+/// A farm is a closed walk from the goal back to it (staying put included) with positive total reward,
+/// found with a Bellman-Ford pass over walks of at most one step per state. When one is found it is
+/// reported with `log::warn!`. An absorbing goal has no policy entry, so it never farms.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_sPolitica` - The policy (State -> Action), e.g. solved with a non-absorbing goal.
+/// * `opt_hm_s_hm_s_f64ProbTransExt` - An optional external transition probability model. If `None`, uses `modelo_transicion_base()`.
+/// * `ref_entorno` - The movement and reward rules the policy was solved with.
+///
+/// # Returns
+///
+/// `true` if the policy-induced chain has a positive-reward cycle through the goal.
+pub fn detectar_farmeo_meta_con_entorno(
+    ref_hm_s_sPolitica: &HashMap<String, String>,
+    opt_hm_s_hm_s_f64ProbTransExt: Option<&ModeloTransicion>,
+    ref_entorno: &Entorno,
+) -> bool {
+    let hm_s_hm_s_f64ModeloBase;
+    let ref_hm_s_hm_s_f64Modelo = match opt_hm_s_hm_s_f64ProbTransExt {
+        Some(ref_hm_s_hm_s_f64ModeloExt) => ref_hm_s_hm_s_f64ModeloExt,
+        None => {
+            hm_s_hm_s_f64ModeloBase = modelo_transicion_base();
+            &hm_s_hm_s_f64ModeloBase
+        }
+    };
+    let hm_s_f64Recompensas = recompensas_con_entorno(ref_entorno);
+    let f64RecompensaMeta = hm_s_f64Recompensas[ESTADO_META];
+
+    // Weighted edges of the policy-induced chain: (origin, destination, reward of the step)
+    let mut vec_tpl_s_s_f64Aristas: Vec<(&'static str, &'static str, f64)> = Vec::new();
+    for sEstado in MAPA_ESTADOS.iter().flatten() {
+        if OBSTACULOS.contains(sEstado) || es_peligro_terminal(ref_entorno, sEstado) {
+            continue;
+        }
+        let Some(sAccion) = ref_hm_s_sPolitica.get(*sEstado) else {
+            continue;
+        };
+        let f64RecompensaEstado = if *sEstado == ESTADO_META {
+            valor_meta(ref_entorno, f64RecompensaMeta)
+        } else {
+            hm_s_f64Recompensas[sEstado]
+        };
+        let hm_s_f64ProbFallo = probabilidades_con_fallo(ref_entorno, sAccion, &ref_hm_s_hm_s_f64Modelo[sAccion]);
+        let hm_s_f64ProbAccion = probabilidades_con_viento(ref_entorno, sEstado, &hm_s_f64ProbFallo);
+        for (sDireccion, f64Prob) in hm_s_f64ProbAccion.iter() {
+            if *f64Prob <= 0.0 {
+                continue;
+            }
+            let sDestino = resolver_destino(ref_entorno, sEstado, sDireccion).unwrap();
+            let f64CostoChoque =
+                if paga_choque(sEstado, sDireccion, sDestino) { ref_entorno.f64CostoChoque } else { 0.0 };
+            let f64Paso =
+                f64RecompensaEstado - f64CostoChoque + recompensa_entrada(ref_entorno, sDestino, f64RecompensaMeta);
+            vec_tpl_s_s_f64Aristas.push((sEstado, sDestino, f64Paso));
+        }
+    }
+
+    // Best reward of a walk that leaves the goal and reaches each state without passing through the goal again
+    let mut hm_s_f64Mejor: HashMap<&str, f64> = HashMap::new();
+    for (sOrigen, sDestino, f64Paso) in &vec_tpl_s_s_f64Aristas {
+        if *sOrigen == ESTADO_META && *sDestino != ESTADO_META {
+            let f64Actual = hm_s_f64Mejor.entry(sDestino).or_insert(f64::NEG_INFINITY);
+            *f64Actual = f64Actual.max(*f64Paso);
+        }
+    }
+    for _ in 0..MAPA_ESTADOS.iter().flatten().count() {
+        let mut bCambios = false;
+        for (sOrigen, sDestino, f64Paso) in &vec_tpl_s_s_f64Aristas {
+            if *sOrigen == ESTADO_META || *sDestino == ESTADO_META {
+                continue;
+            }
+            let Some(f64Origen) = hm_s_f64Mejor.get(sOrigen).copied() else {
+                continue;
+            };
+            let f64Actual = hm_s_f64Mejor.entry(sDestino).or_insert(f64::NEG_INFINITY);
+            if f64Origen + f64Paso > *f64Actual {
+                *f64Actual = f64Origen + f64Paso;
+                bCambios = true;
+            }
+        }
+        if !bCambios {
+            break;
+        }
+    }
+
+    // Close the walk with an edge back into the goal
+    let f64MejorCiclo = vec_tpl_s_s_f64Aristas
+        .iter()
+        .filter(|(_, sDestino, _)| *sDestino == ESTADO_META)
+        .filter_map(|(sOrigen, _, f64Paso)| {
+            if *sOrigen == ESTADO_META {
+                Some(*f64Paso)
+            } else {
+                hm_s_f64Mejor.get(sOrigen).map(|f64Camino| f64Camino + f64Paso)
+            }
+        })
+        .fold(f64::NEG_INFINITY, f64::max);
+    if f64MejorCiclo > 0.0 {
+        log::warn!(
+            "La política puede farmear la recompensa de la meta: ciclo por {} con recompensa {:.3}",
+            ESTADO_META,
+            f64MejorCiclo
+        );
+        return true;
+    }
+    false
+}
+
+/// Lists the policy as directed edges `(origin, destination)`, for graph libraries (petgraph, networkx).
+///
+/// Each non-obstacle state with a policy entry contributes one edge to the cell its action reaches with
+/// no noise (`siguiente_estado_determinista`, the most likely outcome). A move that hits the map edge or
+/// an obstacle is kept as an explicit self-loop `(s, s)`, so strongly connected components of the graph
+/// are exactly the policy's traps and the goal's basin can be found by reachability. States without a
+/// policy entry (the goal, unless it is non-absorbing) have no outgoing edge.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_sPolitica` - The policy (State -> Action).
+///
+/// # Returns
+///
+/// One edge per state, in map order.
+pub fn politica_como_aristas(ref_hm_s_sPolitica: &HashMap<String, String>) -> Vec<(String, String)> {
+    MAPA_ESTADOS
+        .iter()
+        .flatten()
+        .filter(|sEstado| !OBSTACULOS.contains(*sEstado))
+        .filter_map(|sEstado| {
+            let sAccion = ref_hm_s_sPolitica.get(*sEstado)?;
+            let sDestino = siguiente_estado_determinista(sEstado, sAccion)?;
+            Some((sEstado.to_string(), sDestino.to_string()))
+        })
+        .collect()
+}
+
+/// Lists the states whose policy action pushes against the map edge or an obstacle.
+///
+/// A state is listed when the noise-free destination of its action (`siguiente_estado_determinista`)
+/// is the state itself, i.e. the self-loops of `politica_como_aristas`. An optimal policy only does
+/// this when every real move looks worse than waiting by bumping (e.g. to let noise carry the robot
+/// away from a neighboring danger), which often points to a reward or connectivity problem.
+/// Obstacles are skipped.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_sPolitica` - The policy (State -> Action).
+///
+/// # Returns
+///
+/// The states found, in map order; empty if no action bumps.
+pub fn estados_contra_pared(ref_hm_s_sPolitica: &HashMap<String, String>) -> Vec<String> {
+    politica_como_aristas(ref_hm_s_sPolitica)
+        .into_iter()
+        .filter(|(sOrigen, sDestino)| sOrigen == sDestino)
+        .map(|(sOrigen, _)| sOrigen)
+        .collect()
+}
+
+/// Reports the goal each state ends up at when following the policy deterministically.
+///
+/// Paths are followed as in `politica_tiene_ciclo_sin_meta`. The set of states sharing a goal is
+/// that goal's basin of attraction under the policy. The map currently has a single goal
+/// (`ESTADO_META`), so every state either maps to it or to `None`.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_sPolitica` - The policy (State -> Action) to follow.
+///
+/// # Returns
+///
+/// For every non-obstacle state, `Some(goal)` if its path reaches a goal, or `None` if it loops
+/// or stops at a state without a policy entry.
+pub fn meta_objetivo_por_estado(ref_hm_s_sPolitica: &HashMap<String, String>) -> HashMap<String, Option<String>> {
+    let mut hm_s_opt_sMetas: HashMap<&'static str, Option<&'static str>> = HashMap::new();
+
+    for &sInicio in MAPA_ESTADOS.iter().flatten() {
+        if OBSTACULOS.contains(&sInicio) || hm_s_opt_sMetas.contains_key(sInicio) {
+            continue;
+        }
+        let (vec_sCamino, eFin) =
+            recorrer_politica(ref_hm_s_sPolitica, sInicio, |sEstado| hm_s_opt_sMetas.contains_key(sEstado));
+        let opt_sMeta = match eFin {
+            FinRecorrido::Meta(sMeta) => Some(sMeta),
+            FinRecorrido::Conocido(sEstado) => hm_s_opt_sMetas[sEstado],
+            FinRecorrido::SinAccion | FinRecorrido::Ciclo(_) => None,
+        };
+        if let FinRecorrido::Meta(sMeta) = eFin {
+            hm_s_opt_sMetas.insert(sMeta, Some(sMeta));
+        }
+        for sEstado in vec_sCamino {
+            hm_s_opt_sMetas.insert(sEstado, opt_sMeta);
+        }
+    }
+
+    hm_s_opt_sMetas
+        .into_iter()
+        .map(|(sEstado, opt_sMeta)| (sEstado.to_string(), opt_sMeta.map(str::to_string)))
+        .collect()
+}
+
+/// How a deterministic walk of `recorrer_politica` ended.
+enum FinRecorrido {
+    /// Reached this goal.
+    Meta(&'static str),
+    /// Reached a state the caller already knows the outcome of.
+    Conocido(&'static str),
+    /// Reached a state without a policy entry.
+    SinAccion,
+    /// Repeated a state; the cycle starts at this index of the path.
+    Ciclo(usize),
+}
+
+/// Follows the policy with no noise from `sInicio` until a goal, a state accepted by `fn_bConocido`,
+/// a state without action or a repeated state. Returns the states visited before stopping (goal and
+/// known states excluded) and how the walk ended.
+fn recorrer_politica(
+    ref_hm_s_sPolitica: &HashMap<String, String>,
+    sInicio: &'static str,
+    fn_bConocido: impl Fn(&str) -> bool,
+) -> (Vec<&'static str>, FinRecorrido) {
+    let mut vec_sCamino: Vec<&'static str> = Vec::new();
+    let mut sActual = sInicio;
+    loop {
+        if sActual == ESTADO_META {
+            return (vec_sCamino, FinRecorrido::Meta(sActual));
+        }
+        if fn_bConocido(sActual) {
+            return (vec_sCamino, FinRecorrido::Conocido(sActual));
+        }
+        if let Some(uiInicioCiclo) = vec_sCamino.iter().position(|s| *s == sActual) {
+            return (vec_sCamino, FinRecorrido::Ciclo(uiInicioCiclo));
+        }
+        vec_sCamino.push(sActual);
+
+        let sSiguiente = ref_hm_s_sPolitica
+            .get(sActual)
+            .and_then(|sAccion| siguiente_estado_determinista(sActual, sAccion));
+        match sSiguiente {
+            Some(sSiguienteEstado) => sActual = sSiguienteEstado,
+            None => return (vec_sCamino, FinRecorrido::SinAccion), // No action for this state: the path ends here
+        }
+    }
+}
+
+/// Computes the action values Q(s, a) = R(s) + λ·Σ P(s'|s,a)·V(s') implied by state values.
+///
+/// The goal and obstacles are skipped, matching the states `value_iteration` chooses actions for
+/// and the layout of `learning::tabla_q_inicial`, so the result can be used wherever a learned
+/// Q-table is expected (e.g. `learning::brecha_q`).
+///
+/// # Arguments
+///
+/// * `ref_hm_s_f64Valores` - The state values (as returned by `value_iteration`).
+/// * `f64Lambda` - The discount factor (gamma) the values were computed with.
+/// * `opt_hm_s_hm_s_f64ProbTransExt` - An optional external transition probability model. If `None`, uses `modelo_transicion_base()`.
+///
+/// # Returns
+///
+/// A `TablaQ` (state -> action -> value).
+pub fn tabla_q_desde_valores(
+    ref_hm_s_f64Valores: &HashMap<&'static str, f64>,
+    f64Lambda: f64,
+    opt_hm_s_hm_s_f64ProbTransExt: Option<&HashMap<String, HashMap<String, f64>>>,
+) -> TablaQ {
+    let hm_s_hm_s_f64ModeloBase;
+    let ref_hm_s_hm_s_f64Modelo = match opt_hm_s_hm_s_f64ProbTransExt {
+        Some(ref_hm_s_hm_s_f64ModeloExt) => ref_hm_s_hm_s_f64ModeloExt,
+        None => {
+            hm_s_hm_s_f64ModeloBase = modelo_transicion_base();
+            &hm_s_hm_s_f64ModeloBase
+        }
+    };
+    let hm_s_f64Recompensas = obtener_recompensas();
+
+    MAPA_ESTADOS
+        .iter()
+        .flatten()
+        .filter(|sEstado| **sEstado != ESTADO_META && !OBSTACULOS.contains(*sEstado))
+        .map(|&sEstado| {
+            let hm_s_f64Q = acciones()
+                .iter()
+                .map(|sAccion| {
+                    let f64ValorEsperado: f64 = ref_hm_s_hm_s_f64Modelo[*sAccion]
+                        .iter()
+                        .map(|(sResultado, f64Probabilidad)| {
+                            let sEstadoDestino = siguiente_estado_determinista(sEstado, sResultado).unwrap();
+                            f64Probabilidad * ref_hm_s_f64Valores[sEstadoDestino]
+                        })
+                        .sum();
+                    (sAccion.to_string(), hm_s_f64Recompensas[sEstado] + f64Lambda * f64ValorEsperado)
+                })
+                .collect();
+            (sEstado.to_string(), hm_s_f64Q)
+        })
+        .collect()
+}
+
+/// Computes the action values implied by state values under the same rules as `value_iteration_con_opciones`.
+///
+/// Unlike `tabla_q_desde_valores`, every backup goes through the solver's own: the movement rules, rewards
+/// and failure mode of `ref_opciones.entorno` and the per-state models of `hm_s_ModelosPorEstado`. Only the
+/// states the solver chooses actions for are listed: obstacles (including edited ones), an absorbing goal
+/// and lethal danger states are skipped.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_f64Valores` - The state values (as returned by `value_iteration_con_opciones`).
+/// * `f64Lambda` - The discount factor (gamma) the values were computed with.
+/// * `opt_hm_s_hm_s_f64ProbTransExt` - An optional external transition probability model. If `None`, uses `modelo_transicion_base()`.
+/// * `ref_opciones` - The options the values were computed with.
+///
+/// # Returns
+///
+/// A `TablaQ` (state -> action -> value).
+pub fn tabla_q_con_opciones(
+    ref_hm_s_f64Valores: &HashMap<&'static str, f64>,
+    f64Lambda: f64,
+    opt_hm_s_hm_s_f64ProbTransExt: Option<&HashMap<String, HashMap<String, f64>>>,
+    ref_opciones: &OpcionesValueIteration,
+) -> TablaQ {
+    let hm_s_hm_s_f64ModeloBase;
+    let ref_hm_s_hm_s_f64Modelo = match opt_hm_s_hm_s_f64ProbTransExt {
+        Some(ref_hm_s_hm_s_f64ModeloExt) => ref_hm_s_hm_s_f64ModeloExt,
+        None => {
+            hm_s_hm_s_f64ModeloBase = modelo_transicion_base();
+            &hm_s_hm_s_f64ModeloBase
+        }
+    };
+    let hm_s_f64Recompensas = recompensas_con_entorno(&ref_opciones.entorno);
+
+    MAPA_ESTADOS
+        .iter()
+        .flatten()
+        .filter(|sEstado| {
+            !(**sEstado == ESTADO_META && ref_opciones.entorno.bMetaAbsorbente)
+                && tipo_celda_con_entorno(&ref_opciones.entorno, sEstado) != TipoCelda::Obstaculo
+                && !es_peligro_terminal(&ref_opciones.entorno, sEstado)
+        })
+        .map(|&sEstado| {
+            let hm_s_f64Q = valores_acciones(
+                ref_opciones,
+                ref_hm_s_hm_s_f64Modelo,
+                &hm_s_f64Recompensas,
+                sEstado,
+                ref_hm_s_f64Valores,
+                f64Lambda,
+            )
+            .into_iter()
+            .map(|(sAccion, f64Valor)| (sAccion.to_string(), f64Valor))
+            .collect();
+            (sEstado.to_string(), hm_s_f64Q)
+        })
+        .collect()
+}
+
+/// Measures how far a value function is from satisfying the Bellman optimality equation.
+///
+/// Computes max over states of |V(s) − max_a Q(s, a)|, with Q from `tabla_q_con_opciones` under the same
+/// options the values were solved with, so the states whose value is fixed (an absorbing goal, lethal
+/// dangers) and obstacles are skipped. After `value_iteration_con_opciones` stops with threshold ε the
+/// residual is at most λ·ε, so this is a self-contained correctness check for the backup code.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_f64Valores` - The state values to check (as returned by `value_iteration_con_opciones`).
+/// * `f64Lambda` - The discount factor (gamma) the values were computed with.
+/// * `opt_hm_s_hm_s_f64ProbTransExt` - An optional external transition probability model. If `None`, uses `modelo_transicion_base()`.
+/// * `ref_opciones` - The options the values were computed with (`OpcionesValueIteration::default()` for
+///   `value_iteration`).
+///
+/// # Returns
+///
+/// The largest absolute Bellman residual.
+pub fn residual_bellman(
+    ref_hm_s_f64Valores: &HashMap<&'static str, f64>,
+    f64Lambda: f64,
+    opt_hm_s_hm_s_f64ProbTransExt: Option<&HashMap<String, HashMap<String, f64>>>,
+    ref_opciones: &OpcionesValueIteration,
+) -> f64 {
+    tabla_q_con_opciones(ref_hm_s_f64Valores, f64Lambda, opt_hm_s_hm_s_f64ProbTransExt, ref_opciones)
+        .iter()
+        .map(|(sEstado, hm_s_f64Q)| {
+            let f64MejorValor = hm_s_f64Q.values().copied().fold(f64::NEG_INFINITY, f64::max);
+            (ref_hm_s_f64Valores[sEstado.as_str()] - f64MejorValor).abs()
+        })
+        .fold(0.0, f64::max)
+}
+
+/// Computes the values of following a fixed policy (iterative policy evaluation).
+///
+/// Repeats V(s) = R(s) + λ·Σ P(s'|s,π(s))·V(s') over the map until the largest change in a sweep is at
+/// most `f64Epsilon`. The goal's value is fixed to its reward, as in `value_iteration`; states without
+/// a policy entry keep a value of 0. Evaluating a policy under a model other than the one it was solved
+/// with measures what it is worth there.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_sPolitica` - The policy (State -> Action) to evaluate.
+/// * `f64Lambda` - The discount factor (gamma); must be below 1.
+/// * `f64Epsilon` - The convergence threshold.
+/// * `opt_hm_s_hm_s_f64ProbTransExt` - An optional external transition probability model. If `None`, uses `modelo_transicion_base()`.
+///
+/// # Returns
+///
+/// The value of every map state under the policy.
+///
+/// # Panics
+///
+/// If `f64Lambda` is not below 1 (an improper policy would never converge).
+pub fn evaluar_politica(
+    ref_hm_s_sPolitica: &HashMap<String, String>,
+    f64Lambda: f64,
+    f64Epsilon: f64,
+    opt_hm_s_hm_s_f64ProbTransExt: Option<&HashMap<String, HashMap<String, f64>>>,
+) -> HashMap<&'static str, f64> {
+    assert!(f64Lambda < 1.0, "evaluar_politica requiere λ < 1, recibido {}", f64Lambda);
+    let hm_s_hm_s_f64ModeloBase;
+    let ref_hm_s_hm_s_f64Modelo = match opt_hm_s_hm_s_f64ProbTransExt {
+        Some(ref_hm_s_hm_s_f64ModeloExt) => ref_hm_s_hm_s_f64ModeloExt,
+        None => {
+            hm_s_hm_s_f64ModeloBase = modelo_transicion_base();
+            &hm_s_hm_s_f64ModeloBase
+        }
+    };
+    let hm_s_f64Recompensas = obtener_recompensas();
+
+    let mut hm_s_f64V: HashMap<&'static str, f64> =
+        MAPA_ESTADOS.iter().flatten().map(|&sEstado| (sEstado, 0.0)).collect();
+    hm_s_f64V.insert(ESTADO_META, hm_s_f64Recompensas[ESTADO_META]);
+
+    loop {
+        let mut f64DeltaMax: f64 = 0.0;
+        let mut hm_s_f64VNuevo = hm_s_f64V.clone();
+        for &sEstado in MAPA_ESTADOS.iter().flatten() {
+            let Some(sAccion) = ref_hm_s_sPolitica.get(sEstado) else {
+                continue;
+            };
+            if sEstado == ESTADO_META {
+                continue;
+            }
+            let f64ValorEsperado: f64 = ref_hm_s_hm_s_f64Modelo[sAccion]
+                .iter()
+                .map(|(sResultado, f64Probabilidad)| {
+                    f64Probabilidad * hm_s_f64V[siguiente_estado_determinista(sEstado, sResultado).unwrap()]
+                })
+                .sum();
+            let f64Valor = hm_s_f64Recompensas[sEstado] + f64Lambda * f64ValorEsperado;
+            f64DeltaMax = f64DeltaMax.max((f64Valor - hm_s_f64V[sEstado]).abs());
+            hm_s_f64VNuevo.insert(sEstado, f64Valor);
+        }
+        hm_s_f64V = hm_s_f64VNuevo;
+        if f64DeltaMax <= f64Epsilon {
+            break;
+        }
+    }
+    hm_s_f64V
+}
+
+/// Summarizes state values as one number: the expected value of the start state, Σ d(s)·V(s).
+///
+/// With `None` the start state is uniform over the non-goal, non-obstacle states
+/// (`learning::estados_iniciales_validos`), the same starts the simulations use. A given distribution
+/// is normalized to sum 1, and its states without a value are ignored (with a warning).
+///
+/// # Arguments
+///
+/// * `ref_hm_s_f64Valores` - The state values (as returned by `value_iteration`).
+/// * `opt_hm_s_f64Distribucion` - Optional weight of each start state.
+///
+/// # Returns
+///
+/// The expected value, or 0 if the distribution has no weight on states with a value.
+pub fn retorno_esperado(
+    ref_hm_s_f64Valores: &HashMap<&'static str, f64>,
+    opt_hm_s_f64Distribucion: Option<&HashMap<String, f64>>,
+) -> f64 {
+    let vec_tpl_f64f64Pesos: Vec<(f64, f64)> = match opt_hm_s_f64Distribucion {
+        None => estados_iniciales_validos()
+            .into_iter()
+            .filter_map(|sEstado| ref_hm_s_f64Valores.get(sEstado).map(|f64Valor| (1.0, *f64Valor)))
+            .collect(),
+        Some(hm_s_f64Distribucion) => hm_s_f64Distribucion
+            .iter()
+            .filter_map(|(sEstado, f64Peso)| match ref_hm_s_f64Valores.get(sEstado.as_str()) {
+                Some(f64Valor) => Some((*f64Peso, *f64Valor)),
+                None => {
+                    log::warn!("Estado sin valor en la distribución inicial: {}", sEstado);
+                    None
+                }
+            })
+            .collect(),
+    };
+    let f64PesoTotal: f64 = vec_tpl_f64f64Pesos.iter().map(|(f64Peso, _)| f64Peso).sum();
+    if f64PesoTotal <= 0.0 {
+        return 0.0;
+    }
+    vec_tpl_f64f64Pesos.iter().map(|(f64Peso, f64Valor)| f64Peso * f64Valor).sum::<f64>() / f64PesoTotal
+}
+
+/// Convergence threshold used by `sensibilidad_recompensa`, tight enough for differences of small `delta`s.
+const F64_UMBRAL_SENSIBILIDAD: f64 = 1e-6;
+
+/// Finite-difference sensitivity of the optimal values to one state's reward.
+///
+/// Same as `sensibilidad_recompensa_con_entorno` with `Entorno::default()` (the default rewards).
+pub fn sensibilidad_recompensa(sEstadoObjetivo: &str, f64Delta: f64, f64Lambda: f64) -> HashMap<String, f64> {
+    sensibilidad_recompensa_con_entorno(&Entorno::default(), sEstadoObjetivo, f64Delta, f64Lambda)
+}
+
+/// Finite-difference sensitivity of the optimal values to one state's reward, under the rules of `ref_entorno`.
+///
+/// Solves once with the rewards of `ref_entorno` and once with a copy of it whose overrides raise
+/// `sEstadoObjetivo`'s reward by `f64Delta`; no shared state is modified. Dividing the result by
+/// `f64Delta` approximates dV(s)/dR(objetivo); large entries mark the states whose valuation depends
+/// most on that cell.
+///
+/// # Arguments
+///
+/// * `ref_entorno` - The rules (and reward overrides) of the base solve.
+/// * `sEstadoObjetivo` - The state whose reward is perturbed.
+/// * `f64Delta` - The reward perturbation.
+/// * `f64Lambda` - The discount factor (gamma).
+///
+/// # Returns
+///
+/// The change V_perturbed(s) - V_base(s) for every state, or an empty map if `sEstadoObjetivo` is not on the map.
+pub fn sensibilidad_recompensa_con_entorno(
+    ref_entorno: &Entorno,
+    sEstadoObjetivo: &str,
+    f64Delta: f64,
+    f64Lambda: f64,
+) -> HashMap<String, f64> {
+    let Some(f64RecompensaBase) = recompensas_con_entorno(ref_entorno).get(sEstadoObjetivo).copied() else {
+        log::warn!("Estado fuera del mapa en el análisis de sensibilidad: {}", sEstadoObjetivo);
+        return HashMap::new();
+    };
+    let opcionesBase = OpcionesValueIteration {
+        entorno: ref_entorno.clone(),
+        ..Default::default()
+    };
+    let hm_s_f64ValoresBase =
+        value_iteration_con_opciones(f64Lambda, F64_UMBRAL_SENSIBILIDAD, None, &opcionesBase).hm_s_f64Valores;
+
+    let mut opcionesPerturbadas = opcionesBase;
+    opcionesPerturbadas
+        .entorno
+        .hm_s_f64RecompensasPersonalizadas
+        .insert(sEstadoObjetivo.to_string(), f64RecompensaBase + f64Delta);
+    let hm_s_f64ValoresPerturbados =
+        value_iteration_con_opciones(f64Lambda, F64_UMBRAL_SENSIBILIDAD, None, &opcionesPerturbadas).hm_s_f64Valores;
+
+    hm_s_f64ValoresBase
+        .iter()
+        .map(|(sEstado, f64ValorBase)| (sEstado.to_string(), hm_s_f64ValoresPerturbados[sEstado] - f64ValorBase))
+        .collect()
+}
+
+/// Values of each (state, remaining fuel) pair, as returned by `value_iteration_con_combustible`.
+pub type ValoresConCombustible = HashMap<(&'static str, usize), f64>;
+/// Optimal action for each (state, remaining fuel) pair, as returned by `value_iteration_con_combustible`.
+pub type PoliticaConCombustible = HashMap<(String, usize), String>;
+
+/// Solves the MDP with a finite fuel budget, augmenting each state with the remaining fuel.
+///
+/// Every move consumes one unit of fuel. A state with fuel 0 (other than the goal) is terminal
+/// with value 0, and the goal keeps its reward at any fuel level. Because fuel strictly decreases,
+/// the values are computed exactly by backward induction from fuel 0 up to `uiCombustibleMax`,
+/// without a convergence threshold. With little fuel left the policy favors shorter routes.
+///
+/// This is separate from `value_iteration`, which remains the default (unlimited fuel) solver.
+///
+/// # Arguments
+///
+/// * `f64Lambda` - The discount factor (gamma).
+/// * `uiCombustibleMax` - The initial fuel F; states are solved for fuel 0..=F.
+/// * `opt_hm_s_hm_s_f64ProbTransExt` - An optional external transition probability model. If `None`, uses `modelo_transicion_base()`.
+///
+/// # Returns
+///
+/// A tuple containing:
+///   - `ValoresConCombustible`: Values of each (state, fuel) pair.
+///   - `PoliticaConCombustible`: The optimal action for each non-terminal (state, fuel) pair.
+pub fn value_iteration_con_combustible(
+    f64Lambda: f64,
+    uiCombustibleMax: usize,
+    opt_hm_s_hm_s_f64ProbTransExt: Option<&HashMap<String, HashMap<String, f64>>>,
+) -> (ValoresConCombustible, PoliticaConCombustible) {
+    let hm_s_hm_s_f64ModeloBase;
+    let ref_hm_s_hm_s_f64Modelo = match opt_hm_s_hm_s_f64ProbTransExt {
+        Some(ref_hm_s_hm_s_f64ModeloExt) => ref_hm_s_hm_s_f64ModeloExt,
+        None => {
+            hm_s_hm_s_f64ModeloBase = modelo_transicion_base();
+            &hm_s_hm_s_f64ModeloBase
+        }
+    };
+
+    let hm_s_f64RecompensasMap = obtener_recompensas();
+    let mut hm_tpl_s_ui_f64V: ValoresConCombustible = HashMap::new();
+    let mut hm_tpl_s_ui_sPolitica: PoliticaConCombustible = HashMap::new();
+
+    for uiCombustible in 0..=uiCombustibleMax {
+        for arr_sFilaEstados in MAPA_ESTADOS.iter() {
+            for &sEstado in arr_sFilaEstados.iter() {
+                if OBSTACULOS.contains(&sEstado) {
+                    continue;
+                }
+                // The goal keeps its reward whatever fuel is left.
+                if sEstado == ESTADO_META {
+                    hm_tpl_s_ui_f64V.insert((sEstado, uiCombustible), hm_s_f64RecompensasMap[sEstado]);
+                    continue;
+                }
+                // Out of fuel: terminal with no reward.
+                if uiCombustible == 0 {
+                    hm_tpl_s_ui_f64V.insert((sEstado, 0), 0.0);
+                    continue;
+                }
+
+                let (uiFilaActual, uiColActual) = obtener_posicion(sEstado).unwrap();
+                let mut f64MejorValor = f64::NEG_INFINITY;
+                let mut sMejorAccion = String::new();
+
+                for sAccion in acciones().iter() {
+                    let hm_s_f64ProbAccion = ref_hm_s_hm_s_f64Modelo.get(*sAccion).unwrap();
+                    let mut f64ValorEsperado = 0.0;
+                    for (sResultado, f64Probabilidad) in hm_s_f64ProbAccion.iter() {
+                        let (iNuevaFila, iNuevaCol) = mover(uiFilaActual, uiColActual, sResultado);
+                        let sEstadoDestino = obtener_estado(iNuevaFila, iNuevaCol).unwrap_or(sEstado);
+                        // Values for fuel - 1 were computed in the previous pass.
+                        f64ValorEsperado +=
+                            f64Probabilidad * hm_tpl_s_ui_f64V[&(sEstadoDestino, uiCombustible - 1)];
+                    }
+
+                    let f64ValorTotal = hm_s_f64RecompensasMap[sEstado] + f64Lambda * f64ValorEsperado;
+                    if f64ValorTotal > f64MejorValor {
+                        f64MejorValor = f64ValorTotal;
+                        sMejorAccion = sAccion.to_string();
+                    }
+                }
+
+                hm_tpl_s_ui_f64V.insert((sEstado, uiCombustible), f64MejorValor);
+                hm_tpl_s_ui_sPolitica.insert((sEstado.to_string(), uiCombustible), sMejorAccion);
+            }
+        }
+    }
+
+    (hm_tpl_s_ui_f64V, hm_tpl_s_ui_sPolitica)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accion_optima_es_none_en_obstaculos_y_meta() {
+        let (_, hm_s_sPolitica) = value_iteration(0.9, 0.001, None);
+        for sObstaculo in OBSTACULOS.iter() {
+            assert_eq!(accion_optima(&hm_s_sPolitica, sObstaculo), None, "obstáculo {}", sObstaculo);
+        }
+        assert_eq!(accion_optima(&hm_s_sPolitica, ESTADO_META), None);
+        assert!(accion_optima(&hm_s_sPolitica, "S0").is_some());
+
+        // Even a policy that explicitly holds an action for them gives None.
+        let hm_s_sPoliticaForzada: HashMap<String, String> = OBSTACULOS
+            .iter()
+            .chain(std::iter::once(&ESTADO_META))
+            .map(|sEstado| (sEstado.to_string(), "E".to_string()))
+            .collect();
+        for sEstado in OBSTACULOS.iter().chain(std::iter::once(&ESTADO_META)) {
+            assert_eq!(accion_optima(&hm_s_sPoliticaForzada, sEstado), None, "estado {}", sEstado);
+        }
+    }
+
+    /// An environment that exercises every rule the backups depend on.
+    fn entorno_con_reglas() -> Entorno {
+        Entorno {
+            hs_tpl_s_sMovimientosBloqueados: HashSet::from([("S22".to_string(), "E".to_string())]),
+            hs_tpl_s_sParedes: HashSet::from([("S21".to_string(), "S14".to_string())]),
+            hm_s_sTeletransportadores: HashMap::from([("S0".to_string(), "S35".to_string())]),
+            opt_hs_sPeligrosLetales: Some(HashSet::from(["P2".to_string()])),
+            hm_s_tpl_s_f64Viento: HashMap::from([("S28".to_string(), ("O".to_string(), 0.3))]),
+            f64CostoChoque: 0.5,
+            eModoFallo: crate::entorno::ModoFallo::SinMovimiento,
+            ..Default::default()
+        }
+    }
+
+    /// Asserts that two policies agree up to ties: in every state, the action each one picks is worth the
+    /// same (within `f64Tolerancia`) under `ref_hm_s_f64Valores`, `opciones.entorno` and the base model.
+    fn assert_politicas_equivalentes(
+        ref_hm_s_sPoliticaA: &HashMap<String, String>,
+        ref_hm_s_sPoliticaB: &HashMap<String, String>,
+        ref_hm_s_f64Valores: &HashMap<&'static str, f64>,
+        f64Lambda: f64,
+        ref_opciones: &OpcionesValueIteration,
+        f64Tolerancia: f64,
+    ) {
+        let mut vec_sEstadosA: Vec<&String> = ref_hm_s_sPoliticaA.keys().collect();
+        let mut vec_sEstadosB: Vec<&String> = ref_hm_s_sPoliticaB.keys().collect();
+        vec_sEstadosA.sort();
+        vec_sEstadosB.sort();
+        assert_eq!(vec_sEstadosA, vec_sEstadosB);
+
+        let hm_s_hm_s_f64ModeloBase = modelo_transicion_base();
+        let hm_s_f64Recompensas = recompensas_con_entorno(&ref_opciones.entorno);
+        let valor = |sEstado: &str, sAccion: &str| {
+            let ref_hm_s_hm_s_f64Modelo =
+                ref_opciones.hm_s_ModelosPorEstado.get(sEstado).unwrap_or(&hm_s_hm_s_f64ModeloBase);
+            let f64RecompensaMeta = hm_s_f64Recompensas[ESTADO_META];
+            let f64RecompensaEstado = if sEstado == ESTADO_META {
+                valor_meta(&ref_opciones.entorno, f64RecompensaMeta)
+            } else {
+                hm_s_f64Recompensas[sEstado]
+            };
+            valor_accion(
+                &ref_opciones.entorno,
+                sEstado,
+                sAccion,
+                &ref_hm_s_hm_s_f64Modelo[sAccion],
+                f64RecompensaEstado,
+                f64RecompensaMeta,
+                ref_hm_s_f64Valores,
+                f64Lambda,
+            )
+        };
+        for (sEstado, sAccionA) in ref_hm_s_sPoliticaA {
+            let sAccionB = &ref_hm_s_sPoliticaB[sEstado];
+            let (f64ValorA, f64ValorB) = (valor(sEstado, sAccionA), valor(sEstado, sAccionB));
+            assert!(
+                (f64ValorA - f64ValorB).abs() < f64Tolerancia,
+                "{}: {} vale {} y {} vale {}",
+                sEstado,
+                sAccionA,
+                f64ValorA,
+                sAccionB,
+                f64ValorB
+            );
+        }
+    }
+
+    #[test]
+    fn vecinos_coincide_con_mover_y_obtener_estado() {
+        for (uiFila, arr_sFilaEstados) in MAPA_ESTADOS.iter().enumerate() {
+            for (uiCol, sEstado) in arr_sFilaEstados.iter().enumerate() {
+                let vec_tpl_s_sEsperados: Vec<(String, String)> = acciones()
+                    .into_iter()
+                    .filter_map(|sAccion| {
+                        let (iFila, iCol) = mover(uiFila, uiCol, sAccion);
+                        obtener_estado(iFila, iCol).map(|sVecino| (sVecino.to_string(), sAccion.to_string()))
+                    })
+                    .collect();
+                assert_eq!(vecinos(sEstado), vec_tpl_s_sEsperados, "estado {}", sEstado);
+            }
+        }
+    }
+
+    #[test]
+    fn distancias_respetan_paredes_y_pasos_de_un_sentido() {
+        // S22 is next to the goal; a wall between them and a one-way block out of S24 force detours.
+        let entorno = Entorno {
+            hs_tpl_s_sParedes: HashSet::from([("S22".to_string(), "M".to_string())]),
+            hs_tpl_s_sMovimientosBloqueados: HashSet::from([("S24".to_string(), "O".to_string())]),
+            ..Default::default()
+        };
+        let hm_s_uiDistancias = distancias_a_meta_con_entorno(&entorno);
+        assert_eq!(distancias_a_meta()["S22"], 1);
+        assert_eq!(distancias_a_meta()["S24"], 1);
+        assert!(hm_s_uiDistancias["S22"] > 1);
+        assert!(hm_s_uiDistancias["S24"] > 1);
+        // Other entries into the goal are unaffected.
+        assert_eq!(hm_s_uiDistancias["S28"], 1);
+        assert_eq!(distancias_a_meta_con_entorno(&Entorno::default()), distancias_a_meta());
+    }
+
+    #[test]
+    fn inicializacion_distancia_meta_converge_igual_y_antes() {
+        let f64Lambda = 0.98;
+        let f64Epsilon = 1e-9;
+        for entorno in [Entorno::default(), entorno_con_reglas()] {
+            let opcionesCero = OpcionesValueIteration {
+                entorno,
+                ..Default::default()
+            };
+            let opcionesDistancia = OpcionesValueIteration {
+                eInicializacion: InicializacionValores::DistanciaMeta,
+                ..opcionesCero.clone()
+            };
+            let resultadoCero = value_iteration_con_opciones(f64Lambda, f64Epsilon, None, &opcionesCero);
+            let resultadoDistancia = value_iteration_con_opciones(f64Lambda, f64Epsilon, None, &opcionesDistancia);
+
+            for (sEstado, f64Valor) in &resultadoCero.hm_s_f64Valores {
+                let f64ValorDistancia = resultadoDistancia.hm_s_f64Valores[sEstado];
+                assert!(
+                    (f64Valor - f64ValorDistancia).abs() < 1e-6,
+                    "{}: {} vs {}",
+                    sEstado,
+                    f64Valor,
+                    f64ValorDistancia
+                );
+            }
+            assert_politicas_equivalentes(
+                &resultadoCero.hm_s_sPolitica,
+                &resultadoDistancia.hm_s_sPolitica,
+                &resultadoCero.hm_s_f64Valores,
+                f64Lambda,
+                &opcionesCero,
+                1e-6,
+            );
+            assert!(
+                resultadoDistancia.uiIteraciones < resultadoCero.uiIteraciones,
+                "DistanciaMeta: {} barridos, Cero: {}",
+                resultadoDistancia.uiIteraciones,
+                resultadoCero.uiIteraciones
+            );
+        }
+    }
+
+    #[test]
+    fn residual_bellman_tras_value_iteration_bajo_epsilon() {
+        let f64Epsilon = 0.001;
+        let opcionesConReglas = OpcionesValueIteration {
+            entorno: entorno_con_reglas(),
+            hm_s_ModelosPorEstado: HashMap::from([(
+                "S29".to_string(),
+                crate::robustness::construir_modelo_ruido(0.25, 0.5, 0.25),
+            )]),
+            ..Default::default()
+        };
+        for opciones in [OpcionesValueIteration::default(), opcionesConReglas] {
+            for f64Lambda in [0.86, 0.9, 0.94, 0.98] {
+                for opt_hm_s_hm_s_f64Modelo in [None, Some(crate::robustness::construir_modelo_ruido(0.2, 0.6, 0.2))] {
+                    let opt_ref_hm_s_hm_s_f64Modelo = opt_hm_s_hm_s_f64Modelo.as_ref();
+                    let hm_s_f64Valores =
+                        value_iteration_con_opciones(f64Lambda, f64Epsilon, opt_ref_hm_s_hm_s_f64Modelo, &opciones)
+                            .hm_s_f64Valores;
+                    let f64Residual =
+                        residual_bellman(&hm_s_f64Valores, f64Lambda, opt_ref_hm_s_hm_s_f64Modelo, &opciones);
+                    assert!(f64Residual < f64Epsilon, "λ = {}: residual {}", f64Lambda, f64Residual);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parada_por_politica_estable_no_cuenta_como_convergencia() {
+        let opciones = OpcionesValueIteration {
+            opt_uiBarridosPoliticaEstable: Some(3),
+            ..Default::default()
+        };
+        let resultado = value_iteration_con_opciones(0.98, 1e-12, None, &opciones);
+        assert_eq!(resultado.eMotivoParada, MotivoParada::PoliticaEstable);
+        assert!(!resultado.bConvergio);
+
+        let resultado = value_iteration_con_opciones(0.9, 0.001, None, &OpcionesValueIteration::default());
+        assert_eq!(resultado.eMotivoParada, MotivoParada::Convergencia);
+        assert!(resultado.bConvergio);
+    }
+
+    #[test]
+    fn parada_por_politica_estable_con_semilla_de_desempate() {
+        let opcionesSinSemilla = OpcionesValueIteration {
+            opt_uiBarridosPoliticaEstable: Some(3),
+            ..Default::default()
+        };
+        let opciones = OpcionesValueIteration {
+            opt_u64SemillaDesempate: Some(7),
+            ..opcionesSinSemilla.clone()
+        };
+        // The random tie-break must not keep the policy from ever looking stable.
+        let resultado = value_iteration_con_opciones(0.98, 1e-12, None, &opciones);
+        let resultadoSinSemilla = value_iteration_con_opciones(0.98, 1e-12, None, &opcionesSinSemilla);
+        assert_eq!(resultado.eMotivoParada, MotivoParada::PoliticaEstable);
+        assert_eq!(resultado.uiIteraciones, resultadoSinSemilla.uiIteraciones);
+        assert_eq!(resultado.hm_s_sPolitica, value_iteration_con_opciones(0.98, 1e-12, None, &opciones).hm_s_sPolitica);
+
+        // Every drawn action is tied with the best one under the final values.
+        let tabla_q = tabla_q_con_opciones(&resultado.hm_s_f64Valores, 0.98, None, &opciones);
+        for (sEstado, hm_s_f64Q) in &tabla_q {
+            let f64MejorValor = hm_s_f64Q.values().copied().fold(f64::NEG_INFINITY, f64::max);
+            let sAccion = &resultado.hm_s_sPolitica[sEstado];
+            assert!(f64MejorValor - hm_s_f64Q[sAccion] <= F64_TOLERANCIA_EMPATE, "{}: {}", sEstado, sAccion);
+        }
+    }
+
+    #[test]
+    fn predecesores_siguen_las_reglas_del_entorno() {
+        let opciones = OpcionesValueIteration {
+            entorno: entorno_con_reglas(),
+            ..Default::default()
+        };
+        let hm_s_vec_tpl_Predecesores = predecesores(None, &opciones);
+        let hay_entrada = |sDestino: &str, sOrigen: &str, sAccion: &str| {
+            hm_s_vec_tpl_Predecesores
+                .get(sDestino)
+                .is_some_and(|vec_tpl| vec_tpl.iter().any(|(sO, sA, _)| sO == sOrigen && sA == sAccion))
+        };
+        // Teleporter S0 -> S35: entering S0 from S1 lands in S35.
+        assert!(hay_entrada("S35", "S1", "O"));
+        assert!(!hay_entrada("S0", "S1", "O"));
+        // Blocked move S22 -> E and wall S21 | S14 keep the robot in place.
+        assert!(!hay_entrada("M", "S22", "E"));
+        assert!(!hay_entrada("S14", "S21", "N"));
+        // A lethal danger has no outgoing transitions.
+        assert!(hm_s_vec_tpl_Predecesores.values().flatten().all(|(sOrigen, _, _)| sOrigen != "P2"));
+        // Every (origin, action) still distributes all of its probability.
+        let mut hm_tpl_f64Masa: HashMap<(String, String), f64> = HashMap::new();
+        for (sOrigen, sAccion, f64Probabilidad) in hm_s_vec_tpl_Predecesores.values().flatten() {
+            *hm_tpl_f64Masa.entry((sOrigen.clone(), sAccion.clone())).or_insert(0.0) += f64Probabilidad;
+        }
+        assert!(hm_tpl_f64Masa.values().all(|f64Masa| (f64Masa - 1.0).abs() < 1e-9));
+    }
+
+    #[test]
+    fn resolver_incremental_coincide_con_value_iteration_con_entorno() {
+        let opciones = OpcionesValueIteration {
+            entorno: entorno_con_reglas(),
+            hm_s_ModelosPorEstado: HashMap::from([(
+                "S29".to_string(),
+                crate::robustness::construir_modelo_ruido(0.25, 0.5, 0.25),
+            )]),
+            ..Default::default()
+        };
+        let f64Epsilon = 1e-6;
+        let resultadoVi = value_iteration_con_opciones(0.9, f64Epsilon, None, &opciones);
+        let resultadoPs = prioritized_sweeping(0.9, f64Epsilon, None, &opciones);
+        for (sEstado, f64Valor) in &resultadoVi.hm_s_f64Valores {
+            let f64ValorPs = resultadoPs.hm_s_f64Valores[sEstado];
+            assert!((f64Valor - f64ValorPs).abs() < 1e-4, "{}: {} vs {}", sEstado, f64Valor, f64ValorPs);
+        }
+        let valores = &resultadoVi.hm_s_f64Valores;
+        assert_politicas_equivalentes(
+            &resultadoVi.hm_s_sPolitica,
+            &resultadoPs.hm_s_sPolitica,
+            valores,
+            0.9,
+            &opciones,
+            1e-4,
+        );
+
+        // Re-solving from the default solution after switching to the environment reaches the same point.
+        let (hm_s_f64ValoresPrevios, _) = value_iteration(0.9, f64Epsilon, None);
+        let vec_sModificados: Vec<&str> = MAPA_ESTADOS.iter().flatten().copied().collect();
+        let resultadoInc =
+            resolver_incremental(&hm_s_f64ValoresPrevios, &vec_sModificados, 0.9, f64Epsilon, None, &opciones);
+        for (sEstado, f64Valor) in &resultadoVi.hm_s_f64Valores {
+            let f64ValorInc = resultadoInc.hm_s_f64Valores[sEstado];
+            assert!((f64Valor - f64ValorInc).abs() < 1e-4, "{}: {} vs {}", sEstado, f64Valor, f64ValorInc);
+        }
+        assert_politicas_equivalentes(
+            &resultadoVi.hm_s_sPolitica,
+            &resultadoInc.hm_s_sPolitica,
+            valores,
+            0.9,
+            &opciones,
+            1e-4,
+        );
+    }
+
+    #[test]
+    fn alternar_tipo_celda_recorre_el_ciclo_y_lo_aplica() {
+        use crate::config::{RECOMPENSA_PASO, RECOMPENSA_PELIGRO};
+        use crate::entorno::{alternar_tipo_celda, reinicia_en_peligro, tipo_celda_con_entorno};
+        use crate::texto::TipoCelda;
+
+        let mut entorno = Entorno::default();
+        assert_eq!(alternar_tipo_celda(&mut entorno, "S9"), Ok(TipoCelda::Obstaculo));
+        assert_eq!(resolver_destino(&entorno, "S8", "E"), Some("S8"));
+        assert_eq!(alternar_tipo_celda(&mut entorno, "S9"), Ok(TipoCelda::Peligro));
+        assert_eq!(resolver_destino(&entorno, "S8", "E"), Some("S9"));
+        assert!(reinicia_en_peligro(&entorno, "S9"));
+        assert_eq!(recompensas_con_entorno(&entorno)["S9"], RECOMPENSA_PELIGRO);
+        assert_eq!(alternar_tipo_celda(&mut entorno, "S9"), Ok(TipoCelda::Meta));
+        assert_eq!(resolver_destino(&entorno, "S8", "E"), Some(ESTADO_META));
+        assert_eq!(alternar_tipo_celda(&mut entorno, "S9"), Ok(TipoCelda::Normal));
+        assert!(entorno.hm_s_eTiposCelda.is_empty());
+
+        // A map danger edited to normal neither resets nor is lethal, and pays the step reward.
+        assert_eq!(alternar_tipo_celda(&mut entorno, "P2"), Ok(TipoCelda::Meta));
+        assert_eq!(alternar_tipo_celda(&mut entorno, "P2"), Ok(TipoCelda::Normal));
+        assert_eq!(tipo_celda_con_entorno(&entorno, "P2"), TipoCelda::Normal);
+        assert!(!reinicia_en_peligro(&entorno, "P2"));
+        assert_eq!(recompensas_con_entorno(&entorno)["P2"], RECOMPENSA_PASO);
+        entorno.opt_hs_sPeligrosLetales = Some(HashSet::from(["P2".to_string()]));
+        assert!(!es_peligro_terminal(&entorno, "P2"));
+
+        // A reward override equal to the danger reward does not change the cell's type.
+        entorno.hm_s_f64RecompensasPersonalizadas.insert("S8".to_string(), RECOMPENSA_PELIGRO);
+        assert_eq!(tipo_celda_con_entorno(&entorno, "S8"), TipoCelda::Normal);
+
+        // The fixed goal and the map's obstacles cannot be edited.
+        assert!(alternar_tipo_celda(&mut entorno, ESTADO_META).is_err());
+        assert!(alternar_tipo_celda(&mut entorno, "O1").is_err());
+        assert!(alternar_tipo_celda(&mut entorno, "X99").is_err());
+    }
 }
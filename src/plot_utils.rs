@@ -1,7 +1,14 @@
 /// Utility functions for generating and saving plots related to MDP results using the Plotters crate.
 // plot_utils.rs
 
+use crate::config::{obtener_recompensas, COLUMNAS_MAPA, ESTADOS_PELIGRO, ESTADO_META, FILAS_MAPA, MAPA_ESTADOS};
+use crate::experimento::ResultadoTabular;
+use crate::mdp_model::distancias_a_meta;
+use crate::robustness::diferencias_politica;
+use crate::texto::{tipo_celda, TipoCelda};
 use plotters::prelude::*;
+use std::collections::HashMap;
+use std::path::Path;
 
 /// Generates and saves two plots:
 /// 1. `robustez_politicas.png`: A set of bar charts (one for each lambda/policy) showing
@@ -15,6 +22,7 @@ use plotters::prelude::*;
 ///   element is a tuple: `(lambda_value, Vec<(noise_model_label, num_policy_changes)>)`.
 /// * `ref_vec_tpl_f64_ui_uiResumen1000Pasos` - Data for the 1000-step simulation plot. A vector
 ///   where each element is a tuple: `(lambda_value, num_goals_reached, num_danger_states_entered)`.
+/// * `sDirSalida` - Directory the two PNG files are written to ("." for the current directory).
 ///
 /// # Returns
 ///
@@ -22,9 +30,11 @@ use plotters::prelude::*;
 pub fn graficar_resultados_finales(
     ref_vec_tpl_f64_vec_tpl_s_uiGraficosRobustez: &Vec<(f64, Vec<(String, usize)>)>,
     ref_vec_tpl_f64_ui_uiResumen1000Pasos: &Vec<(f64, usize, usize)>,
+    sDirSalida: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // === Robustness Policies Plot ===
-    let daRootRobustez = BitMapBackend::new("robustez_politicas.png", (960, 640)).into_drawing_area();
+    let pbRobustez = Path::new(sDirSalida).join("robustez_politicas.png");
+    let daRootRobustez = BitMapBackend::new(&pbRobustez, (960, 640)).into_drawing_area();
     daRootRobustez.fill(&WHITE)?;
 
     let uiNumGraficosRobustez = ref_vec_tpl_f64_vec_tpl_s_uiGraficosRobustez.len();
@@ -64,7 +74,8 @@ pub fn graficar_resultados_finales(
     }
 
     // === 1000-Step Simulation Performance Plot ===
-    let daRootSim1000Pasos = BitMapBackend::new("simulacion_1000pasos.png", (800, 500)).into_drawing_area();
+    let pbSim1000Pasos = Path::new(sDirSalida).join("simulacion_1000pasos.png");
+    let daRootSim1000Pasos = BitMapBackend::new(&pbSim1000Pasos, (800, 500)).into_drawing_area();
     daRootSim1000Pasos.fill(&WHITE)?;
 
     // Prepare data for plotting (lambdas, goals, dangers)
@@ -129,8 +140,754 @@ pub fn graficar_resultados_finales(
         .background_style(&WHITE.mix(0.8))
         .draw()?;
 
-    println!("✅ Imagen 'robustez_politicas.png' guardada correctamente.");
-    println!("✅ Imagen 'simulacion_1000pasos.png' guardada correctamente.");
+    log::info!("Imagen '{}' guardada correctamente.", pbRobustez.display());
+    log::info!("Imagen '{}' guardada correctamente.", pbSim1000Pasos.display());
 
     Ok(())
 }
+
+/// Side of a map cell, in pixels, in the map renderings (`graficar_comparacion_politicas`, `graficar_brecha_q`).
+const I32_TAMANO_CELDA_COMPARACION: i32 = 80;
+/// Height of the legend strip below the map, in pixels.
+const I32_ALTO_LEYENDA_COMPARACION: i32 = 40;
+
+/// Draws the map once with the states whose action differs between two policies highlighted.
+///
+/// Changed cells are filled orange and show both actions as arrows: the base policy's in blue and
+/// the adapted policy's in red. Unchanged cells are grayed out, obstacles are dark gray and the
+/// goal is green. The changed states come from `robustness::diferencias_politica`, so the count in
+/// the legend is the one reported by `evaluar_robustez` (it includes obstacles, which the solver
+/// also assigns actions to, but their arrows are not drawn).
+///
+/// # Arguments
+///
+/// * `ref_hm_s_sPoliticaBase` - The reference policy (State -> Action).
+/// * `ref_hm_s_sPoliticaAdaptada` - The policy to compare against it (e.g. solved under another noise model).
+/// * `sRuta` - Path of the PNG image to write.
+///
+/// # Returns
+///
+/// `Result<(), Box<dyn std::error::Error>>` indicating success or plotting error.
+pub fn graficar_comparacion_politicas(
+    ref_hm_s_sPoliticaBase: &HashMap<String, String>,
+    ref_hm_s_sPoliticaAdaptada: &HashMap<String, String>,
+    sRuta: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let vec_sCambios = diferencias_politica(ref_hm_s_sPoliticaBase, ref_hm_s_sPoliticaAdaptada);
+    let i32Ancho = COLUMNAS_MAPA as i32 * I32_TAMANO_CELDA_COMPARACION;
+    let i32AltoMapa = FILAS_MAPA as i32 * I32_TAMANO_CELDA_COMPARACION;
+
+    let daRoot = BitMapBackend::new(sRuta, (i32Ancho as u32, (i32AltoMapa + I32_ALTO_LEYENDA_COMPARACION) as u32))
+        .into_drawing_area();
+    daRoot.fill(&WHITE)?;
+
+    let rgbGris = RGBColor(220, 220, 220);
+    let rgbObstaculo = RGBColor(90, 90, 90);
+    let rgbCambio = RGBColor(255, 180, 80);
+
+    for (uiFila, arr_sFilaEstados) in MAPA_ESTADOS.iter().enumerate() {
+        for (uiCol, sEstado) in arr_sFilaEstados.iter().enumerate() {
+            let i32X = uiCol as i32 * I32_TAMANO_CELDA_COMPARACION;
+            let i32Y = uiFila as i32 * I32_TAMANO_CELDA_COMPARACION;
+            let bCambio = vec_sCambios.iter().any(|sCambio| sCambio == sEstado);
+
+            let rgbFondo = match tipo_celda(sEstado) {
+                TipoCelda::Obstaculo => rgbObstaculo,
+                TipoCelda::Meta => GREEN,
+                _ if bCambio => rgbCambio,
+                _ => rgbGris,
+            };
+            let tpl_i32x2Fin = (i32X + I32_TAMANO_CELDA_COMPARACION, i32Y + I32_TAMANO_CELDA_COMPARACION);
+            daRoot.draw(&Rectangle::new([(i32X, i32Y), tpl_i32x2Fin], rgbFondo.filled()))?;
+            daRoot.draw(&Rectangle::new([(i32X, i32Y), tpl_i32x2Fin], BLACK.stroke_width(1)))?;
+            daRoot.draw(&Text::new(sEstado.to_string(), (i32X + 4, i32Y + 4), ("sans-serif", 14).into_font()))?;
+
+            if bCambio && rgbFondo == rgbCambio {
+                let tpl_i32x2Centro = (i32X + I32_TAMANO_CELDA_COMPARACION / 2, i32Y + I32_TAMANO_CELDA_COMPARACION / 2);
+                if let Some(sAccion) = ref_hm_s_sPoliticaBase.get(*sEstado) {
+                    dibujar_flecha(&daRoot, tpl_i32x2Centro, sAccion, -6, &BLUE)?;
+                }
+                if let Some(sAccion) = ref_hm_s_sPoliticaAdaptada.get(*sEstado) {
+                    dibujar_flecha(&daRoot, tpl_i32x2Centro, sAccion, 6, &RED)?;
+                }
+            }
+        }
+    }
+
+    // Legend strip
+    let i32YLeyenda = i32AltoMapa + 12;
+    let fuenteLeyenda = ("sans-serif", 16).into_font();
+    daRoot.draw(&Rectangle::new([(10, i32YLeyenda), (26, i32YLeyenda + 16)], BLUE.filled()))?;
+    daRoot.draw(&Text::new("Base", (32, i32YLeyenda), fuenteLeyenda.clone()))?;
+    daRoot.draw(&Rectangle::new([(100, i32YLeyenda), (116, i32YLeyenda + 16)], RED.filled()))?;
+    daRoot.draw(&Text::new("Adaptada", (122, i32YLeyenda), fuenteLeyenda.clone()))?;
+    daRoot.draw(&Text::new(
+        format!("{} estados cambiados", vec_sCambios.len()),
+        (240, i32YLeyenda),
+        fuenteLeyenda,
+    ))?;
+
+    daRoot.present()?;
+    log::info!("Imagen '{}' guardada correctamente.", sRuta);
+
+    Ok(())
+}
+
+/// Draws an arrow of an action from the center of a cell, shifted sideways by `i32Desplazamiento`
+/// pixels so that two arrows in the same cell do not overlap.
+fn dibujar_flecha<DB: DrawingBackend>(
+    ref_daArea: &DrawingArea<DB, plotters::coord::Shift>,
+    tpl_i32x2Centro: (i32, i32),
+    sAccion: &str,
+    i32Desplazamiento: i32,
+    ref_rgbColor: &RGBColor,
+) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+    let i32Largo = I32_TAMANO_CELDA_COMPARACION / 3;
+    // Unit direction (dx, dy) in screen coordinates (y grows downwards)
+    let (i32Dx, i32Dy) = match sAccion {
+        "N" => (0, -1),
+        "S" => (0, 1),
+        "E" => (1, 0),
+        "O" => (-1, 0),
+        _ => return Ok(()),
+    };
+    // Sideways shift is perpendicular to the arrow
+    let (i32Cx, i32Cy) = (
+        tpl_i32x2Centro.0 - i32Dy * i32Desplazamiento,
+        tpl_i32x2Centro.1 + i32Dx * i32Desplazamiento,
+    );
+    let tpl_i32x2Inicio = (i32Cx - i32Dx * i32Largo / 2, i32Cy - i32Dy * i32Largo / 2);
+    let tpl_i32x2Punta = (i32Cx + i32Dx * i32Largo / 2, i32Cy + i32Dy * i32Largo / 2);
+    ref_daArea.draw(&PathElement::new(vec![tpl_i32x2Inicio, tpl_i32x2Punta], ref_rgbColor.stroke_width(3)))?;
+
+    let i32Cabeza = 7;
+    let tpl_i32x2Base = (tpl_i32x2Punta.0 - i32Dx * i32Cabeza, tpl_i32x2Punta.1 - i32Dy * i32Cabeza);
+    ref_daArea.draw(&Polygon::new(
+        vec![
+            tpl_i32x2Punta,
+            (tpl_i32x2Base.0 - i32Dy * i32Cabeza, tpl_i32x2Base.1 + i32Dx * i32Cabeza),
+            (tpl_i32x2Base.0 + i32Dy * i32Cabeza, tpl_i32x2Base.1 - i32Dx * i32Cabeza),
+        ],
+        ref_rgbColor.filled(),
+    ))?;
+    Ok(())
+}
+
+/// Draws a heatmap of the gap between the best and second-best action value of each state
+/// (see `learning::brecha_q`).
+///
+/// Cells go from red (gap close to 0: fragile, the action could flip) to white (the largest finite
+/// gap on the map); infinite gaps are drawn as the largest one. Each cell shows its gap, and states
+/// without a gap (goal, obstacles) are dark gray.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_f64Brechas` - Gap per state, as returned by `learning::brecha_q`.
+/// * `sRuta` - Path of the PNG image to write.
+///
+/// # Returns
+///
+/// `Result<(), Box<dyn std::error::Error>>` indicating success or plotting error.
+pub fn graficar_brecha_q(
+    ref_hm_s_f64Brechas: &HashMap<String, f64>,
+    sRuta: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let i32Ancho = COLUMNAS_MAPA as i32 * I32_TAMANO_CELDA_COMPARACION;
+    let i32Alto = FILAS_MAPA as i32 * I32_TAMANO_CELDA_COMPARACION;
+    let daRoot = BitMapBackend::new(sRuta, (i32Ancho as u32, i32Alto as u32)).into_drawing_area();
+    daRoot.fill(&WHITE)?;
+
+    let f64MaxBrecha = ref_hm_s_f64Brechas
+        .values()
+        .copied()
+        .filter(|f64Brecha| f64Brecha.is_finite())
+        .fold(0.0, f64::max);
+
+    for (uiFila, arr_sFilaEstados) in MAPA_ESTADOS.iter().enumerate() {
+        for (uiCol, sEstado) in arr_sFilaEstados.iter().enumerate() {
+            let i32X = uiCol as i32 * I32_TAMANO_CELDA_COMPARACION;
+            let i32Y = uiFila as i32 * I32_TAMANO_CELDA_COMPARACION;
+            let tpl_i32x2Fin = (i32X + I32_TAMANO_CELDA_COMPARACION, i32Y + I32_TAMANO_CELDA_COMPARACION);
+
+            let opt_f64Brecha = ref_hm_s_f64Brechas.get(*sEstado);
+            let rgbFondo = match opt_f64Brecha {
+                Some(f64Brecha) => {
+                    let f64T = if f64MaxBrecha > 0.0 { (f64Brecha / f64MaxBrecha).min(1.0) } else { 1.0 };
+                    let u8Canal = (255.0 * f64T) as u8;
+                    RGBColor(255, u8Canal, u8Canal)
+                }
+                None => RGBColor(90, 90, 90),
+            };
+            daRoot.draw(&Rectangle::new([(i32X, i32Y), tpl_i32x2Fin], rgbFondo.filled()))?;
+            daRoot.draw(&Rectangle::new([(i32X, i32Y), tpl_i32x2Fin], BLACK.stroke_width(1)))?;
+            daRoot.draw(&Text::new(sEstado.to_string(), (i32X + 4, i32Y + 4), ("sans-serif", 14).into_font()))?;
+            if let Some(f64Brecha) = opt_f64Brecha {
+                let sBrecha = if f64Brecha.is_finite() { format!("{:.3}", f64Brecha) } else { "∞".to_string() };
+                daRoot.draw(&Text::new(
+                    sBrecha,
+                    (i32X + 16, i32Y + I32_TAMANO_CELDA_COMPARACION / 2),
+                    ("sans-serif", 16).into_font(),
+                ))?;
+            }
+        }
+    }
+
+    daRoot.present()?;
+    log::info!("Imagen '{}' guardada correctamente.", sRuta);
+
+    Ok(())
+}
+
+/// Draws the map colored by the change of each state's value, V_b(s) − V_a(s).
+///
+/// The scale diverges from white at 0 to blue (values went down) and red (values went up), saturating
+/// at the largest absolute difference on the map, so the sign of a change is read from the hue and its
+/// size from the intensity. Each cell shows its difference; obstacles are black, and states missing from
+/// either map are dark gray. A strip below the map gives the minimum and maximum difference and where
+/// they occur. Typical use: solve with `value_iteration_con_opciones` before and after changing a reward
+/// (`Entorno::hm_s_f64RecompensasPersonalizadas`) or the noise model.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_f64ValoresA` - The reference values V_a.
+/// * `ref_hm_s_f64ValoresB` - The values to compare, V_b.
+/// * `sRuta` - Path of the PNG image to write.
+///
+/// # Returns
+///
+/// `Result<(), Box<dyn std::error::Error>>` indicating success or plotting error.
+pub fn graficar_diferencia_valores(
+    ref_hm_s_f64ValoresA: &HashMap<&'static str, f64>,
+    ref_hm_s_f64ValoresB: &HashMap<&'static str, f64>,
+    sRuta: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let i32Ancho = COLUMNAS_MAPA as i32 * I32_TAMANO_CELDA_COMPARACION;
+    let i32AltoMapa = FILAS_MAPA as i32 * I32_TAMANO_CELDA_COMPARACION;
+    let daRoot = BitMapBackend::new(sRuta, (i32Ancho as u32, (i32AltoMapa + I32_ALTO_LEYENDA_COMPARACION) as u32))
+        .into_drawing_area();
+    daRoot.fill(&WHITE)?;
+
+    let hm_s_f64Diferencias: HashMap<&str, f64> = MAPA_ESTADOS
+        .iter()
+        .flatten()
+        .filter(|sEstado| tipo_celda(sEstado) != TipoCelda::Obstaculo)
+        .filter_map(|sEstado| {
+            let f64Diferencia = ref_hm_s_f64ValoresB.get(sEstado)? - ref_hm_s_f64ValoresA.get(sEstado)?;
+            Some((*sEstado, f64Diferencia))
+        })
+        .collect();
+    let f64MaxAbsoluta = hm_s_f64Diferencias.values().fold(0.0_f64, |f64Max, f64D| f64Max.max(f64D.abs()));
+
+    for (uiFila, arr_sFilaEstados) in MAPA_ESTADOS.iter().enumerate() {
+        for (uiCol, sEstado) in arr_sFilaEstados.iter().enumerate() {
+            let i32X = uiCol as i32 * I32_TAMANO_CELDA_COMPARACION;
+            let i32Y = uiFila as i32 * I32_TAMANO_CELDA_COMPARACION;
+            let tpl_i32x2Fin = (i32X + I32_TAMANO_CELDA_COMPARACION, i32Y + I32_TAMANO_CELDA_COMPARACION);
+
+            let opt_f64Diferencia = hm_s_f64Diferencias.get(sEstado).copied();
+            let rgbFondo = match opt_f64Diferencia {
+                _ if tipo_celda(sEstado) == TipoCelda::Obstaculo => BLACK,
+                Some(f64Diferencia) => {
+                    let f64T = if f64MaxAbsoluta > 0.0 { (f64Diferencia.abs() / f64MaxAbsoluta).min(1.0) } else { 0.0 };
+                    let u8Canal = (255.0 * (1.0 - f64T)) as u8;
+                    if f64Diferencia < 0.0 {
+                        RGBColor(u8Canal, u8Canal, 255)
+                    } else {
+                        RGBColor(255, u8Canal, u8Canal)
+                    }
+                }
+                None => RGBColor(90, 90, 90),
+            };
+            daRoot.draw(&Rectangle::new([(i32X, i32Y), tpl_i32x2Fin], rgbFondo.filled()))?;
+            daRoot.draw(&Rectangle::new([(i32X, i32Y), tpl_i32x2Fin], BLACK.stroke_width(1)))?;
+            if let Some(f64Diferencia) = opt_f64Diferencia {
+                daRoot.draw(&Text::new(sEstado.to_string(), (i32X + 4, i32Y + 4), ("sans-serif", 14).into_font()))?;
+                daRoot.draw(&Text::new(
+                    format!("{:+.3}", f64Diferencia),
+                    (i32X + 12, i32Y + I32_TAMANO_CELDA_COMPARACION / 2),
+                    ("sans-serif", 16).into_font(),
+                ))?;
+            }
+        }
+    }
+
+    // Min/max annotation
+    let opt_tpl_s_f64Min = hm_s_f64Diferencias.iter().min_by(|tplA, tplB| tplA.1.total_cmp(tplB.1));
+    let opt_tpl_s_f64Max = hm_s_f64Diferencias.iter().max_by(|tplA, tplB| tplA.1.total_cmp(tplB.1));
+    if let (Some((sMin, f64Min)), Some((sMax, f64Max))) = (opt_tpl_s_f64Min, opt_tpl_s_f64Max) {
+        daRoot.draw(&Text::new(
+            format!("ΔV mín: {:+.3} ({})   ΔV máx: {:+.3} ({})", f64Min, sMin, f64Max, sMax),
+            (10, i32AltoMapa + 12),
+            ("sans-serif", 16).into_font(),
+        ))?;
+    }
+
+    daRoot.present()?;
+    log::info!("Imagen '{}' guardada correctamente.", sRuta);
+
+    Ok(())
+}
+
+/// Draws a histogram of per-episode returns.
+///
+/// The range [min, max] of the returns is split into `uiBins` equal-width bins; the caption shows
+/// the mean and standard deviation, since two policies with the same mean can differ a lot in spread.
+///
+/// # Arguments
+///
+/// * `ref_arr_f64Retornos` - The return of each episode (e.g. from `simulation::retornos_por_episodio`
+///   or `ResultadoAprendizaje::vec_f64RecompensaPorEpisodio`).
+/// * `uiBins` - Number of bins (at least 1).
+/// * `sRuta` - Path of the PNG image to write.
+///
+/// # Returns
+///
+/// `Result<(), Box<dyn std::error::Error>>` indicating success or plotting error.
+pub fn graficar_histograma_retornos(
+    ref_arr_f64Retornos: &[f64],
+    uiBins: usize,
+    sRuta: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if ref_arr_f64Retornos.is_empty() {
+        return Err("no hay retornos para graficar".into());
+    }
+    let uiBins = uiBins.max(1);
+    let f64Min = ref_arr_f64Retornos.iter().copied().fold(f64::INFINITY, f64::min);
+    let f64Max = ref_arr_f64Retornos.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    // Avoid zero-width bins when every return is the same
+    let f64Ancho = if f64Max > f64Min { (f64Max - f64Min) / uiBins as f64 } else { 1.0 };
+
+    let mut vec_uiConteos = vec![0usize; uiBins];
+    for f64Retorno in ref_arr_f64Retornos {
+        let uiBin = (((f64Retorno - f64Min) / f64Ancho) as usize).min(uiBins - 1);
+        vec_uiConteos[uiBin] += 1;
+    }
+
+    let f64Media = ref_arr_f64Retornos.iter().sum::<f64>() / ref_arr_f64Retornos.len() as f64;
+    let f64Varianza = ref_arr_f64Retornos.iter().map(|f64R| (f64R - f64Media).powi(2)).sum::<f64>()
+        / ref_arr_f64Retornos.len() as f64;
+
+    let daRoot = BitMapBackend::new(sRuta, (800, 500)).into_drawing_area();
+    daRoot.fill(&WHITE)?;
+
+    let uiConteoMax = *vec_uiConteos.iter().max().unwrap();
+    let mut ccChart = ChartBuilder::on(&daRoot)
+        .caption(
+            format!("Retornos por episodio (media {:.2}, desv. {:.2})", f64Media, f64Varianza.sqrt()),
+            ("sans-serif", 20),
+        )
+        .margin(20)
+        .x_label_area_size(40)
+        .y_label_area_size(50)
+        .build_cartesian_2d(f64Min..(f64Min + f64Ancho * uiBins as f64), 0..(uiConteoMax + 1))?;
+
+    ccChart.configure_mesh().x_desc("Retorno").y_desc("Episodios").draw()?;
+
+    ccChart.draw_series(vec_uiConteos.iter().enumerate().map(|(uiBin, uiConteo)| {
+        let f64X0 = f64Min + uiBin as f64 * f64Ancho;
+        Rectangle::new([(f64X0, 0), (f64X0 + f64Ancho, *uiConteo)], BLUE.mix(0.5).filled())
+    }))?;
+
+    daRoot.present()?;
+    log::info!("Imagen '{}' guardada correctamente.", sRuta);
+
+    Ok(())
+}
+
+/// Draws average reward against the discount factor, one line per success-probability level.
+///
+/// Points of each level are joined in increasing lambda order and marked with a dot; each level gets
+/// its own color and a legend entry `p = prob`, so trends across lambda can be compared between noise levels.
+///
+/// # Arguments
+///
+/// * `ref_arr_tpl_f64x3Datos` - `(lambda, success_probability, average_reward)` tuples, in any order.
+/// * `sRuta` - Path of the PNG image to write.
+///
+/// # Returns
+///
+/// `Result<(), Box<dyn std::error::Error>>` indicating success or plotting error.
+pub fn graficar_recompensa_vs_lambda(
+    ref_arr_tpl_f64x3Datos: &[(f64, f64, f64)],
+    sRuta: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if ref_arr_tpl_f64x3Datos.is_empty() {
+        return Err("no hay datos para graficar".into());
+    }
+
+    // Group the points by probability level, each sorted by lambda
+    let mut vec_tpl_f64_vec_tpl_f64f64Series: Vec<(f64, Vec<(f64, f64)>)> = Vec::new();
+    for (f64Lambda, f64Prob, f64Recompensa) in ref_arr_tpl_f64x3Datos {
+        match vec_tpl_f64_vec_tpl_f64f64Series.iter_mut().find(|(f64ProbSerie, _)| f64ProbSerie == f64Prob) {
+            Some((_, vec_tpl_f64f64Puntos)) => vec_tpl_f64f64Puntos.push((*f64Lambda, *f64Recompensa)),
+            None => vec_tpl_f64_vec_tpl_f64f64Series.push((*f64Prob, vec![(*f64Lambda, *f64Recompensa)])),
+        }
+    }
+    vec_tpl_f64_vec_tpl_f64f64Series.sort_by(|tplA, tplB| tplA.0.partial_cmp(&tplB.0).unwrap());
+    for (_, vec_tpl_f64f64Puntos) in vec_tpl_f64_vec_tpl_f64f64Series.iter_mut() {
+        vec_tpl_f64f64Puntos.sort_by(|tplA, tplB| tplA.0.partial_cmp(&tplB.0).unwrap());
+    }
+
+    let f64LambdaMin = ref_arr_tpl_f64x3Datos.iter().map(|tpl| tpl.0).fold(f64::INFINITY, f64::min);
+    let f64LambdaMax = ref_arr_tpl_f64x3Datos.iter().map(|tpl| tpl.0).fold(f64::NEG_INFINITY, f64::max);
+    let f64RecompensaMin = ref_arr_tpl_f64x3Datos.iter().map(|tpl| tpl.2).fold(f64::INFINITY, f64::min);
+    let f64RecompensaMax = ref_arr_tpl_f64x3Datos.iter().map(|tpl| tpl.2).fold(f64::NEG_INFINITY, f64::max);
+    // Pad the ranges so single points and flat lines stay visible
+    let f64MargenX = ((f64LambdaMax - f64LambdaMin) * 0.05).max(0.01);
+    let f64MargenY = ((f64RecompensaMax - f64RecompensaMin) * 0.1).max(0.1);
+
+    let daRoot = BitMapBackend::new(sRuta, (800, 500)).into_drawing_area();
+    daRoot.fill(&WHITE)?;
+
+    let mut ccChart = ChartBuilder::on(&daRoot)
+        .caption("Recompensa promedio vs λ", ("sans-serif", 20))
+        .margin(20)
+        .x_label_area_size(40)
+        .y_label_area_size(50)
+        .build_cartesian_2d(
+            (f64LambdaMin - f64MargenX)..(f64LambdaMax + f64MargenX),
+            (f64RecompensaMin - f64MargenY)..(f64RecompensaMax + f64MargenY),
+        )?;
+
+    ccChart.configure_mesh().x_desc("λ").y_desc("Recompensa promedio").draw()?;
+
+    for (uiSerie, (f64Prob, vec_tpl_f64f64Puntos)) in vec_tpl_f64_vec_tpl_f64f64Series.iter().enumerate() {
+        let rgbaColor = Palette99::pick(uiSerie).to_rgba();
+        ccChart
+            .draw_series(LineSeries::new(vec_tpl_f64f64Puntos.iter().copied(), rgbaColor.stroke_width(2)))?
+            .label(format!("p = {:.2}", f64Prob))
+            .legend(move |(i32LegX, i32LegY)| {
+                PathElement::new(vec![(i32LegX, i32LegY), (i32LegX + 20, i32LegY)], rgbaColor.stroke_width(2))
+            });
+        ccChart.draw_series(
+            vec_tpl_f64f64Puntos.iter().map(|tpl_f64f64Punto| Circle::new(*tpl_f64f64Punto, 4, rgbaColor.filled())),
+        )?;
+    }
+
+    ccChart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+
+    daRoot.present()?;
+    log::info!("Imagen '{}' guardada correctamente.", sRuta);
+
+    Ok(())
+}
+
+/// Draws the danger-hit rate of an experiment sweep as a heatmap over (lambda, success probability).
+///
+/// Each cell is one row of `ref_resultado` (`experimento::ejecutar_experimento`), filled from white at no
+/// danger hits to red at the highest rate of the sweep and labeled with its rate per 1000 steps, so it shows
+/// whether larger discount factors make the policy more or less willing to cross danger states under noise.
+/// Lambdas run along the x axis and probabilities along the y axis, both in increasing order.
+///
+/// # Arguments
+///
+/// * `ref_resultado` - The sweep results (`ResultadoTabular::vec_f64PeligrosPor1000Pasos` per combination).
+/// * `sRuta` - Path of the PNG image to write.
+///
+/// # Returns
+///
+/// `Result<(), Box<dyn std::error::Error>>` indicating success or plotting error.
+pub fn graficar_superficie_peligros(
+    ref_resultado: &ResultadoTabular,
+    sRuta: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if ref_resultado.is_empty() {
+        return Err("no hay datos para graficar".into());
+    }
+
+    // Distinct sweep values of each axis, in increasing order
+    let valores_ordenados = |ref_vec_f64Columna: &Vec<f64>| -> Vec<f64> {
+        let mut vec_f64Valores = ref_vec_f64Columna.clone();
+        vec_f64Valores.sort_by(|f64A, f64B| f64A.total_cmp(f64B));
+        vec_f64Valores.dedup();
+        vec_f64Valores
+    };
+    let vec_f64Lambdas = valores_ordenados(&ref_resultado.vec_f64Lambda);
+    let vec_f64Probs = valores_ordenados(&ref_resultado.vec_f64ProbExito);
+    let f64TasaMax = ref_resultado.vec_f64PeligrosPor1000Pasos.iter().copied().fold(0.0_f64, f64::max);
+
+    // The cells are laid out by hand, as in the map renderings: a chart axis over a single sweep value
+    // (e.g. the default single probability) would have no width.
+    let (i32Ancho, i32Alto) = (800, 500);
+    let (i32MargenIzq, i32MargenSup, i32MargenInf) = (90, 50, 60);
+    let i32AnchoCelda = (i32Ancho - i32MargenIzq - 20) / vec_f64Lambdas.len() as i32;
+    let i32AltoCelda = (i32Alto - i32MargenSup - i32MargenInf) / vec_f64Probs.len() as i32;
+    let i32BaseCeldas = i32MargenSup + i32AltoCelda * vec_f64Probs.len() as i32;
+
+    let daRoot = BitMapBackend::new(sRuta, (i32Ancho as u32, i32Alto as u32)).into_drawing_area();
+    daRoot.fill(&WHITE)?;
+    daRoot.draw(&Text::new(
+        format!("Peligros por 1000 pasos (máx {:.2})", f64TasaMax),
+        (i32Ancho / 2 - 130, 15),
+        ("sans-serif", 20).into_font(),
+    ))?;
+
+    for uiFila in 0..ref_resultado.len() {
+        let uiCol = vec_f64Lambdas.iter().position(|f64L| *f64L == ref_resultado.vec_f64Lambda[uiFila]).unwrap();
+        let uiNivel = vec_f64Probs.iter().position(|f64P| *f64P == ref_resultado.vec_f64ProbExito[uiFila]).unwrap();
+        // Higher probabilities on top
+        let i32X = i32MargenIzq + uiCol as i32 * i32AnchoCelda;
+        let i32Y = i32BaseCeldas - (uiNivel as i32 + 1) * i32AltoCelda;
+        let tpl_i32x2Fin = (i32X + i32AnchoCelda, i32Y + i32AltoCelda);
+
+        let f64Tasa = ref_resultado.vec_f64PeligrosPor1000Pasos[uiFila];
+        let f64T = if f64TasaMax > 0.0 { (f64Tasa / f64TasaMax).min(1.0) } else { 0.0 };
+        let u8Canal = (255.0 * (1.0 - f64T)) as u8;
+        daRoot.draw(&Rectangle::new([(i32X, i32Y), tpl_i32x2Fin], RGBColor(255, u8Canal, u8Canal).filled()))?;
+        daRoot.draw(&Rectangle::new([(i32X, i32Y), tpl_i32x2Fin], BLACK.stroke_width(1)))?;
+        daRoot.draw(&Text::new(
+            format!("{:.2}", f64Tasa),
+            (i32X + i32AnchoCelda / 2 - 20, i32Y + i32AltoCelda / 2 - 8),
+            ("sans-serif", 16).into_font(),
+        ))?;
+    }
+
+    // Axis labels: lambda under each column, probability left of each row
+    for (uiCol, f64Lambda) in vec_f64Lambdas.iter().enumerate() {
+        daRoot.draw(&Text::new(
+            format!("{:.2}", f64Lambda),
+            (i32MargenIzq + uiCol as i32 * i32AnchoCelda + i32AnchoCelda / 2 - 15, i32BaseCeldas + 8),
+            ("sans-serif", 14).into_font(),
+        ))?;
+    }
+    for (uiNivel, f64Prob) in vec_f64Probs.iter().enumerate() {
+        daRoot.draw(&Text::new(
+            format!("{:.2}", f64Prob),
+            (i32MargenIzq - 40, i32BaseCeldas - uiNivel as i32 * i32AltoCelda - i32AltoCelda / 2 - 7),
+            ("sans-serif", 14).into_font(),
+        ))?;
+    }
+    daRoot.draw(&Text::new(
+        "λ",
+        (i32MargenIzq + (i32Ancho - i32MargenIzq) / 2, i32BaseCeldas + 32),
+        ("sans-serif", 16).into_font(),
+    ))?;
+    daRoot.draw(&Text::new("p éxito", (8, i32MargenSup - 20), ("sans-serif", 16).into_font()))?;
+
+    daRoot.present()?;
+    log::info!("Imagen '{}' guardada correctamente.", sRuta);
+
+    Ok(())
+}
+
+/// Draws learning curves: each algorithm's per-episode reward as a faint line and its moving average as a bold one.
+///
+/// The moving average at episode `i` is the mean of the last `uiVentana` rewards up to `i` (fewer at the
+/// start), so the smoothed line starts at the first episode. Each curve gets its own color, and the
+/// legend labels the bold lines with the curve names.
+///
+/// # Arguments
+///
+/// * `ref_arr_tpl_s_vec_f64Curvas` - `(name, per-episode reward)` pairs, e.g. each learner's
+///   `ResultadoAprendizaje::vec_f64RecompensaPorEpisodio`.
+/// * `uiVentana` - Width of the moving average in episodes (at least 1).
+/// * `sRuta` - Path of the PNG image to write.
+///
+/// # Returns
+///
+/// `Result<(), Box<dyn std::error::Error>>` indicating success or plotting error.
+pub fn graficar_curva_aprendizaje(
+    ref_arr_tpl_s_vec_f64Curvas: &[(String, Vec<f64>)],
+    uiVentana: usize,
+    sRuta: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let uiEpisodiosMax =
+        ref_arr_tpl_s_vec_f64Curvas.iter().map(|(_, vec_f64Curva)| vec_f64Curva.len()).max().unwrap_or(0);
+    if uiEpisodiosMax == 0 {
+        return Err("no hay curvas para graficar".into());
+    }
+    let f64RecompensaMin = ref_arr_tpl_s_vec_f64Curvas
+        .iter()
+        .flat_map(|(_, vec_f64Curva)| vec_f64Curva.iter().copied())
+        .fold(f64::INFINITY, f64::min);
+    let f64RecompensaMax = ref_arr_tpl_s_vec_f64Curvas
+        .iter()
+        .flat_map(|(_, vec_f64Curva)| vec_f64Curva.iter().copied())
+        .fold(f64::NEG_INFINITY, f64::max);
+    let f64MargenY = ((f64RecompensaMax - f64RecompensaMin) * 0.05).max(0.1);
+
+    let daRoot = BitMapBackend::new(sRuta, (960, 540)).into_drawing_area();
+    daRoot.fill(&WHITE)?;
+
+    let mut ccChart = ChartBuilder::on(&daRoot)
+        .caption(format!("Curva de aprendizaje (media móvil de {})", uiVentana.max(1)), ("sans-serif", 20))
+        .margin(20)
+        .x_label_area_size(40)
+        .y_label_area_size(50)
+        .build_cartesian_2d(0..uiEpisodiosMax, (f64RecompensaMin - f64MargenY)..(f64RecompensaMax + f64MargenY))?;
+
+    ccChart.configure_mesh().x_desc("Episodio").y_desc("Recompensa").draw()?;
+
+    for (uiCurva, (sNombre, vec_f64Curva)) in ref_arr_tpl_s_vec_f64Curvas.iter().enumerate() {
+        let rgbaColor = Palette99::pick(uiCurva).to_rgba();
+        ccChart.draw_series(LineSeries::new(vec_f64Curva.iter().copied().enumerate(), rgbaColor.mix(0.25)))?;
+        ccChart
+            .draw_series(LineSeries::new(
+                media_movil(vec_f64Curva, uiVentana).into_iter().enumerate(),
+                rgbaColor.stroke_width(3),
+            ))?
+            .label(sNombre.as_str())
+            .legend(move |(i32LegX, i32LegY)| {
+                PathElement::new(vec![(i32LegX, i32LegY), (i32LegX + 20, i32LegY)], rgbaColor.stroke_width(3))
+            });
+    }
+
+    ccChart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+
+    daRoot.present()?;
+    log::info!("Imagen '{}' guardada correctamente.", sRuta);
+
+    Ok(())
+}
+
+/// Scatter-plots each state's value V(s) against its BFS distance to the goal, with the discounting reference curve.
+///
+/// Distances come from `mdp_model::distancias_a_meta` (noise-free moves, obstacles skipped); states with no
+/// path to the goal or missing from `ref_hm_s_f64Valores` are left out. Danger states are drawn in red so
+/// anomalously low values stand out. The dashed curve is λ^d·R(goal), the value of walking straight to the
+/// goal with no living reward and no noise; the solid one is the least-squares fit V ≈ α + β·λ^d, whose
+/// coefficients are shown in the legend.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_f64Valores` - State values, e.g. from `value_iteration`.
+/// * `f64Lambda` - The discount factor the values were computed with.
+/// * `sRuta` - Path of the PNG image to write.
+///
+/// # Returns
+///
+/// `Result<(), Box<dyn std::error::Error>>` indicating success or plotting error.
+pub fn graficar_valor_vs_distancia(
+    ref_hm_s_f64Valores: &HashMap<&str, f64>,
+    f64Lambda: f64,
+    sRuta: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // (distance, value, is danger) of every plotted state, in map order
+    let hm_s_uiDistancias = distancias_a_meta();
+    let vec_tpl_ui_f64_bPuntos: Vec<(usize, f64, bool)> = MAPA_ESTADOS
+        .iter()
+        .flatten()
+        .filter_map(|sEstado| {
+            let uiDistancia = *hm_s_uiDistancias.get(sEstado)?;
+            let f64Valor = *ref_hm_s_f64Valores.get(sEstado)?;
+            Some((uiDistancia, f64Valor, ESTADOS_PELIGRO.contains(sEstado)))
+        })
+        .collect();
+    if vec_tpl_ui_f64_bPuntos.is_empty() {
+        return Err("no hay estados con distancia y valor para graficar".into());
+    }
+
+    let f64RecompensaMeta = obtener_recompensas()[ESTADO_META];
+    let uiDistanciaMax = vec_tpl_ui_f64_bPuntos.iter().map(|tpl| tpl.0).max().unwrap();
+    let (f64Alfa, f64Beta) = ajuste_descuento(&vec_tpl_ui_f64_bPuntos, f64Lambda);
+    let fn_f64Curva = |f64Coef0: f64, f64Coef1: f64| {
+        (0..=uiDistanciaMax * 10).map(move |uiPaso| {
+            let f64Distancia = uiPaso as f64 / 10.0;
+            (f64Distancia, f64Coef0 + f64Coef1 * f64Lambda.powf(f64Distancia))
+        })
+    };
+
+    let f64ValorMin = vec_tpl_ui_f64_bPuntos
+        .iter()
+        .map(|tpl| tpl.1)
+        .chain(fn_f64Curva(0.0, f64RecompensaMeta).map(|tpl| tpl.1))
+        .chain(fn_f64Curva(f64Alfa, f64Beta).map(|tpl| tpl.1))
+        .fold(f64::INFINITY, f64::min);
+    let f64ValorMax = vec_tpl_ui_f64_bPuntos
+        .iter()
+        .map(|tpl| tpl.1)
+        .chain(fn_f64Curva(0.0, f64RecompensaMeta).map(|tpl| tpl.1))
+        .chain(fn_f64Curva(f64Alfa, f64Beta).map(|tpl| tpl.1))
+        .fold(f64::NEG_INFINITY, f64::max);
+    let f64MargenY = ((f64ValorMax - f64ValorMin) * 0.05).max(0.1);
+
+    let daRoot = BitMapBackend::new(sRuta, (800, 500)).into_drawing_area();
+    daRoot.fill(&WHITE)?;
+
+    let mut ccChart = ChartBuilder::on(&daRoot)
+        .caption(format!("Valor vs distancia a la meta (λ = {:.2})", f64Lambda), ("sans-serif", 20))
+        .margin(20)
+        .x_label_area_size(40)
+        .y_label_area_size(50)
+        .build_cartesian_2d(
+            -0.5..(uiDistanciaMax as f64 + 0.5),
+            (f64ValorMin - f64MargenY)..(f64ValorMax + f64MargenY),
+        )?;
+
+    ccChart.configure_mesh().x_desc("Distancia BFS a la meta").y_desc("V(s)").draw()?;
+
+    ccChart
+        .draw_series(DashedLineSeries::new(fn_f64Curva(0.0, f64RecompensaMeta), 6, 4, BLACK.stroke_width(1)))?
+        .label("λ^d · R(meta)")
+        .legend(|(i32LegX, i32LegY)| PathElement::new(vec![(i32LegX, i32LegY), (i32LegX + 20, i32LegY)], BLACK));
+    ccChart
+        .draw_series(LineSeries::new(fn_f64Curva(f64Alfa, f64Beta), BLUE.stroke_width(2)))?
+        .label(format!("ajuste: {:.2} + {:.2} · λ^d", f64Alfa, f64Beta))
+        .legend(|(i32LegX, i32LegY)| {
+            PathElement::new(vec![(i32LegX, i32LegY), (i32LegX + 20, i32LegY)], BLUE.stroke_width(2))
+        });
+    for (bPeligro, rgbColor, sEtiqueta) in [(false, BLUE, "Estados"), (true, RED, "Peligros")] {
+        ccChart
+            .draw_series(
+                vec_tpl_ui_f64_bPuntos
+                    .iter()
+                    .filter(|tpl| tpl.2 == bPeligro)
+                    .map(|tpl| Circle::new((tpl.0 as f64, tpl.1), 4, rgbColor.filled())),
+            )?
+            .label(sEtiqueta)
+            .legend(move |(i32LegX, i32LegY)| Circle::new((i32LegX + 10, i32LegY), 4, rgbColor.filled()));
+    }
+
+    ccChart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+
+    daRoot.present()?;
+    log::info!("Imagen '{}' guardada correctamente.", sRuta);
+
+    Ok(())
+}
+
+/// Least-squares fit of V ≈ α + β·λ^d over `(distance, value, _)` points; returns `(α, β)`.
+/// With a single distinct distance the slope is undefined and `(mean value, 0)` is returned.
+fn ajuste_descuento(ref_arr_tpl_ui_f64_bPuntos: &[(usize, f64, bool)], f64Lambda: f64) -> (f64, f64) {
+    let f64N = ref_arr_tpl_ui_f64_bPuntos.len() as f64;
+    let f64MediaX = ref_arr_tpl_ui_f64_bPuntos.iter().map(|tpl| f64Lambda.powi(tpl.0 as i32)).sum::<f64>() / f64N;
+    let f64MediaY = ref_arr_tpl_ui_f64_bPuntos.iter().map(|tpl| tpl.1).sum::<f64>() / f64N;
+    let (f64Sxy, f64Sxx) = ref_arr_tpl_ui_f64_bPuntos.iter().fold((0.0, 0.0), |(f64Sxy, f64Sxx), tpl| {
+        let f64Dx = f64Lambda.powi(tpl.0 as i32) - f64MediaX;
+        (f64Sxy + f64Dx * (tpl.1 - f64MediaY), f64Sxx + f64Dx * f64Dx)
+    });
+    if f64Sxx < 1e-12 {
+        return (f64MediaY, 0.0);
+    }
+    let f64Beta = f64Sxy / f64Sxx;
+    (f64MediaY - f64Beta * f64MediaX, f64Beta)
+}
+
+/// Trailing moving average: element `i` is the mean of the last `uiVentana` values up to `i` (fewer at the start).
+fn media_movil(ref_arr_f64Valores: &[f64], uiVentana: usize) -> Vec<f64> {
+    let uiVentana = uiVentana.max(1);
+    let mut f64Suma = 0.0;
+    ref_arr_f64Valores
+        .iter()
+        .enumerate()
+        .map(|(uiIndice, f64Valor)| {
+            f64Suma += f64Valor;
+            if uiIndice >= uiVentana {
+                f64Suma -= ref_arr_f64Valores[uiIndice - uiVentana];
+            }
+            f64Suma / (uiIndice + 1).min(uiVentana) as f64
+        })
+        .collect()
+}
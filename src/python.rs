@@ -0,0 +1,67 @@
+/// Python bindings for the MDP solver and the 1000-step simulation (`pyo3` feature).
+///
+/// Builds a Python module named `robotica` that uses the default map configuration:
+///
+/// ```python
+/// import robotica
+/// politica, valores = robotica.resolver(0.9, 0.001)
+/// metas, peligros = robotica.simular(politica, 1000, 0.8)
+/// ```
+// src/python.rs
+use crate::mdp_model::value_iteration;
+use crate::robustness::construir_modelo_ruido;
+use crate::simulation::{simulacion_1000_pasos_con_opciones, OpcionesSimulacion};
+use pyo3::prelude::*;
+use std::collections::HashMap;
+
+/// Runs value iteration on the default map and returns `(politica, valores)` as dicts.
+///
+/// # Arguments
+///
+/// * `f64Lambda` - The discount factor (gamma).
+/// * `f64Epsilon` - The convergence threshold.
+#[pyfunction]
+fn resolver(f64Lambda: f64, f64Epsilon: f64) -> (HashMap<String, String>, HashMap<String, f64>) {
+    let (hm_s_f64Valores, hm_s_sPolitica) = value_iteration(f64Lambda, f64Epsilon, None);
+    let hm_s_f64ValoresPy = hm_s_f64Valores
+        .into_iter()
+        .map(|(sEstado, f64Valor)| (sEstado.to_string(), f64Valor))
+        .collect();
+    (hm_s_sPolitica, hm_s_f64ValoresPy)
+}
+
+/// Runs `simulacion_1000_pasos` with the given policy and returns `(metas, peligros)`.
+///
+/// Each move goes in the intended direction with probability `f64ProbExito` and slips to either
+/// side with the rest split evenly, the same noise as `robustness::construir_modelo_ruido`.
+///
+/// # Arguments
+///
+/// * `hm_s_sPolitica` - The policy (State -> Action), as returned by `resolver`.
+/// * `uiPasos` - The total number of steps for the simulation.
+/// * `f64ProbExito` - Probability that a move goes in the intended direction (1.0 for no noise).
+#[pyfunction]
+fn simular(hm_s_sPolitica: HashMap<String, String>, uiPasos: usize, f64ProbExito: f64) -> PyResult<(usize, usize)> {
+    if !(0.0..=1.0).contains(&f64ProbExito) {
+        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "f64ProbExito debe estar en [0, 1]: {}",
+            f64ProbExito
+        )));
+    }
+    let f64ProbLateral = (1.0 - f64ProbExito) / 2.0;
+    let opciones = OpcionesSimulacion {
+        opt_hm_s_hm_s_f64Modelo: Some(construir_modelo_ruido(f64ProbLateral, f64ProbExito, f64ProbLateral)),
+        ..Default::default()
+    };
+    let (uiMetas, uiPeligros, _) = simulacion_1000_pasos_con_opciones(&hm_s_sPolitica, uiPasos, &opciones)
+        .map_err(pyo3::exceptions::PyValueError::new_err)?;
+    Ok((uiMetas, uiPeligros))
+}
+
+/// Python module definition: `import robotica`.
+#[pymodule]
+fn robotica(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(resolver, m)?)?;
+    m.add_function(wrap_pyfunction!(simular, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,223 @@
+/// One-time reward pickups, modeled by augmenting each state with a bitmask of the pickups already collected.
+///
+/// A pickup is a state that pays a bonus (instead of its normal reward) the first time it is visited
+/// in an episode, and its normal reward afterwards. Because the bitmask is part of the state the MDP
+/// stays Markov, and the solver can optimize the order in which pickups are collected.
+// src/recogibles.rs
+use crate::config::{acciones, obtener_recompensas, ESTADOS_PELIGRO, ESTADO_META, MAPA_ESTADOS, OBSTACULOS};
+use crate::entorno::{resolver_destino, Entorno};
+use crate::mdp_model::modelo_transicion_base;
+use ::rand::seq::SliceRandom;
+use ::rand::thread_rng;
+use std::collections::HashMap;
+
+/// Maximum number of pickups (the state space grows as 2^pickups).
+pub const UI_MAX_RECOGIBLES: usize = 16;
+
+/// Values of each (state, collected-pickups bitmask) pair.
+pub type ValoresConRecogibles = HashMap<(&'static str, u32), f64>;
+/// Optimal action for each (state, collected-pickups bitmask) pair.
+pub type PoliticaConRecogibles = HashMap<(String, u32), String>;
+
+/// Returns the reward of being in `sEstado` with the pickups in `u32Mascara` already collected,
+/// and the mask after leaving it.
+fn recompensa_y_mascara(
+    ref_hm_s_f64Recompensas: &HashMap<&'static str, f64>,
+    ref_vec_tpl_s_f64Recogibles: &[(&str, f64)],
+    sEstado: &str,
+    u32Mascara: u32,
+) -> (f64, u32) {
+    for (uiIndice, (sRecogible, f64Bono)) in ref_vec_tpl_s_f64Recogibles.iter().enumerate() {
+        let u32Bit = 1u32 << uiIndice;
+        if *sRecogible == sEstado && u32Mascara & u32Bit == 0 {
+            return (*f64Bono, u32Mascara | u32Bit);
+        }
+    }
+    (*ref_hm_s_f64Recompensas.get(sEstado).unwrap_or(&0.0), u32Mascara)
+}
+
+/// Performs value iteration over (state, collected-pickups bitmask) pairs.
+///
+/// Bit `i` of the mask is set once pickup `i` of `ref_vec_tpl_s_f64Recogibles` has been collected.
+/// Being in pickup `i` with bit `i` clear earns its bonus and sets the bit for the next state.
+/// The goal is terminal with its normal reward, as in `value_iteration`.
+///
+/// # Arguments
+///
+/// * `f64Lambda` - The discount factor (gamma).
+/// * `f64Epsilon` - The convergence threshold.
+/// * `ref_vec_tpl_s_f64Recogibles` - The pickups as `(state, bonus)`, at most `UI_MAX_RECOGIBLES`.
+/// * `opt_hm_s_hm_s_f64ProbTransExt` - An optional external transition probability model.
+///
+/// # Returns
+///
+/// A tuple with the values and the policy of every (state, mask) pair.
+///
+/// # Panics
+///
+/// If there are more than `UI_MAX_RECOGIBLES` pickups.
+pub fn value_iteration_con_recogibles(
+    f64Lambda: f64,
+    f64Epsilon: f64,
+    ref_vec_tpl_s_f64Recogibles: &[(&str, f64)],
+    opt_hm_s_hm_s_f64ProbTransExt: Option<&HashMap<String, HashMap<String, f64>>>,
+) -> (ValoresConRecogibles, PoliticaConRecogibles) {
+    assert!(
+        ref_vec_tpl_s_f64Recogibles.len() <= UI_MAX_RECOGIBLES,
+        "Demasiados recogibles: {} (máximo {})",
+        ref_vec_tpl_s_f64Recogibles.len(),
+        UI_MAX_RECOGIBLES
+    );
+
+    let hm_s_hm_s_f64ModeloBase;
+    let ref_hm_s_hm_s_f64Modelo = match opt_hm_s_hm_s_f64ProbTransExt {
+        Some(ref_hm_s_hm_s_f64ModeloExt) => ref_hm_s_hm_s_f64ModeloExt,
+        None => {
+            hm_s_hm_s_f64ModeloBase = modelo_transicion_base();
+            &hm_s_hm_s_f64ModeloBase
+        }
+    };
+    let hm_s_f64RecompensasMap = obtener_recompensas();
+    let entorno = Entorno::default();
+    let u32NumMascaras = 1u32 << ref_vec_tpl_s_f64Recogibles.len();
+
+    let vec_sEstados: Vec<&'static str> = MAPA_ESTADOS
+        .iter()
+        .flatten()
+        .copied()
+        .filter(|sEstado| !OBSTACULOS.contains(sEstado))
+        .collect();
+
+    let mut hm_tpl_s_u32_f64V: ValoresConRecogibles = HashMap::new();
+    for &sEstado in &vec_sEstados {
+        for u32Mascara in 0..u32NumMascaras {
+            hm_tpl_s_u32_f64V.insert((sEstado, u32Mascara), 0.0);
+        }
+    }
+    let mut hm_tpl_s_u32_sPolitica: PoliticaConRecogibles = HashMap::new();
+
+    let mut bCambios = true;
+    while bCambios {
+        bCambios = false;
+        let mut hm_tpl_s_u32_f64VNuevo = hm_tpl_s_u32_f64V.clone();
+
+        for &sEstado in &vec_sEstados {
+            for u32Mascara in 0..u32NumMascaras {
+                if sEstado == ESTADO_META {
+                    hm_tpl_s_u32_f64VNuevo.insert((sEstado, u32Mascara), hm_s_f64RecompensasMap[ESTADO_META]);
+                    continue;
+                }
+
+                let (f64Recompensa, u32MascaraSiguiente) = recompensa_y_mascara(
+                    &hm_s_f64RecompensasMap,
+                    ref_vec_tpl_s_f64Recogibles,
+                    sEstado,
+                    u32Mascara,
+                );
+
+                let mut f64MejorValor = f64::NEG_INFINITY;
+                let mut sMejorAccion = String::new();
+                for sAccion in acciones().iter() {
+                    let mut f64ValorEsperado = 0.0;
+                    for (sResultado, f64Probabilidad) in ref_hm_s_hm_s_f64Modelo[*sAccion].iter() {
+                        let sEstadoDestino = resolver_destino(&entorno, sEstado, sResultado).unwrap();
+                        f64ValorEsperado +=
+                            f64Probabilidad * hm_tpl_s_u32_f64V[&(sEstadoDestino, u32MascaraSiguiente)];
+                    }
+                    let f64ValorTotal = f64Recompensa + f64Lambda * f64ValorEsperado;
+                    if f64ValorTotal > f64MejorValor {
+                        f64MejorValor = f64ValorTotal;
+                        sMejorAccion = sAccion.to_string();
+                    }
+                }
+
+                if (hm_tpl_s_u32_f64V[&(sEstado, u32Mascara)] - f64MejorValor).abs() > f64Epsilon {
+                    bCambios = true;
+                }
+                hm_tpl_s_u32_f64VNuevo.insert((sEstado, u32Mascara), f64MejorValor);
+                hm_tpl_s_u32_sPolitica.insert((sEstado.to_string(), u32Mascara), sMejorAccion);
+            }
+        }
+
+        hm_tpl_s_u32_f64V = hm_tpl_s_u32_f64VNuevo;
+    }
+
+    (hm_tpl_s_u32_f64V, hm_tpl_s_u32_sPolitica)
+}
+
+/// Runs the continuous-reset simulation of `simulacion_1000_pasos` with one-time pickups.
+///
+/// The collected-pickups mask is cleared whenever a new episode starts (after reaching the goal
+/// or a danger state), so every episode can collect each pickup once.
+///
+/// # Arguments
+///
+/// * `ref_hm_tpl_s_u32_sPolitica` - The policy returned by `value_iteration_con_recogibles`.
+/// * `ref_vec_tpl_s_f64Recogibles` - The same pickups used to solve.
+/// * `uiMaxPasos` - The total number of steps for the simulation.
+///
+/// # Returns
+///
+/// A tuple `(usize, usize, usize)`: goals reached, danger states entered and pickups collected.
+pub fn simulacion_con_recogibles(
+    ref_hm_tpl_s_u32_sPolitica: &PoliticaConRecogibles,
+    ref_vec_tpl_s_f64Recogibles: &[(&str, f64)],
+    uiMaxPasos: usize,
+) -> (usize, usize, usize) {
+    let vec_sEstadosValidos: Vec<&'static str> = MAPA_ESTADOS
+        .iter()
+        .flatten()
+        .copied()
+        .filter(|sEstado| *sEstado != ESTADO_META && !OBSTACULOS.contains(sEstado))
+        .collect();
+    let hm_s_f64RecompensasMap = obtener_recompensas();
+    let entorno = Entorno::default();
+
+    let mut rngThreadRng = thread_rng();
+    let mut sEstadoActual: &'static str = vec_sEstadosValidos.choose(&mut rngThreadRng).unwrap();
+    let mut u32Mascara = 0u32;
+
+    let mut uiLlegoMetaCount = 0;
+    let mut uiCayoPeligroCount = 0;
+    let mut uiRecogidosCount = 0;
+    let mut f64RecompensaTotalSim = 0.0;
+
+    for _ in 0..uiMaxPasos {
+        let (f64Recompensa, u32MascaraSiguiente) = recompensa_y_mascara(
+            &hm_s_f64RecompensasMap,
+            ref_vec_tpl_s_f64Recogibles,
+            sEstadoActual,
+            u32Mascara,
+        );
+        f64RecompensaTotalSim += f64Recompensa;
+        if u32MascaraSiguiente != u32Mascara {
+            uiRecogidosCount += 1;
+        }
+
+        if sEstadoActual == ESTADO_META || ESTADOS_PELIGRO.contains(&sEstadoActual) {
+            if sEstadoActual == ESTADO_META {
+                uiLlegoMetaCount += 1;
+            } else {
+                uiCayoPeligroCount += 1;
+            }
+            // New episode: new start and all pickups available again
+            sEstadoActual = vec_sEstadosValidos.choose(&mut rngThreadRng).unwrap();
+            u32Mascara = 0;
+            continue;
+        }
+
+        match ref_hm_tpl_s_u32_sPolitica.get(&(sEstadoActual.to_string(), u32Mascara)) {
+            Some(sAccion) => {
+                sEstadoActual = resolver_destino(&entorno, sEstadoActual, sAccion).unwrap();
+                u32Mascara = u32MascaraSiguiente;
+            }
+            None => break,
+        }
+    }
+
+    log::info!("Llegadas a meta: {}", uiLlegoMetaCount);
+    log::info!("Caídas en peligro: {}", uiCayoPeligroCount);
+    log::info!("Recogibles obtenidos: {}", uiRecogidosCount);
+    log::info!("Recompensa total: {:.2}", f64RecompensaTotalSim);
+    (uiLlegoMetaCount, uiCayoPeligroCount, uiRecogidosCount)
+}
@@ -0,0 +1,72 @@
+/// Markdown report summarizing a full run (1000-step results, robustness and policy maps).
+// src/reporte.rs
+use crate::texto::mapa_politica_texto;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+/// Writes a Markdown report of a run built from the in-memory results.
+///
+/// The report contains:
+/// - A table with the goals reached and danger states entered in the 1000-step simulation, per lambda.
+/// - A table with the number of policy changes under each noise model, per lambda.
+/// - The policy arrow map of each lambda, as a code block.
+/// - Links to the generated `robustez_politicas.png` and `simulacion_1000pasos.png`.
+///
+/// # Arguments
+///
+/// * `ref_vec_tpl_f64_ui_uiResumen1000Pasos` - `(lambda, goals, dangers)` per lambda, as given to `graficar_resultados_finales`.
+/// * `ref_vec_tpl_f64_vec_tpl_s_uiRobustez` - `(lambda, [(noise label, changes)])` per lambda, as given to `graficar_resultados_finales`.
+/// * `ref_vec_tpl_f64_hm_s_sPoliticas` - `(lambda, policy)` per lambda.
+/// * `sRuta` - Path of the `.md` file to write.
+///
+/// # Returns
+///
+/// `Result<(), Box<dyn std::error::Error>>` indicating success or an I/O error.
+pub fn generar_reporte_markdown(
+    ref_vec_tpl_f64_ui_uiResumen1000Pasos: &[(f64, usize, usize)],
+    ref_vec_tpl_f64_vec_tpl_s_uiRobustez: &[(f64, Vec<(String, usize)>)],
+    ref_vec_tpl_f64_hm_s_sPoliticas: &[(f64, HashMap<String, String>)],
+    sRuta: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut bwArchivo = BufWriter::new(File::create(sRuta)?);
+
+    writeln!(bwArchivo, "# Reporte de ejecución MDP\n")?;
+
+    writeln!(bwArchivo, "## Simulación de 1000 pasos\n")?;
+    writeln!(bwArchivo, "| λ | Llegadas a meta | Caídas en peligro |")?;
+    writeln!(bwArchivo, "|---|---|---|")?;
+    for (f64Lambda, uiMetas, uiPeligros) in ref_vec_tpl_f64_ui_uiResumen1000Pasos {
+        writeln!(bwArchivo, "| {:.2} | {} | {} |", f64Lambda, uiMetas, uiPeligros)?;
+    }
+    writeln!(bwArchivo, "\n![Desempeño de políticas](simulacion_1000pasos.png)\n")?;
+
+    writeln!(bwArchivo, "## Robustez de las políticas\n")?;
+    writeln!(bwArchivo, "Número de estados cuya acción cambia bajo cada modelo de ruido.\n")?;
+    // Noise labels are the same for every lambda; take them from the first entry.
+    let vec_sEtiquetas: Vec<&String> = ref_vec_tpl_f64_vec_tpl_s_uiRobustez
+        .first()
+        .map(|(_, vec_tpl_s_uiResultados)| vec_tpl_s_uiResultados.iter().map(|(sEtiqueta, _)| sEtiqueta).collect())
+        .unwrap_or_default();
+    writeln!(
+        bwArchivo,
+        "| λ | {} |",
+        vec_sEtiquetas.iter().map(|s| s.as_str()).collect::<Vec<_>>().join(" | ")
+    )?;
+    writeln!(bwArchivo, "|---|{}", "---|".repeat(vec_sEtiquetas.len()))?;
+    for (f64Lambda, vec_tpl_s_uiResultados) in ref_vec_tpl_f64_vec_tpl_s_uiRobustez {
+        let vec_sCambios: Vec<String> = vec_tpl_s_uiResultados.iter().map(|(_, uiCambios)| uiCambios.to_string()).collect();
+        writeln!(bwArchivo, "| {:.2} | {} |", f64Lambda, vec_sCambios.join(" | "))?;
+    }
+    writeln!(bwArchivo, "\n![Robustez de políticas](robustez_politicas.png)\n")?;
+
+    writeln!(bwArchivo, "## Políticas óptimas\n")?;
+    for (f64Lambda, hm_s_sPolitica) in ref_vec_tpl_f64_hm_s_sPoliticas {
+        writeln!(bwArchivo, "### λ = {:.2}\n", f64Lambda)?;
+        writeln!(bwArchivo, "```\n{}\n```\n", mapa_politica_texto(hm_s_sPolitica))?;
+    }
+
+    bwArchivo.flush()?;
+    log::info!("Reporte '{}' guardado.", sRuta);
+    Ok(())
+}
@@ -1,5 +1,13 @@
 /// Functions for evaluating the robustness of an MDP policy under different transition noise models.
-use crate::mdp_model::value_iteration;
+use crate::entorno::Entorno;
+use crate::learning::estados_iniciales_validos;
+use crate::mdp_model::{
+    evaluar_politica, meta_objetivo_por_estado, retorno_esperado, value_iteration, value_iteration_con_opciones,
+    ModeloTransicion, OpcionesValueIteration,
+};
+use crate::simulation::episodios_con_modelo;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use std::collections::HashMap;
 
 /// Defines alternative transition noise models for robustness evaluation.
@@ -74,52 +82,440 @@ pub fn construir_modelo_ruido(
     hm_s_hm_s_f64Modelo
 }
 
+/// Tolerance on the sum of the probabilities given to `construir_modelo_con_retroceso`.
+const F64_TOLERANCIA_SUMA_PROB: f64 = 1e-9;
+
+/// Creates a noise model where the robot can also slip backward, opposite to the intended action.
+///
+/// Both perpendicular directions get `f64PerpendicularProb` each, as in `construir_modelo_ruido`
+/// with a symmetric error, and the opposite direction gets `f64RetrocesoProb`.
+///
+/// # Arguments
+///
+/// * `f64CentroProb` - Probability of successfully performing the intended action.
+/// * `f64PerpendicularProb` - Probability of deviating to each of the two perpendicular directions.
+/// * `f64RetrocesoProb` - Probability of moving in the opposite direction.
+///
+/// # Returns
+///
+/// A transition model with the same shape as `construir_modelo_ruido`'s, usable by the solver in the same way.
+///
+/// # Panics
+///
+/// If `f64CentroProb + 2 * f64PerpendicularProb + f64RetrocesoProb` is not 1.
+pub fn construir_modelo_con_retroceso(
+    f64CentroProb: f64,
+    f64PerpendicularProb: f64,
+    f64RetrocesoProb: f64,
+) -> HashMap<String, HashMap<String, f64>> {
+    let f64Suma = f64CentroProb + 2.0 * f64PerpendicularProb + f64RetrocesoProb;
+    assert!(
+        (f64Suma - 1.0).abs() <= F64_TOLERANCIA_SUMA_PROB,
+        "Las probabilidades del modelo con retroceso suman {} (deben sumar 1)",
+        f64Suma
+    );
+
+    let mut hm_s_hm_s_f64Modelo = construir_modelo_ruido(f64PerpendicularProb, f64CentroProb, f64PerpendicularProb);
+    for (sAccion, hm_s_f64Transiciones) in hm_s_hm_s_f64Modelo.iter_mut() {
+        let sOpuesta = match sAccion.as_str() {
+            "N" => "S",
+            "S" => "N",
+            "E" => "O",
+            _ => "E",
+        };
+        hm_s_f64Transiciones.insert(sOpuesta.to_string(), f64RetrocesoProb);
+    }
+    hm_s_hm_s_f64Modelo
+}
+
+/// Builds the noise-free transition model: every action moves in its own direction with probability 1.0.
+///
+/// Equivalent to `construir_modelo_ruido(0.0, 1.0, 0.0)` without the zero-probability entries, and accepted
+/// by the same external-model parameters. Solving with it, the policy follows a shortest path to the goal
+/// (see `mdp_model::distancias_a_meta`).
+///
+/// # Returns
+///
+/// A `HashMap<String, HashMap<String, f64>>` mapping each action to itself with probability 1.0.
+pub fn modelo_determinista() -> HashMap<String, HashMap<String, f64>> {
+    ["N", "S", "E", "O"]
+        .iter()
+        .map(|sAccion| (sAccion.to_string(), HashMap::from([(sAccion.to_string(), 1.0)])))
+        .collect()
+}
+
 /// Evaluates the robustness of a given base policy by comparing it against policies
 /// generated under various noise models.
 ///
+/// Same as `evaluar_robustez_con_entorno` with `Entorno::default()`.
+pub fn evaluar_robustez(
+    ref_hm_s_sPoliticaBase: &HashMap<String, String>,
+    f64Lambda: f64,
+    bProgreso: bool,
+) -> Vec<(String, usize)> {
+    evaluar_robustez_con_entorno(ref_hm_s_sPoliticaBase, f64Lambda, bProgreso, &Entorno::default())
+}
+
+/// Evaluates the robustness of a given base policy by comparing it against policies
+/// generated under various noise models, all solved under the same `Entorno` rules and rewards.
+///
 /// Robustness is measured by the number of states in which the optimal action changes
 /// when the transition probabilities are altered.
+/// With the `parallel` feature the noise models are solved concurrently with rayon;
+/// the results keep the same order either way.
 ///
 /// # Arguments
 ///
 /// * `ref_hm_s_sPoliticaBase` - A reference to the base optimal policy (State -> Action).
 /// * `f64Lambda` - The discount factor used for `value_iteration`.
+/// * `bProgreso` - If `true`, prints the (lambda, noise model) combination being solved to stderr before
+///   each solve, so a stalled run shows where it stopped. This is independent of the log level.
+/// * `ref_entorno` - The rules (and reward overrides) the base policy was solved under.
 ///
 /// # Returns
 ///
 /// A `Vec<(String, usize)>` where each tuple contains:
 ///   - A string label for the noise model (e.g., "80%").
 ///   - The number of states where the policy differed from the base policy under this noise model.
-pub fn evaluar_robustez(
+pub fn evaluar_robustez_con_entorno(
     ref_hm_s_sPoliticaBase: &HashMap<String, String>,
     f64Lambda: f64,
+    bProgreso: bool,
+    ref_entorno: &Entorno,
 ) -> Vec<(String, usize)> {
-    let mut vec_tpl_s_uiResultados = Vec::new();
+    let opciones = OpcionesValueIteration {
+        entorno: ref_entorno.clone(),
+        ..Default::default()
+    };
+    // Each noise model is an independent solve, so they can run in parallel.
+    let evaluar_modelo = |tpl_f64x3_ModeloRef: &(f64, f64, f64)| -> (String, usize) {
+        let (f64Izq, f64Centro, f64Der) = *tpl_f64x3_ModeloRef;
+        let sEtiqueta = format!("{}%", (f64Centro * 100.0) as usize);
+        let hm_s_hm_s_f64ModeloRuido = construir_modelo_ruido(f64Izq, f64Centro, f64Der);
 
-    for (f64Izq, f64Centro, f64Der) in ARR_TPL_F64X3_MODELOS_RUIDO.iter() {
-        let sEtiqueta = format!("{}%", (*f64Centro * 100.0) as usize);
-        let hm_s_hm_s_f64ModeloRuido = construir_modelo_ruido(*f64Izq, *f64Centro, *f64Der);
+        // Written to stderr directly: an explicit request for progress must not depend on the log level.
+        if bProgreso {
+            eprintln!(
+                "… Resolviendo λ = {:.2}, modelo = ({:.2}, {:.2}, {:.2})",
+                f64Lambda, f64Izq, f64Centro, f64Der
+            );
+        }
 
         // Epsilon (convergence threshold) for value_iteration, can be a small constant.
         // The policy is the second element of the tuple returned by value_iteration.
-        let (_, hm_s_sPoliticaAdaptada) =
-            value_iteration(f64Lambda, 0.01, Some(&hm_s_hm_s_f64ModeloRuido));
-
-        let uiCambios = ref_hm_s_sPoliticaBase
-            .iter()
-            .filter(|(sStateKey, sOriginalActionValue)| {
-                // sStateKey: &&String, sOriginalActionValue: &&String
-                match hm_s_sPoliticaAdaptada.get(*sStateKey) {
-                    // get expects &String or String, so dereference sStateKey twice
-                    Some(sAdaptedActionValue) => sAdaptedActionValue != *sOriginalActionValue, // sAdaptedActionValue: &String
-                    None => true, // State not in adapted policy, consider it a change
-                }
-            })
-            .count();
-
-        println!("Ruido {}: {} cambios", sEtiqueta, uiCambios);
-        vec_tpl_s_uiResultados.push((sEtiqueta, uiCambios));
+        let hm_s_sPoliticaAdaptada =
+            value_iteration_con_opciones(f64Lambda, 0.01, Some(&hm_s_hm_s_f64ModeloRuido), &opciones).hm_s_sPolitica;
+
+        let uiCambios = diferencias_politica(ref_hm_s_sPoliticaBase, &hm_s_sPoliticaAdaptada).len();
+
+        (sEtiqueta, uiCambios)
+    };
+
+    // `collect` keeps the order of ARR_TPL_F64X3_MODELOS_RUIDO in both cases.
+    #[cfg(feature = "parallel")]
+    let vec_tpl_s_uiResultados: Vec<(String, usize)> =
+        ARR_TPL_F64X3_MODELOS_RUIDO.par_iter().map(evaluar_modelo).collect();
+    #[cfg(not(feature = "parallel"))]
+    let vec_tpl_s_uiResultados: Vec<(String, usize)> =
+        ARR_TPL_F64X3_MODELOS_RUIDO.iter().map(evaluar_modelo).collect();
+
+    for (sEtiqueta, uiCambios) in &vec_tpl_s_uiResultados {
+        log::info!("Ruido {}: {} cambios", sEtiqueta, uiCambios);
     }
 
     vec_tpl_s_uiResultados
 }
+
+/// Simulates one fixed policy under several labeled transition models: solve once, measure under many.
+///
+/// Every model gets the same seeded episodes as `simulation::tasa_exito` (random valid non-danger start,
+/// ending on the goal, on a danger state or after `uiMaxPasosPorEpisodio` steps), so differences between
+/// rows come from the models alone. Each row is also logged.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_sPolitica` - The policy (State -> Action), e.g. solved under the base model.
+/// * `ref_arr_tpl_s_hmModelos` - `(label, model)` pairs; models map action -> effective direction ->
+///   probability, as built by `construir_modelo_ruido` or `construir_modelo_con_retroceso`.
+/// * `uiEpisodios` - Episodes per model.
+/// * `uiMaxPasosPorEpisodio` - Step cap per episode.
+/// * `u64Semilla` - Seed for the random number generator, shared by all models.
+///
+/// # Returns
+///
+/// One `(label, success_rate, danger_rate, mean_reward)` tuple per model, in the given order. Rates are
+/// fractions of the episodes; the reward is the mean undiscounted return.
+pub fn evaluar_bajo_modelos(
+    ref_hm_s_sPolitica: &HashMap<String, String>,
+    ref_arr_tpl_s_hmModelos: &[(&str, ModeloTransicion)],
+    uiEpisodios: usize,
+    uiMaxPasosPorEpisodio: usize,
+    u64Semilla: u64,
+) -> Vec<(String, f64, f64, f64)> {
+    ref_arr_tpl_s_hmModelos
+        .iter()
+        .map(|(sEtiqueta, hm_s_hm_s_f64Modelo)| {
+            let vec_tpl_Episodios = episodios_con_modelo(
+                ref_hm_s_sPolitica,
+                uiEpisodios,
+                uiMaxPasosPorEpisodio,
+                hm_s_hm_s_f64Modelo.clone(),
+                u64Semilla,
+                None,
+                None,
+            );
+            let f64Episodios = vec_tpl_Episodios.len().max(1) as f64;
+            let f64TasaExito = vec_tpl_Episodios.iter().filter(|tpl| tpl.0).count() as f64 / f64Episodios;
+            let f64TasaPeligro = vec_tpl_Episodios.iter().filter(|tpl| tpl.1).count() as f64 / f64Episodios;
+            let f64RecompensaMedia = vec_tpl_Episodios.iter().map(|tpl| tpl.2).sum::<f64>() / f64Episodios;
+            log::info!(
+                "Modelo {}: éxito {:.1}%, peligro {:.1}%, recompensa media {:.3}",
+                sEtiqueta,
+                100.0 * f64TasaExito,
+                100.0 * f64TasaPeligro,
+                f64RecompensaMedia
+            );
+            (sEtiqueta.to_string(), f64TasaExito, f64TasaPeligro, f64RecompensaMedia)
+        })
+        .collect()
+}
+
+/// Lists the states whose action differs between two policies.
+///
+/// A state of the base policy that is missing from the other policy counts as a change.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_sPoliticaBase` - The reference policy (State -> Action).
+/// * `ref_hm_s_sPoliticaOtra` - The policy to compare against it.
+///
+/// # Returns
+///
+/// The names of the changed states, sorted.
+pub fn diferencias_politica(
+    ref_hm_s_sPoliticaBase: &HashMap<String, String>,
+    ref_hm_s_sPoliticaOtra: &HashMap<String, String>,
+) -> Vec<String> {
+    let mut vec_sCambios: Vec<String> = ref_hm_s_sPoliticaBase
+        .iter()
+        .filter(|(sEstado, sAccionBase)| match ref_hm_s_sPoliticaOtra.get(*sEstado) {
+            Some(sAccionOtra) => sAccionOtra != *sAccionBase,
+            None => true, // State not in the other policy, consider it a change
+        })
+        .map(|(sEstado, _)| sEstado.clone())
+        .collect();
+    vec_sCambios.sort();
+    vec_sCambios
+}
+
+/// Convergence threshold used for the solves in `lambdas_criticos`; tight so that
+/// policy changes come from lambda and not from an under-converged solve.
+const F64_EPSILON_LAMBDAS_CRITICOS: f64 = 1e-6;
+
+/// Finds the discount factors in `[f64LambdaMin, f64LambdaMax]` at which the optimal policy changes.
+///
+/// The interval is bisected: if the policies at both ends are equal the sub-interval is assumed
+/// constant, otherwise it is split at the midpoint until it is narrower than `f64Tolerancia`, and its
+/// midpoint is reported as a threshold. The recursion depth is therefore about
+/// log2((max - min) / tolerancia). A policy that changes and changes back inside an interval whose
+/// ends agree is not detected.
+///
+/// # Arguments
+///
+/// * `f64LambdaMin` - Lower end of the interval.
+/// * `f64LambdaMax` - Upper end of the interval (below 1).
+/// * `f64Tolerancia` - Width at which bisection stops; thresholds are accurate to ±tolerancia/2.
+///
+/// # Returns
+///
+/// The critical lambda values, in increasing order.
+pub fn lambdas_criticos(f64LambdaMin: f64, f64LambdaMax: f64, f64Tolerancia: f64) -> Vec<f64> {
+    let resolver = |f64Lambda: f64| value_iteration(f64Lambda, F64_EPSILON_LAMBDAS_CRITICOS, None).1;
+
+    let mut vec_f64Criticos = Vec::new();
+    // Pending intervals: (lambda_lo, policy_lo, lambda_hi, policy_hi)
+    let mut vec_tpl_Pendientes = vec![(f64LambdaMin, resolver(f64LambdaMin), f64LambdaMax, resolver(f64LambdaMax))];
+
+    while let Some((f64Lo, hm_s_sPoliticaLo, f64Hi, hm_s_sPoliticaHi)) = vec_tpl_Pendientes.pop() {
+        if diferencias_politica(&hm_s_sPoliticaLo, &hm_s_sPoliticaHi).is_empty() {
+            continue;
+        }
+        let f64Medio = (f64Lo + f64Hi) / 2.0;
+        if f64Hi - f64Lo <= f64Tolerancia {
+            vec_f64Criticos.push(f64Medio);
+            continue;
+        }
+        let hm_s_sPoliticaMedio = resolver(f64Medio);
+        vec_tpl_Pendientes.push((f64Lo, hm_s_sPoliticaLo, f64Medio, hm_s_sPoliticaMedio.clone()));
+        vec_tpl_Pendientes.push((f64Medio, hm_s_sPoliticaMedio, f64Hi, hm_s_sPoliticaHi));
+    }
+
+    vec_f64Criticos.sort_by(|f64A, f64B| f64A.partial_cmp(f64B).unwrap());
+    vec_f64Criticos
+}
+
+/// Largest discount factor tried by `lambda_minimo_busca_meta`; closer to 1 the solves get slow.
+const F64_LAMBDA_MAX_BUSCA_META: f64 = 0.99;
+
+/// Checks whether the optimal policy for `f64Lambda` takes every start state to the goal.
+///
+/// Paths are followed without noise, as in `mdp_model::meta_objetivo_por_estado`.
+fn politica_busca_meta(f64Lambda: f64, ref_opciones: &OpcionesValueIteration) -> bool {
+    let hm_s_sPolitica =
+        value_iteration_con_opciones(f64Lambda, F64_EPSILON_LAMBDAS_CRITICOS, None, ref_opciones).hm_s_sPolitica;
+    let hm_s_opt_sMetas = meta_objetivo_por_estado(&hm_s_sPolitica);
+    estados_iniciales_validos()
+        .into_iter()
+        .all(|sEstado| matches!(hm_s_opt_sMetas.get(sEstado), Some(Some(_))))
+}
+
+/// Finds the smallest discount factor whose optimal policy leads every start state to the goal.
+///
+/// Same as `lambda_minimo_busca_meta_con_entorno` with `Entorno::default()`.
+pub fn lambda_minimo_busca_meta(f64Tolerancia: f64) -> Option<f64> {
+    lambda_minimo_busca_meta_con_entorno(f64Tolerancia, &Entorno::default())
+}
+
+/// Finds the smallest discount factor whose optimal policy, solved under `ref_entorno`, leads every start
+/// state to the goal.
+///
+/// With a living penalty, a small lambda makes a distant goal worth almost nothing, so the policy only
+/// minimizes the immediate cost and may wander or bump into walls. `[0, F64_LAMBDA_MAX_BUSCA_META]` is
+/// bisected on whether the optimal policy reaches the goal from every state of
+/// `learning::estados_iniciales_validos()` when followed without noise. As in `lambdas_criticos`, the
+/// property is assumed to flip only once in the interval.
+///
+/// # Arguments
+///
+/// * `f64Tolerancia` - Width at which bisection stops.
+/// * `ref_entorno` - The rules (and reward overrides) to solve under.
+///
+/// # Returns
+///
+/// The upper end of the final interval, whose policy reaches the goal (within `f64Tolerancia` of the
+/// threshold), or `None` if even the policy at `F64_LAMBDA_MAX_BUSCA_META` does not.
+///
+/// # Panics
+///
+/// If `f64Tolerancia` is not positive.
+pub fn lambda_minimo_busca_meta_con_entorno(f64Tolerancia: f64, ref_entorno: &Entorno) -> Option<f64> {
+    assert!(f64Tolerancia > 0.0, "lambda_minimo_busca_meta: la tolerancia debe ser positiva");
+    let opciones = OpcionesValueIteration {
+        entorno: ref_entorno.clone(),
+        ..Default::default()
+    };
+    if !politica_busca_meta(F64_LAMBDA_MAX_BUSCA_META, &opciones) {
+        return None;
+    }
+    if politica_busca_meta(0.0, &opciones) {
+        return Some(0.0);
+    }
+    let (mut f64Lo, mut f64Hi) = (0.0, F64_LAMBDA_MAX_BUSCA_META);
+    while f64Hi - f64Lo > f64Tolerancia {
+        let f64Medio = (f64Lo + f64Hi) / 2.0;
+        if politica_busca_meta(f64Medio, &opciones) {
+            f64Hi = f64Medio;
+        } else {
+            f64Lo = f64Medio;
+        }
+    }
+    log::info!("Lambda mínimo que busca la meta: {:.4}", f64Hi);
+    Some(f64Hi)
+}
+
+/// Convergence threshold used for the solves and evaluations in `regret_por_modelo`.
+const F64_EPSILON_REGRET: f64 = 1e-6;
+
+/// Measures how much the baseline policy loses under each noise model compared with re-solving for it.
+///
+/// The baseline is the optimal policy for the base model (80% success). For each model of
+/// `ARR_TPL_F64X3_MODELOS_RUIDO`, the regret is the expected start-state value (`retorno_esperado`,
+/// uniform start) of that model's optimal policy minus that of the baseline policy, both evaluated under
+/// the same model with `evaluar_politica` (as in `valor_informacion`), so the two values carry the same
+/// evaluation error. It is 0 (up to the solver tolerance) for the base model itself and never
+/// meaningfully negative.
+///
+/// # Arguments
+///
+/// * `f64Lambda` - The discount factor (gamma); must be below 1.
+///
+/// # Returns
+///
+/// One `(label, regret)` per noise model, in the order of `ARR_TPL_F64X3_MODELOS_RUIDO`, labeled by
+/// its `(left, center, right)` probabilities, e.g. "(0.10, 0.80, 0.10)".
+pub fn regret_por_modelo(f64Lambda: f64) -> Vec<(String, f64)> {
+    let (_, hm_s_sPoliticaBase) = value_iteration(f64Lambda, F64_EPSILON_REGRET, None);
+
+    ARR_TPL_F64X3_MODELOS_RUIDO
+        .iter()
+        .map(|&(f64Izq, f64Centro, f64Der)| {
+            let sEtiqueta = format!("({:.2}, {:.2}, {:.2})", f64Izq, f64Centro, f64Der);
+            let hm_s_hm_s_f64ModeloRuido = construir_modelo_ruido(f64Izq, f64Centro, f64Der);
+
+            let (_, hm_s_sPoliticaOptima) =
+                value_iteration(f64Lambda, F64_EPSILON_REGRET, Some(&hm_s_hm_s_f64ModeloRuido));
+            let hm_s_f64ValoresOptimos = evaluar_politica(
+                &hm_s_sPoliticaOptima,
+                f64Lambda,
+                F64_EPSILON_REGRET,
+                Some(&hm_s_hm_s_f64ModeloRuido),
+            );
+            let hm_s_f64ValoresBase = evaluar_politica(
+                &hm_s_sPoliticaBase,
+                f64Lambda,
+                F64_EPSILON_REGRET,
+                Some(&hm_s_hm_s_f64ModeloRuido),
+            );
+            let f64Regret =
+                retorno_esperado(&hm_s_f64ValoresOptimos, None) - retorno_esperado(&hm_s_f64ValoresBase, None);
+
+            log::info!("Regret {}: {:.4}", sEtiqueta, f64Regret);
+            (sEtiqueta, f64Regret)
+        })
+        .collect()
+}
+
+/// Convergence threshold used for the solves and evaluations in `valor_informacion`.
+const F64_EPSILON_VALOR_INFORMACION: f64 = 1e-6;
+
+/// Computes the value of knowing the true noise model instead of planning under an assumed one.
+///
+/// Solves under both models, then evaluates both optimal policies under `ref_modelo_real` with
+/// `evaluar_politica`. The gain at a state is V(real-optimal policy) − V(assumed-optimal policy), both
+/// under the real model: 0 where the wrong assumption costs nothing, largest where misestimating the
+/// noise hurts most. Models can come from `construir_modelo_ruido` or `construir_modelo_con_retroceso`.
+///
+/// # Arguments
+///
+/// * `f64Lambda` - The discount factor (gamma); must be below 1.
+/// * `ref_modelo_asumido` - The transition model planned with.
+/// * `ref_modelo_real` - The transition model the robot actually follows.
+///
+/// # Returns
+///
+/// A tuple with the gain at every non-goal, non-obstacle state and its aggregate, the gain in expected
+/// start-state value (`retorno_esperado`, uniform start).
+pub fn valor_informacion(
+    f64Lambda: f64,
+    ref_modelo_asumido: &HashMap<String, HashMap<String, f64>>,
+    ref_modelo_real: &HashMap<String, HashMap<String, f64>>,
+) -> (HashMap<String, f64>, f64) {
+    let (_, hm_s_sPoliticaAsumida) =
+        value_iteration(f64Lambda, F64_EPSILON_VALOR_INFORMACION, Some(ref_modelo_asumido));
+    let (_, hm_s_sPoliticaReal) = value_iteration(f64Lambda, F64_EPSILON_VALOR_INFORMACION, Some(ref_modelo_real));
+
+    let hm_s_f64ValoresAsumida =
+        evaluar_politica(&hm_s_sPoliticaAsumida, f64Lambda, F64_EPSILON_VALOR_INFORMACION, Some(ref_modelo_real));
+    let hm_s_f64ValoresReal =
+        evaluar_politica(&hm_s_sPoliticaReal, f64Lambda, F64_EPSILON_VALOR_INFORMACION, Some(ref_modelo_real));
+
+    let hm_s_f64Ganancia: HashMap<String, f64> = estados_iniciales_validos()
+        .into_iter()
+        .map(|sEstado| (sEstado.to_string(), hm_s_f64ValoresReal[sEstado] - hm_s_f64ValoresAsumida[sEstado]))
+        .collect();
+    let f64GananciaTotal =
+        retorno_esperado(&hm_s_f64ValoresReal, None) - retorno_esperado(&hm_s_f64ValoresAsumida, None);
+
+    log::info!("Valor de la información: {:.4}", f64GananciaTotal);
+    (hm_s_f64Ganancia, f64GananciaTotal)
+}
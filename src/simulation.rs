@@ -1,99 +1,368 @@
 /// Handles MDP simulation, including visual simulation with Macroquad and a 1000-step statistical simulation.
 // src/simulation.rs
-use crate::config::{
-    acciones, obtener_recompensas, ESTADOS_PELIGRO, ESTADO_META, MAPA_ESTADOS, OBSTACULOS,
+use crate::config::{acciones, COLUMNAS_MAPA, ESTADO_META, FILAS_MAPA, MAPA_ESTADOS, OBSTACULOS};
+use crate::entorno::{
+    alternar_tipo_celda, direccion_con_viento, es_choque, es_peligro_con_entorno, es_peligro_terminal, paga_choque,
+    recompensa_entrada, recompensas_con_entorno, reinicia_en_peligro, resolver_destino, tipo_celda_con_entorno,
+    valor_meta, Entorno,
 };
-use crate::mdp_model::{obtener_estado, obtener_posicion}; // Assuming these are already updated
+#[cfg(feature = "egui")]
+use crate::inspector::Inspector;
+use crate::learning::{
+    accion_softmax, distribucion_softmax, estados_iniciales_validos, muestrear_direccion, paso_entorno,
+    EntornoAprendizaje, TablaQ,
+};
+use crate::mdp_model::{
+    modelo_transicion_base, obtener_posicion, siguiente_estado_determinista, value_iteration_con_opciones,
+    ModeloTransicion, OpcionesValueIteration,
+}; // Assuming these are already updated
+use crate::robustness::{construir_modelo_ruido, modelo_determinista};
+use crate::texto::TipoCelda;
+use ::rand::rngs::StdRng;
 use ::rand::seq::SliceRandom;
 use ::rand::thread_rng;
-use ::rand::Rng;
+use ::rand::{Rng, SeedableRng};
 use macroquad::prelude::*;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 
 /// Size of each cell in pixels for visual simulation.
 const F32_TAMANO_CELDA: f32 = 80.0;
 /// Margin around cells in pixels.
 const F32_MARGEN: f32 = 2.0;
-/// Default color for map cells.
-const MQ_COLOR_NORMAL: Color = GRAY;
-/// Color for danger/pit cells.
-const MQ_COLOR_PELIGRO: Color = RED;
-/// Color for the goal cell.
-const MQ_COLOR_META: Color = GREEN;
-/// Color for the robot's current cell.
-const MQ_COLOR_ROBOT: Color = BLUE;
-/// Color for obstacle cells.
-const MQ_COLOR_OBSTACULO: Color = DARKGRAY;
+/// Seconds a slip stays highlighted in `reproducir_trayectoria` after the move.
+const F64_DURACION_DESVIO: f64 = 0.3;
+/// Number of previously visited cells drawn as a fading trail behind the robot.
+const UI_LONGITUD_ESTELA: usize = 12;
+/// Width in pixels of the slider tracks in the control panel.
+const F32_ANCHO_SLIDER: f32 = 200.0;
+/// Horizontal offset in pixels from a slider's label to its track.
+const F32_OFFSET_SLIDER: f32 = 90.0;
+
+/// Colors of the visual simulation, passed to `ejecutar_simulacion`.
+///
+/// `TemaColores::default()` is the original palette; `TemaColores::daltonico()` avoids the red/green
+/// pairing of dangers and goal for viewers with color-vision deficiencies.
+#[derive(Debug, Clone, Copy)]
+pub struct TemaColores {
+    /// Color for ordinary map cells.
+    pub mqColorNormal: Color,
+    /// Color for danger/pit cells.
+    pub mqColorPeligro: Color,
+    /// Color for the goal cell.
+    pub mqColorMeta: Color,
+    /// Color for the robot's current cell and trail.
+    pub mqColorRobot: Color,
+    /// Color for obstacle cells.
+    pub mqColorObstaculo: Color,
+    /// Color that cells are tinted toward as their visit count grows.
+    pub mqColorVisitas: Color,
+}
+
+impl Default for TemaColores {
+    fn default() -> Self {
+        TemaColores {
+            mqColorNormal: GRAY,
+            mqColorPeligro: RED,
+            mqColorMeta: GREEN,
+            mqColorRobot: BLUE,
+            mqColorObstaculo: DARKGRAY,
+            mqColorVisitas: ORANGE,
+        }
+    }
+}
+
+impl TemaColores {
+    /// Colorblind-safe theme from the Okabe–Ito palette: vermillion dangers, blue goal, reddish purple
+    /// robot and a yellow visit tint, which stay distinct under protanopia, deuteranopia and tritanopia.
+    pub fn daltonico() -> Self {
+        TemaColores {
+            mqColorNormal: Color::from_rgba(190, 190, 190, 255),
+            mqColorPeligro: Color::from_rgba(213, 94, 0, 255),
+            mqColorMeta: Color::from_rgba(0, 114, 178, 255),
+            mqColorRobot: Color::from_rgba(204, 121, 167, 255),
+            mqColorObstaculo: Color::from_rgba(60, 60, 60, 255),
+            mqColorVisitas: Color::from_rgba(240, 228, 66, 255),
+        }
+    }
+}
+
+/// Softmax exploration with an annealed temperature for `ejecutar_simulacion`.
+///
+/// Each step samples the action with `learning::accion_softmax` at the temperature of
+/// `temperatura_en_paso`, which falls geometrically from `f64TemperaturaInicial` at the first step to
+/// `f64TemperaturaFinal` at the last, so the robot wanders at first and becomes goal-directed.
+#[derive(Debug, Clone)]
+pub struct RecocidoSoftmax {
+    /// The action values to sample from (e.g. from `mdp_model::tabla_q_desde_valores`).
+    pub tabla_q: TablaQ,
+    /// Temperature at the first step (positive).
+    pub f64TemperaturaInicial: f64,
+    /// Temperature at the last step (positive).
+    pub f64TemperaturaFinal: f64,
+}
+
+impl RecocidoSoftmax {
+    /// Temperature at step `uiPaso` (0-based) of a rollout of `uiPasos` steps.
+    pub fn temperatura_en_paso(&self, uiPaso: usize, uiPasos: usize) -> f64 {
+        let f64Fraccion = if uiPasos > 1 { uiPaso.min(uiPasos - 1) as f64 / (uiPasos - 1) as f64 } else { 1.0 };
+        self.f64TemperaturaInicial * (self.f64TemperaturaFinal / self.f64TemperaturaInicial).powf(f64Fraccion)
+    }
+}
+
+/// Optional settings for `simulacion_1000_pasos_con_opciones`.
+///
+/// `OpcionesSimulacion::default()` reproduces `simulacion_1000_pasos` exactly.
+#[derive(Debug, Clone, Default)]
+pub struct OpcionesSimulacion {
+    /// Extra movement rules; should match the `Entorno` the policy was solved with.
+    pub entorno: Entorno,
+    /// If set, the robot is reset to a random valid state as soon as it has visited the same
+    /// state more than this many times since its last reset (a policy loop that never reaches
+    /// the goal). These resets are counted separately from goals and dangers.
+    pub opt_uiReinicioPorEstancamiento: Option<usize>,
+    /// If `true`, reaching the goal ends the rollout instead of resetting to a random state, so the
+    /// run measures a single episode (see `simulacion_episodio`). Dangers still reset the robot.
+    pub bTerminarEnMeta: bool,
+    /// If set, every rollout (the first one and each one after a reset) starts from this state instead
+    /// of a random valid one, so runs with different parameters are comparable. It must pass
+    /// `es_estado_inicial_valido`.
+    pub opt_sEstadoInicial: Option<String>,
+    /// If set, rollouts start from a state drawn with these weights (normalized; see
+    /// `distribucion_inicial_ordenada` for the checks) instead of uniformly. `opt_sEstadoInicial` takes precedence.
+    pub opt_hm_s_f64DistribucionInicial: Option<HashMap<String, f64>>,
+    /// If set, each move slips as in this transition model (e.g. `robustness::construir_modelo_ruido`)
+    /// before any wind, so the rollout sees the same noise the policy was solved with. `None` moves in
+    /// the intended direction, as does an action missing from the model.
+    pub opt_hm_s_hm_s_f64Modelo: Option<ModeloTransicion>,
+}
+
+impl OpcionesSimulacion {
+    /// Checks the user-supplied start settings: `opt_sEstadoInicial` must pass `es_estado_inicial_valido`
+    /// and `opt_hm_s_f64DistribucionInicial` must pass `distribucion_inicial_ordenada`.
+    ///
+    /// The simulations call it first and return its error, so calling it where the options are built
+    /// only reports problems earlier.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())`, or `Err` describing the first problem found.
+    pub fn validar(&self) -> Result<(), String> {
+        if let Some(sEstado) = &self.opt_sEstadoInicial {
+            if !es_estado_inicial_valido(sEstado) {
+                return Err(format!("Estado inicial no válido: {}", sEstado));
+            }
+        }
+        if let Some(ref_hm_s_f64Distribucion) = &self.opt_hm_s_f64DistribucionInicial {
+            distribucion_inicial_ordenada(ref_hm_s_f64Distribucion)?;
+        }
+        Ok(())
+    }
+}
+
+/// Running tally of the visual rollouts of a session, shown by `ejecutar_simulacion` as a scoreboard.
+///
+/// Create one with `MarcadorSesion::default()` and pass it to every `ejecutar_simulacion` call so the
+/// totals carry over from one run (and one lambda) to the next.
+#[derive(Debug, Clone, Default)]
+pub struct MarcadorSesion {
+    /// Rollouts finished, including those cut short by "Re-resolver".
+    pub uiRollouts: usize,
+    /// Rollouts that reached the goal.
+    pub uiMetas: usize,
+    /// Entries into a danger cell, over all rollouts.
+    pub uiPeligros: usize,
+    /// Sum of the steps taken by the rollouts that reached the goal.
+    pub uiPasosHastaMeta: usize,
+}
+
+impl MarcadorSesion {
+    /// Records the end of a rollout after `uiPasos` steps; `bMeta` tells whether it reached the goal.
+    pub fn registrar_rollout(&mut self, bMeta: bool, uiPasos: usize) {
+        self.uiRollouts += 1;
+        if bMeta {
+            self.uiMetas += 1;
+            self.uiPasosHastaMeta += uiPasos;
+        }
+    }
+
+    /// Average number of steps of the rollouts that reached the goal, or `None` if none did.
+    pub fn pasos_medios_hasta_meta(&self) -> Option<f64> {
+        (self.uiMetas > 0).then(|| self.uiPasosHastaMeta as f64 / self.uiMetas as f64)
+    }
+}
+
+/// Checks whether a state can start a rollout: it is on the map and is neither an obstacle nor the goal.
+pub fn es_estado_inicial_valido(sEstado: &str) -> bool {
+    obtener_posicion(sEstado).is_some() && sEstado != ESTADO_META && !OBSTACULOS.contains(&sEstado)
+}
+
+/// Checks a start-state distribution and lists its entries in a fixed (name) order, so a seeded draw
+/// gives the same state every run. Weights need not sum to 1; they are normalized when sampling.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_f64Distribucion` - Weight of each start state.
+///
+/// # Returns
+///
+/// The `(state, weight)` entries, sorted by state name, or `Err` if a listed state is not a valid start
+/// state (see `es_estado_inicial_valido`), a weight is negative or not finite, or all weights are 0.
+pub fn distribucion_inicial_ordenada(
+    ref_hm_s_f64Distribucion: &HashMap<String, f64>,
+) -> Result<Vec<(&'static str, f64)>, String> {
+    let mut vec_tpl_s_f64Entradas: Vec<(&'static str, f64)> = Vec::with_capacity(ref_hm_s_f64Distribucion.len());
+    for (sEstado, f64Peso) in ref_hm_s_f64Distribucion {
+        if !es_estado_inicial_valido(sEstado) {
+            return Err(format!("Estado inicial no válido en la distribución: {}", sEstado));
+        }
+        if !f64Peso.is_finite() || *f64Peso < 0.0 {
+            return Err(format!("Peso no válido en la distribución inicial: {} = {}", sEstado, f64Peso));
+        }
+        let (uiFila, uiCol) = obtener_posicion(sEstado).unwrap();
+        vec_tpl_s_f64Entradas.push((MAPA_ESTADOS[uiFila][uiCol], *f64Peso));
+    }
+    if !vec_tpl_s_f64Entradas.iter().any(|(_, f64Peso)| *f64Peso > 0.0) {
+        return Err("La distribución inicial no tiene peso".to_string());
+    }
+    vec_tpl_s_f64Entradas.sort_by(|tplA, tplB| tplA.0.cmp(tplB.0));
+    Ok(vec_tpl_s_f64Entradas)
+}
+
+/// Returns `opt_sEstadoInicial` if set, otherwise a state drawn from the weighted entries of
+/// `opt_ref_arr_tpl_s_f64Distribucion` (see `distribucion_inicial_ordenada`), otherwise a random state of
+/// `ref_vec_sEstadosValidos`. Callers check `opt_sEstadoInicial` with `es_estado_inicial_valido` first.
+fn estado_inicial(
+    opt_sEstadoInicial: Option<&str>,
+    opt_ref_arr_tpl_s_f64Distribucion: Option<&[(&'static str, f64)]>,
+    ref_vec_sEstadosValidos: &[String],
+    ref_mut_rng: &mut impl Rng,
+) -> String {
+    match (opt_sEstadoInicial, opt_ref_arr_tpl_s_f64Distribucion) {
+        (Some(sEstado), _) => sEstado.to_string(),
+        (None, Some(ref_arr_tpl_s_f64Distribucion)) => {
+            ref_arr_tpl_s_f64Distribucion.choose_weighted(ref_mut_rng, |tpl| tpl.1).unwrap().0.to_string()
+        }
+        (None, None) => ref_vec_sEstadosValidos.choose(ref_mut_rng).unwrap().clone(),
+    }
+}
 
 /// Runs a visual simulation of the robot navigating the map using Macroquad.
 ///
-/// The robot starts at a random non-goal, non-obstacle state.
+/// The robot starts at `opt_sEstadoInicial`, or at a random non-goal, non-obstacle state if it is `None`.
 /// It attempts to follow the provided policy but includes an epsilon chance for random exploration.
 /// The simulation runs for a specified number of steps or until the robot reaches the goal.
+/// The last `UI_LONGITUD_ESTELA` visited cells are drawn as a translucent trail that fades with age,
+/// and every cell is tinted toward the theme's visit color in proportion to how often it has been visited
+/// in the current rollout, which makes oscillation easy to spot.
+///
+/// Moves follow the movement rules of `ref_entorno` and slip as in the transition model the shown policy was
+/// solved with: `mdp_model::modelo_transicion_base` at first.
+///
+/// A control panel below the map has sliders for lambda and the success probability.
+/// Pressing "Re-resolver" runs `value_iteration_con_opciones` with those values (splitting the failure
+/// probability evenly between the two perpendicular directions) under `ref_entorno`, swaps the window's
+/// policy arrows and values for the new ones, makes the robot slip with the new probability and restarts
+/// the rollout from the start state (a new random one if `opt_sEstadoInicial` is `None`). The re-solve
+/// only lives in the window: the caller's policy is untouched.
+///
+/// Right-clicking a map cell cycles its type (normal → obstacle → danger → goal → normal) in a window-local copy
+/// of `ref_entorno` (see `entorno::alternar_tipo_celda`), redraws the map and re-solves as "Re-resolver" does.
+/// The goal `ESTADO_META` and the map's own obstacles cannot be edited; the reason is shown next to the button.
+///
+/// Below the per-run info line, a session scoreboard shows the totals of `ref_mut_marcador`: rollouts,
+/// goals, danger entries and average steps to the goal. The run adds its own rollouts to it.
+///
+/// Every cell shows its policy arrow and V(s). With the `egui` feature an inspector panel
+/// (see `inspector::Inspector`) draws them instead: clicking a cell shows its Q-values, and both
+/// overlays can be toggled.
 ///
 /// # Arguments
 ///
-/// * `ref_mut_hm_s_sPolitica` - A mutable reference to the policy (State -> Action) to follow.
+/// * `ref_hm_s_sPolitica` - The policy (State -> Action) to follow.
+/// * `ref_hm_s_f64Valores` - The state values the policy was derived from, drawn in each cell.
+/// * `f64Lambda` - The discount factor the policy was solved with (initial slider value).
+/// * `ref_entorno` - Extra movement rules; should match the `Entorno` the policy was solved with.
 /// * `uiPasos` - Maximum number of steps for this simulation run.
 /// * `ref_mut_hm_s_f64RecompensasMap` - Mutable reference to rewards map (used to increment if goal is reached, though this seems unusual here).
+/// * `opt_sEstadoInicial` - Fixed start state, which must pass `es_estado_inicial_valido`.
+/// * `ref_mut_marcador` - The session scoreboard, updated as rollouts end.
+/// * `ref_tema` - Colors of the map, trail and controls; a legend of the cell colors is drawn beside the map.
+/// * `opt_recocido` - If set, actions are sampled from an annealed softmax instead (see `RecocidoSoftmax`),
+///   and the current temperature and action distribution are shown in the info panel.
+///
+/// # Returns
+///
+/// `Ok(())` once the window is closed, or `Err` (before opening it) if `opt_sEstadoInicial` is not a valid
+/// start state or a temperature of `opt_recocido` is not positive.
+#[allow(clippy::too_many_arguments)]
 pub async fn ejecutar_simulacion(
-    ref_mut_hm_s_sPolitica: &mut HashMap<String, String>,
+    ref_hm_s_sPolitica: &HashMap<String, String>,
+    ref_hm_s_f64Valores: &HashMap<&'static str, f64>,
+    f64Lambda: f64,
+    ref_entorno: &Entorno,
     uiPasos: usize,
     ref_mut_hm_s_f64RecompensasMap: &mut HashMap<&'static str, f64>,
-) {
+    opt_sEstadoInicial: Option<String>,
+    ref_mut_marcador: &mut MarcadorSesion,
+    ref_tema: &TemaColores,
+    opt_recocido: Option<&RecocidoSoftmax>,
+) -> Result<(), String> {
+    if let Some(ref_recocido) = opt_recocido {
+        if ref_recocido.f64TemperaturaInicial <= 0.0 || ref_recocido.f64TemperaturaFinal <= 0.0 {
+            return Err("ejecutar_simulacion: las temperaturas del recocido deben ser positivas".to_string());
+        }
+    }
+    if let Some(sEstado) = opt_sEstadoInicial.as_deref() {
+        if !es_estado_inicial_valido(sEstado) {
+            return Err(format!("Estado inicial no válido: {}", sEstado));
+        }
+    }
     let mut rngThreadRng = ::rand::thread_rng();
+    let mut rngSoftmax = StdRng::from_entropy();
     let f64EpsilonSim = 0.8; // Epsilon for exploration in simulation
 
-    // Initialize the current state from a random valid state
-    let vec_sEstadosValidos: Vec<String> = MAPA_ESTADOS
-        .iter()
-        .flatten()
-        .filter(|&&sEstadoRef| sEstadoRef != ESTADO_META && !OBSTACULOS.contains(&sEstadoRef))
-        .map(|&sEstadoRef| sEstadoRef.to_string())
-        .collect();
+    // Movement rules of the window, changed by the cell editor (right click)
+    let mut entornoPanel = ref_entorno.clone();
+    let mut sMensajeEditor = String::new();
 
-    let mut sEstadoActual = vec_sEstadosValidos
-        .choose(&mut rngThreadRng)
-        .unwrap()
-        .clone();
+    // Initialize the current state from the fixed start state, or a random valid one
+    let mut vec_sEstadosValidos = estados_iniciales_con_entorno(&entornoPanel);
+
+    let mut sEstadoActual =
+        estado_inicial(opt_sEstadoInicial.as_deref(), None, &vec_sEstadosValidos, &mut rngThreadRng);
     let mut uiPasoActual = 0;
+    // Recently visited states, oldest first (excludes the current state)
+    let mut vdq_sEstela: VecDeque<String> = VecDeque::with_capacity(UI_LONGITUD_ESTELA);
+    // Visits per state in the current rollout
+    let mut hm_s_uiVisitas: HashMap<String, usize> = HashMap::from([(sEstadoActual.clone(), 1)]);
 
     // Speed control: time between movements
     let mut f64UltimoMovimiento = get_time();
     let f64IntervaloMovimiento = 0.5; // Adjust this value to change speed (in seconds)
 
+    // Control panel state
+    let mut f64LambdaPanel = f64Lambda;
+    let mut f64ProbExitoPanel = 0.8;
+    // Transition model the robot slips with, replaced by "Re-resolver"
+    let mut hm_s_hm_s_f64ModeloPanel = modelo_transicion_base();
+    // Policy and values shown and followed by the window, replaced by "Re-resolver"
+    // (with `egui` the inspector keeps its own values)
+    let mut hm_s_sPoliticaPanel = ref_hm_s_sPolitica.clone();
+    #[cfg(not(feature = "egui"))]
+    let mut hm_s_f64ValoresPanel = ref_hm_s_f64Valores.clone();
+    #[cfg(feature = "egui")]
+    let _ = ref_hm_s_f64Valores;
+    #[cfg(feature = "egui")]
+    let mut inspector = Inspector::nuevo(f64Lambda, None, &entornoPanel);
+
     loop {
         clear_background(WHITE);
 
-        // Draw the map
-        for (uiIFila, ref_arr_sFilaEstados) in MAPA_ESTADOS.iter().enumerate() {
-            for (uiICol, ref_sEstadoNombre) in ref_arr_sFilaEstados.iter().enumerate() {
-                let sEstadoDeCelda: &'static str = *ref_sEstadoNombre;
-                let mqColorCell = if OBSTACULOS.contains(&sEstadoDeCelda) {
-                    MQ_COLOR_OBSTACULO
-                } else if ESTADOS_PELIGRO.contains(&sEstadoDeCelda) {
-                    MQ_COLOR_PELIGRO
-                } else if sEstadoDeCelda == ESTADO_META {
-                    MQ_COLOR_META
-                } else if sEstadoDeCelda == sEstadoActual.as_str() {
-                    // Compare &str with &str
-                    MQ_COLOR_ROBOT
-                } else {
-                    MQ_COLOR_NORMAL
-                };
-
-                let f32X = uiICol as f32 * F32_TAMANO_CELDA;
-                let f32Y = uiIFila as f32 * F32_TAMANO_CELDA;
-
-                draw_rectangle(
-                    f32X + F32_MARGEN,
-                    f32Y + F32_MARGEN,
-                    F32_TAMANO_CELDA - 2.0 * F32_MARGEN,
-                    F32_TAMANO_CELDA - 2.0 * F32_MARGEN,
-                    mqColorCell,
-                );
-            }
-        }
+        // Draw the map and the trail
+        let uiMaxVisitas = dibujar_mapa(&sEstadoActual, &hm_s_uiVisitas, ref_tema, &entornoPanel);
+        dibujar_estela(&vdq_sEstela, &sEstadoActual, ref_tema);
+        dibujar_leyenda_colores(COLUMNAS_MAPA as f32 * F32_TAMANO_CELDA + 10.0, 60.0, ref_tema);
 
         draw_text(
             &format!("Paso: {} - Estado: {}", uiPasoActual, sEstadoActual),
@@ -102,6 +371,95 @@ pub async fn ejecutar_simulacion(
             20.0,
             BLACK,
         );
+        let sPasosMedios = ref_mut_marcador
+            .pasos_medios_hasta_meta()
+            .map_or("-".to_string(), |f64Pasos| format!("{:.1}", f64Pasos));
+        draw_text(
+            &format!(
+                "Sesión: {} rollouts - Metas: {} - Peligros: {} - Pasos medios a meta: {}",
+                ref_mut_marcador.uiRollouts, ref_mut_marcador.uiMetas, ref_mut_marcador.uiPeligros, sPasosMedios
+            ),
+            10.0,
+            40.0,
+            20.0,
+            BLACK,
+        );
+        if let Some(ref_recocido) = opt_recocido.filter(|ref_r| ref_r.tabla_q.contains_key(&sEstadoActual)) {
+            let f64Temperatura = ref_recocido.temperatura_en_paso(uiPasoActual, uiPasos);
+            let vec_sProbabilidades: Vec<String> =
+                distribucion_softmax(&ref_recocido.tabla_q, &sEstadoActual, f64Temperatura)
+                    .into_iter()
+                    .map(|(sAccion, f64Prob)| format!("{} {:.2}", sAccion, f64Prob))
+                    .collect();
+            draw_text(
+                &format!("Temperatura: {:.2} - P(acción): {}", f64Temperatura, vec_sProbabilidades.join("  ")),
+                10.0,
+                60.0,
+                20.0,
+                BLACK,
+            );
+        }
+
+        #[cfg(feature = "egui")]
+        {
+            inspector.procesar_click(F32_TAMANO_CELDA);
+            inspector.dibujar_superposiciones(&hm_s_sPoliticaPanel, F32_TAMANO_CELDA);
+        }
+        #[cfg(not(feature = "egui"))]
+        dibujar_politica_y_valores(&hm_s_sPoliticaPanel, &hm_s_f64ValoresPanel, &entornoPanel);
+
+        // Control panel below the map
+        let f32PanelY = FILAS_MAPA as f32 * F32_TAMANO_CELDA + 10.0;
+        dibujar_leyenda_visitas(420.0, f32PanelY, uiMaxVisitas, ref_tema);
+        f64LambdaPanel = dibujar_slider(10.0, f32PanelY, "lambda", f64LambdaPanel, 0.50, 0.99, ref_tema);
+        f64ProbExitoPanel =
+            dibujar_slider(10.0, f32PanelY + 35.0, "P(exito)", f64ProbExitoPanel, 0.50, 1.0, ref_tema);
+        let mut bReResolver = dibujar_boton(10.0, f32PanelY + 65.0, 140.0, 28.0, "Re-resolver", ref_tema);
+        // Cell editor: a right click on the map cycles the cell's type and re-solves.
+        if is_mouse_button_pressed(MouseButton::Right) {
+            if let Some(sCelda) = celda_bajo_raton() {
+                match alternar_tipo_celda(&mut entornoPanel, sCelda) {
+                    Ok(eTipo) => {
+                        sMensajeEditor = format!("{}: {:?}", sCelda, eTipo);
+                        vec_sEstadosValidos = estados_iniciales_con_entorno(&entornoPanel);
+                        bReResolver = true;
+                    }
+                    Err(sError) => sMensajeEditor = sError,
+                }
+            }
+        }
+        draw_text(&sMensajeEditor, 160.0, f32PanelY + 85.0, 20.0, BLACK);
+        if bReResolver {
+            let f64ProbDesvio = (1.0 - f64ProbExitoPanel) / 2.0;
+            hm_s_hm_s_f64ModeloPanel = construir_modelo_ruido(f64ProbDesvio, f64ProbExitoPanel, f64ProbDesvio);
+            let opcionesPanel = OpcionesValueIteration {
+                entorno: entornoPanel.clone(),
+                ..Default::default()
+            };
+            let resultadoPanel =
+                value_iteration_con_opciones(f64LambdaPanel, 0.001, Some(&hm_s_hm_s_f64ModeloPanel), &opcionesPanel);
+            hm_s_sPoliticaPanel = resultadoPanel.hm_s_sPolitica;
+            #[cfg(not(feature = "egui"))]
+            {
+                hm_s_f64ValoresPanel = resultadoPanel.hm_s_f64Valores;
+            }
+            #[cfg(feature = "egui")]
+            inspector.resolver(f64LambdaPanel, Some(&hm_s_hm_s_f64ModeloPanel), &entornoPanel);
+
+            // Restart the rollout with the new policy; the interrupted one still counts. A fixed start state
+            // the editor turned into an obstacle or a goal gives way to a random one.
+            ref_mut_marcador.registrar_rollout(false, uiPasoActual);
+            let opt_sInicio = opt_sEstadoInicial
+                .as_deref()
+                .filter(|sInicio| vec_sEstadosValidos.iter().any(|sValido| sValido == sInicio));
+            sEstadoActual = estado_inicial(opt_sInicio, None, &vec_sEstadosValidos, &mut rngThreadRng);
+            uiPasoActual = 0;
+            vdq_sEstela.clear();
+            hm_s_uiVisitas = HashMap::from([(sEstadoActual.clone(), 1)]);
+        }
+
+        #[cfg(feature = "egui")]
+        inspector.dibujar_panel(&hm_s_sPoliticaPanel);
 
         next_frame().await;
 
@@ -116,7 +474,11 @@ pub async fn ejecutar_simulacion(
             break;
         }
 
-        let sAccionElegida = if rngThreadRng.gen::<f64>() < f64EpsilonSim {
+        let sAccionElegida = if let Some(ref_recocido) = opt_recocido {
+            // Annealed softmax over the action values
+            let f64Temperatura = ref_recocido.temperatura_en_paso(uiPasoActual, uiPasos);
+            accion_softmax(&ref_recocido.tabla_q, &sEstadoActual, f64Temperatura, &mut rngSoftmax)
+        } else if rngThreadRng.gen::<f64>() < f64EpsilonSim {
             // Explore: choose a random action
             let vec_sAccionesPosibles = acciones(); // From config.rs
             vec_sAccionesPosibles
@@ -125,46 +487,497 @@ pub async fn ejecutar_simulacion(
                 .to_string()
         } else {
             // Follow the optimal policy
-            ref_mut_hm_s_sPolitica.get(&sEstadoActual).unwrap().clone()
+            hm_s_sPoliticaPanel.get(&sEstadoActual).unwrap().clone()
         };
 
-        // Call mdp_model::obtener_posicion, which expects sEstado: &str
-        if let Some((uiFilaActual, uiColActual)) = obtener_posicion(sEstadoActual.as_str()) {
-            // Call local mover
-            let (uiNuevaFila, uiNuevaCol) = mover(uiFilaActual, uiColActual, &sAccionElegida);
-            // Call mdp_model::obtener_estado, which expects iFila: isize, iCol: isize
-            if let Some(sNuevoEstado) = obtener_estado(uiNuevaFila as isize, uiNuevaCol as isize) {
-                if !OBSTACULOS.contains(&sNuevoEstado) {
-                    sEstadoActual = sNuevoEstado.to_string();
-                }
+        // Same move resolution as the solver: the move slips as in the panel's model, then wind at the
+        // current cell may push it, and blocked moves (edges, obstacles, walls) leave the robot in place.
+        let sDireccion = muestrear_direccion(
+            &entornoPanel,
+            &hm_s_hm_s_f64ModeloPanel[sAccionElegida.as_str()],
+            sAccionElegida.as_str(),
+            &mut rngThreadRng,
+        );
+        let sDireccion = direccion_con_viento(&entornoPanel, sEstadoActual.as_str(), sDireccion, &mut rngThreadRng);
+        if let Some(sNuevoEstado) = resolver_destino(&entornoPanel, sEstadoActual.as_str(), sDireccion) {
+            if vdq_sEstela.len() == UI_LONGITUD_ESTELA {
+                vdq_sEstela.pop_front();
+            }
+            vdq_sEstela.push_back(sEstadoActual.clone());
+            sEstadoActual = sNuevoEstado.to_string();
+            if es_peligro_con_entorno(&entornoPanel, sNuevoEstado) {
+                ref_mut_marcador.uiPeligros += 1;
             }
         }
 
         uiPasoActual += 1;
+        *hm_s_uiVisitas.entry(sEstadoActual.clone()).or_insert(0) += 1;
     }
 
+    ref_mut_marcador.registrar_rollout(sEstadoActual.as_str() == ESTADO_META, uiPasoActual);
     if sEstadoActual.as_str() == ESTADO_META {
         *ref_mut_hm_s_f64RecompensasMap.get_mut(ESTADO_META).unwrap() += 1.0;
     }
+    Ok(())
+}
+
+/// Draws the map cells for the visual simulations, with the robot at `sEstadoActual` and every other cell
+/// tinted toward the theme's visit color in proportion to its count in `ref_hm_s_uiVisitas`. Cells are
+/// colored by their type under the cell edits of `ref_entorno` (`entorno::tipo_celda_con_entorno`).
+///
+/// # Returns
+///
+/// The largest visit count (at least 1), for `dibujar_leyenda_visitas`.
+fn dibujar_mapa(
+    sEstadoActual: &str,
+    ref_hm_s_uiVisitas: &HashMap<String, usize>,
+    ref_tema: &TemaColores,
+    ref_entorno: &Entorno,
+) -> usize {
+    let uiMaxVisitas = ref_hm_s_uiVisitas.values().copied().max().unwrap_or(1);
+    for (uiIFila, ref_arr_sFilaEstados) in MAPA_ESTADOS.iter().enumerate() {
+        for (uiICol, ref_sEstadoNombre) in ref_arr_sFilaEstados.iter().enumerate() {
+            let sEstadoDeCelda: &'static str = ref_sEstadoNombre;
+            let mqColorCell = match tipo_celda_con_entorno(ref_entorno, sEstadoDeCelda) {
+                TipoCelda::Obstaculo => ref_tema.mqColorObstaculo,
+                TipoCelda::Peligro => ref_tema.mqColorPeligro,
+                TipoCelda::Meta => ref_tema.mqColorMeta,
+                TipoCelda::Normal if sEstadoDeCelda == sEstadoActual => ref_tema.mqColorRobot,
+                TipoCelda::Normal => ref_tema.mqColorNormal,
+            };
+            // Visit overlay (obstacles and the robot's own cell keep their color)
+            let uiVisitas = *ref_hm_s_uiVisitas.get(sEstadoDeCelda).unwrap_or(&0);
+            let bConOverlay = mqColorCell != ref_tema.mqColorObstaculo && mqColorCell != ref_tema.mqColorRobot;
+            let mqColorCell = if uiVisitas > 0 && bConOverlay {
+                mezclar_color(mqColorCell, ref_tema.mqColorVisitas, 0.8 * uiVisitas as f32 / uiMaxVisitas as f32)
+            } else {
+                mqColorCell
+            };
+
+            let f32X = uiICol as f32 * F32_TAMANO_CELDA;
+            let f32Y = uiIFila as f32 * F32_TAMANO_CELDA;
+
+            draw_rectangle(
+                f32X + F32_MARGEN,
+                f32Y + F32_MARGEN,
+                F32_TAMANO_CELDA - 2.0 * F32_MARGEN,
+                F32_TAMANO_CELDA - 2.0 * F32_MARGEN,
+                mqColorCell,
+            );
+        }
+    }
+    uiMaxVisitas
+}
+
+/// Draws the policy arrow and V(s) of every free cell under the cell edits of `ref_entorno` (no arrow on
+/// goals).
+#[cfg(not(feature = "egui"))]
+fn dibujar_politica_y_valores(
+    ref_hm_s_sPolitica: &HashMap<String, String>,
+    ref_hm_s_f64Valores: &HashMap<&'static str, f64>,
+    ref_entorno: &Entorno,
+) {
+    for sEstado in MAPA_ESTADOS.iter().flatten() {
+        let eTipo = tipo_celda_con_entorno(ref_entorno, sEstado);
+        if eTipo == TipoCelda::Obstaculo {
+            continue;
+        }
+        let Some((f32CentroX, f32CentroY)) = centro_celda(sEstado) else {
+            continue;
+        };
+        if eTipo != TipoCelda::Meta {
+            if let Some(sAccion) = ref_hm_s_sPolitica.get(*sEstado) {
+                let (f32Dx, f32Dy) = match sAccion.as_str() {
+                    "N" => (0.0, -1.0),
+                    "S" => (0.0, 1.0),
+                    "E" => (1.0, 0.0),
+                    _ => (-1.0, 0.0),
+                };
+                let f32MedioLargo = F32_TAMANO_CELDA * 0.15;
+                let (f32PuntaX, f32PuntaY) = (f32CentroX + f32Dx * f32MedioLargo, f32CentroY + f32Dy * f32MedioLargo);
+                draw_line(
+                    f32CentroX - f32Dx * f32MedioLargo,
+                    f32CentroY - f32Dy * f32MedioLargo,
+                    f32PuntaX,
+                    f32PuntaY,
+                    3.0,
+                    BLACK,
+                );
+                draw_circle(f32PuntaX, f32PuntaY, 4.0, BLACK);
+            }
+        }
+        if let Some(f64Valor) = ref_hm_s_f64Valores.get(*sEstado) {
+            let f32MedioCelda = F32_TAMANO_CELDA / 2.0;
+            draw_text(
+                &format!("{:.2}", f64Valor),
+                f32CentroX - f32MedioCelda + 6.0,
+                f32CentroY + f32MedioCelda - 8.0,
+                18.0,
+                BLACK,
+            );
+        }
+    }
+}
+
+/// Draws the trail of recently visited cells (oldest first), fading from the oldest to the most recent
+/// and joined up to the robot's current cell.
+fn dibujar_estela(ref_vdq_sEstela: &VecDeque<String>, sEstadoActual: &str, ref_tema: &TemaColores) {
+    let uiLargoEstela = ref_vdq_sEstela.len();
+    for (uiIndice, sEstadoEstela) in ref_vdq_sEstela.iter().enumerate() {
+        let f32Alfa = 0.6 * (uiIndice + 1) as f32 / (uiLargoEstela + 1) as f32;
+        if let Some((f32X, f32Y)) = centro_celda(sEstadoEstela) {
+            draw_circle(f32X, f32Y, F32_TAMANO_CELDA * 0.15, Color { a: f32Alfa, ..ref_tema.mqColorRobot });
+            let sSiguiente = ref_vdq_sEstela.get(uiIndice + 1).map_or(sEstadoActual, |s| s.as_str());
+            if let Some((f32XSig, f32YSig)) = centro_celda(sSiguiente) {
+                draw_line(f32X, f32Y, f32XSig, f32YSig, 3.0, Color { a: f32Alfa, ..ref_tema.mqColorRobot });
+            }
+        }
+    }
+}
+
+/// One recorded move of `RegistroSimulacion`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PasoRegistrado {
+    /// State the move started from.
+    pub sEstado: String,
+    /// Intended action.
+    pub sAccion: String,
+    /// State actually reached.
+    pub sEstadoSiguiente: String,
+    /// Reward earned for the step (`learning::TransicionEntorno::f64Recompensa`).
+    pub f64Recompensa: f64,
+    /// `true` if the robot did not end up where the intended move leads without noise (it slipped).
+    pub bDesvio: bool,
+}
+
+/// A full stochastic rollout recorded by `simulacion_detallada`, replayable with `reproducir_trayectoria`.
+///
+/// It derives `Serialize`/`Deserialize`, so an interesting run can be saved (e.g. with `serde_json`) and
+/// replayed later.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RegistroSimulacion {
+    /// State the rollout started from.
+    pub sEstadoInicial: String,
+    /// Moves in order; the last one ends at the goal, at a danger state or at the step cap.
+    pub vec_PasosRegistrados: Vec<PasoRegistrado>,
+    /// `true` if the rollout reached the goal.
+    pub bLlegoMeta: bool,
+}
+
+/// Records one stochastic rollout of a policy, move by move, for `reproducir_trayectoria`.
+///
+/// The rollout follows the episodes of `tasa_exito`: it starts from a random valid non-danger state,
+/// moves with `learning::paso_entorno` (intended direction with probability `f64ProbExito`, each lateral
+/// one with the rest split evenly) and ends on the goal, on a danger state or after `uiMaxPasos` moves.
+/// A move is marked as a slip when its destination differs from `mdp_model::siguiente_estado_determinista`.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_sPolitica` - The policy (State -> Action) to follow.
+/// * `uiMaxPasos` - Step cap.
+/// * `f64ProbExito` - Probability that a move goes in the intended direction.
+/// * `u64Semilla` - Seed for the random number generator; a seed always records the same rollout.
+///
+/// # Returns
+///
+/// The recorded `RegistroSimulacion`. It stops early at a state without a policy entry.
+pub fn simulacion_detallada(
+    ref_hm_s_sPolitica: &HashMap<String, String>,
+    uiMaxPasos: usize,
+    f64ProbExito: f64,
+    u64Semilla: u64,
+) -> RegistroSimulacion {
+    let f64ProbLateral = (1.0 - f64ProbExito) / 2.0;
+    let entorno = EntornoAprendizaje {
+        hm_s_hm_s_f64Modelo: construir_modelo_ruido(f64ProbLateral, f64ProbExito, f64ProbLateral),
+        ..Default::default()
+    };
+    let vec_sIniciales: Vec<&'static str> = estados_iniciales_validos()
+        .into_iter()
+        .filter(|sEstado| !es_peligro_con_entorno(&entorno.entorno, sEstado))
+        .collect();
+    let mut rng = StdRng::seed_from_u64(u64Semilla);
+
+    let mut sEstado: &'static str = vec_sIniciales.choose(&mut rng).unwrap();
+    let mut registro = RegistroSimulacion { sEstadoInicial: sEstado.to_string(), ..Default::default() };
+    while registro.vec_PasosRegistrados.len() < uiMaxPasos {
+        let Some(sAccion) = ref_hm_s_sPolitica.get(sEstado) else {
+            break;
+        };
+        let transicion = paso_entorno(&entorno, sEstado, sAccion, &mut rng);
+        registro.vec_PasosRegistrados.push(PasoRegistrado {
+            sEstado: sEstado.to_string(),
+            sAccion: sAccion.clone(),
+            sEstadoSiguiente: transicion.sEstadoSiguiente.to_string(),
+            f64Recompensa: transicion.f64Recompensa,
+            bDesvio: siguiente_estado_determinista(sEstado, sAccion) != Some(transicion.sEstadoSiguiente),
+        });
+        sEstado = transicion.sEstadoSiguiente;
+        if transicion.bTerminal {
+            registro.bLlegoMeta = true;
+            break;
+        }
+        if es_peligro_con_entorno(&entorno.entorno, sEstado) {
+            break;
+        }
+    }
+    registro
+}
+
+/// Replays a recorded rollout in the Macroquad window, deterministically.
+///
+/// The map, visit overlay and trail are drawn as in `ejecutar_simulacion`, advancing one recorded move
+/// every 0.5 s. A move that slipped (`PasoRegistrado::bDesvio`) flashes the robot's cell with a red frame
+/// for `F64_DURACION_DESVIO` seconds and is flagged in the info line.
+///
+/// Controls: Space pauses and resumes, the right arrow advances one move while paused, Escape stops the
+/// replay. After the last move the final state stays on screen for one more interval.
+///
+/// # Arguments
+///
+/// * `ref_registro` - The rollout to replay (e.g. from `simulacion_detallada`).
+pub async fn reproducir_trayectoria(ref_registro: &RegistroSimulacion) {
+    let uiTotalPasos = ref_registro.vec_PasosRegistrados.len();
+    let mut sEstadoActual = ref_registro.sEstadoInicial.clone();
+    let mut uiPasoActual: usize = 0;
+    let mut vdq_sEstela: VecDeque<String> = VecDeque::with_capacity(UI_LONGITUD_ESTELA);
+    let mut hm_s_uiVisitas: HashMap<String, usize> = HashMap::from([(sEstadoActual.clone(), 1)]);
+
+    let mut f64UltimoMovimiento = get_time();
+    let f64IntervaloMovimiento = 0.5;
+    let mut bPausado = false;
+    let tema = TemaColores::default();
+
+    loop {
+        clear_background(WHITE);
+
+        let uiMaxVisitas = dibujar_mapa(&sEstadoActual, &hm_s_uiVisitas, &tema, &Entorno::default());
+        dibujar_estela(&vdq_sEstela, &sEstadoActual, &tema);
+
+        // Last move played, if any, and whether it slipped
+        let opt_pasoUltimo =
+            uiPasoActual.checked_sub(1).map(|uiIndice| &ref_registro.vec_PasosRegistrados[uiIndice]);
+        let bDesvio = opt_pasoUltimo.is_some_and(|paso| paso.bDesvio);
+        if bDesvio && get_time() - f64UltimoMovimiento < F64_DURACION_DESVIO {
+            if let Some((uiFila, uiCol)) = obtener_posicion(&sEstadoActual) {
+                draw_rectangle_lines(
+                    uiCol as f32 * F32_TAMANO_CELDA + F32_MARGEN,
+                    uiFila as f32 * F32_TAMANO_CELDA + F32_MARGEN,
+                    F32_TAMANO_CELDA - 2.0 * F32_MARGEN,
+                    F32_TAMANO_CELDA - 2.0 * F32_MARGEN,
+                    8.0,
+                    tema.mqColorPeligro,
+                );
+            }
+        }
+
+        let sAccion = opt_pasoUltimo.map_or(String::from("-"), |paso| {
+            format!("{}{}", paso.sAccion, if paso.bDesvio { " (¡desvío!)" } else { "" })
+        });
+        draw_text(
+            &format!(
+                "Repetición - Paso: {}/{} - Estado: {} - Acción: {}",
+                uiPasoActual, uiTotalPasos, sEstadoActual, sAccion
+            ),
+            10.0,
+            20.0,
+            20.0,
+            if bDesvio { tema.mqColorPeligro } else { BLACK },
+        );
+        let sControles = if bPausado {
+            "En pausa - Espacio: continuar, →: avanzar, Esc: salir"
+        } else {
+            "Espacio: pausa, Esc: salir"
+        };
+        draw_text(
+            sControles,
+            10.0,
+            40.0,
+            20.0,
+            BLACK,
+        );
+        dibujar_leyenda_visitas(420.0, FILAS_MAPA as f32 * F32_TAMANO_CELDA + 10.0, uiMaxVisitas, &tema);
+
+        next_frame().await;
+
+        if is_key_pressed(KeyCode::Escape) {
+            break;
+        }
+        if is_key_pressed(KeyCode::Space) {
+            bPausado = !bPausado;
+        }
+        let f64Ahora = get_time();
+        let bAvanzar = if bPausado {
+            is_key_pressed(KeyCode::Right)
+        } else {
+            f64Ahora - f64UltimoMovimiento >= f64IntervaloMovimiento
+        };
+        if !bAvanzar {
+            continue;
+        }
+        f64UltimoMovimiento = f64Ahora;
+
+        if uiPasoActual >= uiTotalPasos {
+            break;
+        }
+        let paso = &ref_registro.vec_PasosRegistrados[uiPasoActual];
+        if paso.sEstadoSiguiente != sEstadoActual {
+            if vdq_sEstela.len() == UI_LONGITUD_ESTELA {
+                vdq_sEstela.pop_front();
+            }
+            vdq_sEstela.push_back(sEstadoActual.clone());
+        }
+        sEstadoActual = paso.sEstadoSiguiente.clone();
+        uiPasoActual += 1;
+        *hm_s_uiVisitas.entry(sEstadoActual.clone()).or_insert(0) += 1;
+    }
+}
+
+/// Linearly blends two colors; `f32T = 0` gives `mqColorA` and `f32T = 1` gives `mqColorB`.
+fn mezclar_color(mqColorA: Color, mqColorB: Color, f32T: f32) -> Color {
+    let f32T = f32T.clamp(0.0, 1.0);
+    Color::new(
+        mqColorA.r + (mqColorB.r - mqColorA.r) * f32T,
+        mqColorA.g + (mqColorB.g - mqColorA.g) * f32T,
+        mqColorA.b + (mqColorB.b - mqColorA.b) * f32T,
+        mqColorA.a + (mqColorB.a - mqColorA.a) * f32T,
+    )
+}
+
+/// Draws a small gradient legend for the visit overlay, from 0 to `uiMaxVisitas` visits.
+fn dibujar_leyenda_visitas(f32X: f32, f32Y: f32, uiMaxVisitas: usize, ref_tema: &TemaColores) {
+    draw_text("Visitas", f32X, f32Y + 15.0, 20.0, BLACK);
+    for uiPaso in 0..5 {
+        let f32T = 0.8 * uiPaso as f32 / 4.0;
+        draw_rectangle(
+            f32X + uiPaso as f32 * 20.0,
+            f32Y + 22.0,
+            20.0,
+            15.0,
+            mezclar_color(ref_tema.mqColorNormal, ref_tema.mqColorVisitas, f32T),
+        );
+    }
+    draw_text("0", f32X, f32Y + 55.0, 18.0, BLACK);
+    draw_text(&uiMaxVisitas.to_string(), f32X + 85.0, f32Y + 55.0, 18.0, BLACK);
+}
+
+/// Draws a legend of the cell colors of `ref_tema`, one swatch and label per line from `(f32X, f32Y)` down.
+fn dibujar_leyenda_colores(f32X: f32, f32Y: f32, ref_tema: &TemaColores) {
+    let arr_tpl_s_mqEntradas = [
+        ("Normal", ref_tema.mqColorNormal),
+        ("Peligro", ref_tema.mqColorPeligro),
+        ("Meta", ref_tema.mqColorMeta),
+        ("Robot", ref_tema.mqColorRobot),
+        ("Obstáculo", ref_tema.mqColorObstaculo),
+    ];
+    for (uiIndice, (sEtiqueta, mqColor)) in arr_tpl_s_mqEntradas.iter().enumerate() {
+        let f32YEntrada = f32Y + uiIndice as f32 * 25.0;
+        draw_rectangle(f32X, f32YEntrada, 20.0, 20.0, *mqColor);
+        draw_text(sEtiqueta, f32X + 28.0, f32YEntrada + 16.0, 20.0, BLACK);
+    }
+}
+
+/// Returns the pixel coordinates of the center of a state's cell, or `None` if the state is not on the map.
+fn centro_celda(sEstado: &str) -> Option<(f32, f32)> {
+    obtener_posicion(sEstado).map(|(uiFila, uiCol)| {
+        (
+            (uiCol as f32 + 0.5) * F32_TAMANO_CELDA,
+            (uiFila as f32 + 0.5) * F32_TAMANO_CELDA,
+        )
+    })
+}
+
+/// Returns the map cell under the mouse pointer, or `None` if the pointer is outside the map.
+fn celda_bajo_raton() -> Option<&'static str> {
+    let (f32MouseX, f32MouseY) = mouse_position();
+    if f32MouseX < 0.0 || f32MouseY < 0.0 {
+        return None;
+    }
+    let (uiFila, uiCol) = ((f32MouseY / F32_TAMANO_CELDA) as usize, (f32MouseX / F32_TAMANO_CELDA) as usize);
+    (uiFila < FILAS_MAPA && uiCol < COLUMNAS_MAPA).then(|| MAPA_ESTADOS[uiFila][uiCol])
+}
+
+/// Start states of a rollout under the cell edits of `ref_entorno`: the normal and danger cells.
+fn estados_iniciales_con_entorno(ref_entorno: &Entorno) -> Vec<String> {
+    MAPA_ESTADOS
+        .iter()
+        .flatten()
+        .filter(|sEstado| {
+            matches!(tipo_celda_con_entorno(ref_entorno, sEstado), TipoCelda::Normal | TipoCelda::Peligro)
+        })
+        .map(|sEstado| sEstado.to_string())
+        .collect()
 }
 
-/// Calculates new (row, col) after an action, specific to simulation grid movement.
-/// Uses `saturating_sub` for boundary safety at 0. Upper bounds are not checked here.
+/// Draws a horizontal slider and updates its value while the left mouse button is held on it.
 ///
 /// # Arguments
-/// * `uiFila` - Current row.
-/// * `uiCol` - Current column.
-/// * `sAccion` - Action string ("N", "S", "E", "O").
+/// * `f32X`, `f32Y` - Top-left position of the slider label.
+/// * `sEtiqueta` - Label drawn to the left of the track.
+/// * `f64Valor` - Current value.
+/// * `f64Min`, `f64Max` - Range of the slider.
+/// * `ref_tema` - Colors; the knob uses the robot color.
 /// # Returns
-/// Tuple `(usize, usize)` for new row and column.
-fn mover(uiFila: usize, uiCol: usize, sAccion: &str) -> (usize, usize) {
-    match sAccion {
-        "N" => (uiFila.saturating_sub(1), uiCol),
-        "S" => (uiFila + 1, uiCol), // Potential panic if uiFila+1 is out of bounds, not handled by this local mover
-        "E" => (uiFila, uiCol + 1), // Potential panic
-        "O" => (uiFila, uiCol.saturating_sub(1)),
-        _ => (uiFila, uiCol),
+/// The (possibly updated) value, clamped to `[f64Min, f64Max]`.
+fn dibujar_slider(
+    f32X: f32,
+    f32Y: f32,
+    sEtiqueta: &str,
+    f64Valor: f64,
+    f64Min: f64,
+    f64Max: f64,
+    ref_tema: &TemaColores,
+) -> f64 {
+    let f32InicioPista = f32X + F32_OFFSET_SLIDER;
+    let f32CentroY = f32Y + 10.0;
+    let mut f64ValorNuevo = f64Valor;
+
+    let (f32MouseX, f32MouseY) = mouse_position();
+    if is_mouse_button_down(MouseButton::Left)
+        && f32MouseX >= f32InicioPista - 5.0
+        && f32MouseX <= f32InicioPista + F32_ANCHO_SLIDER + 5.0
+        && (f32MouseY - f32CentroY).abs() <= 10.0
+    {
+        let f64Fraccion = ((f32MouseX - f32InicioPista) / F32_ANCHO_SLIDER).clamp(0.0, 1.0) as f64;
+        f64ValorNuevo = f64Min + f64Fraccion * (f64Max - f64Min);
     }
+    f64ValorNuevo = f64ValorNuevo.clamp(f64Min, f64Max);
+
+    let f32Fraccion = ((f64ValorNuevo - f64Min) / (f64Max - f64Min)) as f32;
+    draw_text(sEtiqueta, f32X, f32CentroY + 5.0, 20.0, BLACK);
+    draw_line(f32InicioPista, f32CentroY, f32InicioPista + F32_ANCHO_SLIDER, f32CentroY, 3.0, DARKGRAY);
+    draw_circle(f32InicioPista + f32Fraccion * F32_ANCHO_SLIDER, f32CentroY, 8.0, ref_tema.mqColorRobot);
+    draw_text(
+        &format!("{:.2}", f64ValorNuevo),
+        f32InicioPista + F32_ANCHO_SLIDER + 15.0,
+        f32CentroY + 5.0,
+        20.0,
+        BLACK,
+    );
+
+    f64ValorNuevo
+}
+
+/// Draws a button and reports whether it was clicked this frame.
+///
+/// # Arguments
+/// * `f32X`, `f32Y` - Top-left corner.
+/// * `f32Ancho`, `f32Alto` - Size in pixels.
+/// * `sTexto` - Button caption.
+/// * `ref_tema` - Colors; the idle button uses the normal cell color.
+/// # Returns
+/// `true` if the left mouse button was pressed inside the button this frame.
+fn dibujar_boton(f32X: f32, f32Y: f32, f32Ancho: f32, f32Alto: f32, sTexto: &str, ref_tema: &TemaColores) -> bool {
+    let (f32MouseX, f32MouseY) = mouse_position();
+    let bEncima = f32MouseX >= f32X
+        && f32MouseX <= f32X + f32Ancho
+        && f32MouseY >= f32Y
+        && f32MouseY <= f32Y + f32Alto;
+
+    draw_rectangle(f32X, f32Y, f32Ancho, f32Alto, if bEncima { LIGHTGRAY } else { ref_tema.mqColorNormal });
+    draw_text(sTexto, f32X + 10.0, f32Y + f32Alto - 8.0, 20.0, BLACK);
+
+    bEncima && is_mouse_button_pressed(MouseButton::Left)
 }
 
 /// Runs a non-visual simulation for a fixed number of steps (typically 1000).
@@ -188,59 +1001,163 @@ pub fn simulacion_1000_pasos(
     ref_hm_s_sPolitica: &HashMap<String, String>,
     uiMaxPasos: usize,
 ) -> (usize, usize) {
-    let vec_sEstadosValidos: Vec<String> = MAPA_ESTADOS
-        .iter()
-        .flatten()
-        .filter(|&&sEstadoRef| sEstadoRef != ESTADO_META && !OBSTACULOS.contains(&sEstadoRef))
-        .map(|&sEstadoRef| sEstadoRef.to_string()) // sEstadoRef is &&str, so *sEstadoRef is &str
-        .collect();
+    let (uiMetas, uiPeligros, _) =
+        simulacion_1000_pasos_con_opciones(ref_hm_s_sPolitica, uiMaxPasos, &OpcionesSimulacion::default())
+            .expect("las opciones por defecto son válidas");
+    (uiMetas, uiPeligros)
+}
+
+/// Runs `simulacion_1000_pasos` with the optional settings in `ref_opciones`.
+///
+/// With a non-absorbing goal (`Entorno::bMetaAbsorbente = false`) the robot is not reset on the goal and
+/// keeps following the policy from it; every step spent there counts as a goal visit.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_sPolitica` - The policy (State -> Action) to follow.
+/// * `uiMaxPasos` - The total number of steps for the simulation.
+/// * `ref_opciones` - Optional settings (see `OpcionesSimulacion`).
+///
+/// # Returns
+///
+/// `Ok` with a tuple `(usize, usize, usize)`:
+///   - Number of times the goal state was reached.
+///   - Number of times a danger state was entered.
+///   - Number of resets triggered by `opt_uiReinicioPorEstancamiento` (always 0 if it is `None`).
+///
+/// `Err` with the message of `OpcionesSimulacion::validar` if `ref_opciones` fails it.
+pub fn simulacion_1000_pasos_con_opciones(
+    ref_hm_s_sPolitica: &HashMap<String, String>,
+    uiMaxPasos: usize,
+    ref_opciones: &OpcionesSimulacion,
+) -> Result<(usize, usize, usize), String> {
+    let (uiMetas, uiPeligros, uiReinicios, _) = simular_pasos(ref_hm_s_sPolitica, uiMaxPasos, ref_opciones)?;
+    Ok((uiMetas, uiPeligros, uiReinicios))
+}
+
+/// Runs a single episode: follows the policy from a random valid state until it reaches the goal.
+///
+/// Same as `simulacion_1000_pasos_con_opciones` with `bTerminarEnMeta` forced on, so unlike the
+/// continuous-reset run (a throughput measure over many episodes), the result describes one episode.
+/// Steps spent on danger or stagnation resets count towards the total.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_sPolitica` - The policy (State -> Action) to follow.
+/// * `uiMaxPasos` - The step cap for the episode.
+/// * `ref_opciones` - Optional settings (see `OpcionesSimulacion`).
+///
+/// # Returns
+///
+/// `Ok` with the number of steps taken to reach the goal, or `Ok(None)` if it was not reached within
+/// `uiMaxPasos`. `Err` with the message of `OpcionesSimulacion::validar` if `ref_opciones` fails it.
+pub fn simulacion_episodio(
+    ref_hm_s_sPolitica: &HashMap<String, String>,
+    uiMaxPasos: usize,
+    ref_opciones: &OpcionesSimulacion,
+) -> Result<Option<usize>, String> {
+    let opciones = OpcionesSimulacion {
+        bTerminarEnMeta: true,
+        ..ref_opciones.clone()
+    };
+    let (uiMetas, _, _, uiPasos) = simular_pasos(ref_hm_s_sPolitica, uiMaxPasos, &opciones)?;
+    Ok((uiMetas > 0).then_some(uiPasos))
+}
+
+/// Shared loop of `simulacion_1000_pasos_con_opciones` and `simulacion_episodio`; also returns the
+/// number of steps actually run, which is below `uiMaxPasos` only if the rollout ended early.
+fn simular_pasos(
+    ref_hm_s_sPolitica: &HashMap<String, String>,
+    uiMaxPasos: usize,
+    ref_opciones: &OpcionesSimulacion,
+) -> Result<(usize, usize, usize, usize), String> {
+    ref_opciones.validar()?;
+    let vec_sEstadosValidos = estados_iniciales_con_entorno(&ref_opciones.entorno);
 
     let mut rngThreadRng = thread_rng();
-    let mut sEstadoActual = vec_sEstadosValidos
-        .choose(&mut rngThreadRng)
-        .unwrap()
-        .clone();
+    let opt_sInicio = ref_opciones.opt_sEstadoInicial.as_deref();
+    let opt_vec_tpl_s_f64Distribucion = ref_opciones
+        .opt_hm_s_f64DistribucionInicial
+        .as_ref()
+        .map(distribucion_inicial_ordenada)
+        .transpose()?;
+    let opt_arr_tpl_Distribucion = opt_vec_tpl_s_f64Distribucion.as_deref();
+    let mut sEstadoActual =
+        estado_inicial(opt_sInicio, opt_arr_tpl_Distribucion, &vec_sEstadosValidos, &mut rngThreadRng);
 
     let mut uiLlegoMetaCount = 0;
     let mut uiCayoPeligroCount = 0;
+    let mut uiReinicioEstancamientoCount = 0;
+    // Visits per state since the last reset (only tracked for stagnation detection)
+    let mut hm_s_uiVisitas: HashMap<String, usize> = HashMap::new();
     let mut f64RecompensaTotalSim = 0.0; // Note: This accumulates rewards but isn't part of the return tuple.
+    let mut uiPasos = 0;
 
-    for _ in 0..uiMaxPasos {
-        // This call to obtener_recompensas() is inefficient as it rebuilds the map each time.
-        // However, sticking to requested changes.
-        f64RecompensaTotalSim += obtener_recompensas()
-            .get(sEstadoActual.as_str())
-            .unwrap_or(&0.0);
+    let hm_s_f64Recompensas = recompensas_con_entorno(&ref_opciones.entorno);
+    while uiPasos < uiMaxPasos {
+        f64RecompensaTotalSim += hm_s_f64Recompensas.get(sEstadoActual.as_str()).unwrap_or(&0.0);
 
         if sEstadoActual.as_str() == ESTADO_META {
             uiLlegoMetaCount += 1;
-            sEstadoActual = vec_sEstadosValidos
-                .choose(&mut rngThreadRng)
-                .unwrap()
-                .clone();
-            continue;
+            if ref_opciones.bTerminarEnMeta {
+                break;
+            }
+            // A non-absorbing goal is left by following the policy, like any other cell.
+            if ref_opciones.entorno.bMetaAbsorbente {
+                uiPasos += 1;
+                sEstadoActual =
+                    estado_inicial(opt_sInicio, opt_arr_tpl_Distribucion, &vec_sEstadosValidos, &mut rngThreadRng);
+                hm_s_uiVisitas.clear();
+                continue;
+            }
         }
 
-        if ESTADOS_PELIGRO.contains(&sEstadoActual.as_str()) {
+        uiPasos += 1;
+
+        // Lethal danger resets the robot; a costly one (see `Entorno::opt_hs_sPeligrosLetales`) is passed through.
+        if es_peligro_con_entorno(&ref_opciones.entorno, sEstadoActual.as_str()) {
             uiCayoPeligroCount += 1;
-            sEstadoActual = vec_sEstadosValidos
-                .choose(&mut rngThreadRng)
-                .unwrap()
-                .clone();
+        }
+        if reinicia_en_peligro(&ref_opciones.entorno, sEstadoActual.as_str()) {
+            sEstadoActual =
+                estado_inicial(opt_sInicio, opt_arr_tpl_Distribucion, &vec_sEstadosValidos, &mut rngThreadRng);
+            hm_s_uiVisitas.clear();
             continue;
         }
 
+        if let Some(uiMaxVisitas) = ref_opciones.opt_uiReinicioPorEstancamiento {
+            let uiVisitas = hm_s_uiVisitas.entry(sEstadoActual.clone()).or_insert(0);
+            *uiVisitas += 1;
+            if *uiVisitas > uiMaxVisitas {
+                uiReinicioEstancamientoCount += 1;
+                sEstadoActual =
+                    estado_inicial(opt_sInicio, opt_arr_tpl_Distribucion, &vec_sEstadosValidos, &mut rngThreadRng);
+                hm_s_uiVisitas.clear();
+                continue;
+            }
+        }
+
         // Policy lookup. sEstadoActual is String.
         if let Some(sAccionRef) = ref_hm_s_sPolitica.get(&sEstadoActual) {
-            // Call mdp_model::obtener_posicion, expects sEstado: &str
-            if let Some((uiFila, uiCol)) = obtener_posicion(sEstadoActual.as_str()) {
-                // Call local mover, expects uiFila: usize, uiCol: usize, sAccion: &str
-                // sAccionRef is &String, which coerces to &str.
-                let (uiNuevaFila, uiNuevaCol) = mover(uiFila, uiCol, sAccionRef);
-                // Call mdp_model::obtener_estado, expects iFila: isize, iCol: isize
-                sEstadoActual = obtener_estado(uiNuevaFila as isize, uiNuevaCol as isize)
-                    .map(|sNuevoEstadoStr| sNuevoEstadoStr.to_string()) // sNuevoEstadoStr is &'static str
-                    .unwrap_or_else(|| sEstadoActual.clone()); // Fallback to current state if move is invalid
+            // Same move resolution as the solver: the move slips as in the model, if any, then stays in
+            // place on walls, obstacles and blocked passages, after any wind at the current cell.
+            let sDireccion = match ref_opciones
+                .opt_hm_s_hm_s_f64Modelo
+                .as_ref()
+                .and_then(|ref_hm_s_hm_s_f64Modelo| ref_hm_s_hm_s_f64Modelo.get(sAccionRef))
+            {
+                Some(ref_hm_s_f64ModeloAccion) => {
+                    muestrear_direccion(&ref_opciones.entorno, ref_hm_s_f64ModeloAccion, sAccionRef, &mut rngThreadRng)
+                }
+                None => sAccionRef.as_str(),
+            };
+            let sDireccion =
+                direccion_con_viento(&ref_opciones.entorno, sEstadoActual.as_str(), sDireccion, &mut rngThreadRng);
+            if let Some(sNuevoEstado) = resolver_destino(&ref_opciones.entorno, sEstadoActual.as_str(), sDireccion) {
+                if paga_choque(sEstadoActual.as_str(), sDireccion, sNuevoEstado) {
+                    f64RecompensaTotalSim -= ref_opciones.entorno.f64CostoChoque;
+                }
+                sEstadoActual = sNuevoEstado.to_string();
             }
         } else {
             // No action found in policy for current state, end simulation or handle error.
@@ -248,8 +1165,519 @@ pub fn simulacion_1000_pasos(
             break;
         }
     }
-    println!("Llegadas a meta: {}", uiLlegoMetaCount);
-    println!("Caídas en peligro: {}", uiCayoPeligroCount);
-    println!("Recompensa total: {:.2}", f64RecompensaTotalSim); // This is printed but not returned.
-    (uiLlegoMetaCount, uiCayoPeligroCount)
+    log::info!("Llegadas a meta: {}", uiLlegoMetaCount);
+    log::info!("Caídas en peligro: {}", uiCayoPeligroCount);
+    log::info!("Recompensa total: {:.2}", f64RecompensaTotalSim); // This is logged but not returned.
+    if ref_opciones.opt_uiReinicioPorEstancamiento.is_some() {
+        log::info!("Reinicios por estancamiento: {}", uiReinicioEstancamientoCount);
+    }
+    Ok((uiLlegoMetaCount, uiCayoPeligroCount, uiReinicioEstancamientoCount, uiPasos))
+}
+
+/// Runs a continuous walk that samples each intended action from the Boltzmann distribution of a Q-table.
+///
+/// At every step the intended action is drawn with `learning::accion_softmax`, then the move slips as in
+/// `tasa_exito` (intended direction with probability `f64ProbExito`, each lateral one with the rest split
+/// evenly). As in `simulacion_1000_pasos`, reaching the goal or a danger state resets the robot to a random
+/// start state (`learning::estados_iniciales_validos` minus the danger states). At a temperature of 0 the
+/// walk follows `learning::politica_greedy` of the table; higher temperatures spread the visits.
+///
+/// # Arguments
+///
+/// * `ref_tabla_q` - The action values (e.g. from `mdp_model::tabla_q_desde_valores`).
+/// * `f64Temperatura` - The softmax temperature.
+/// * `uiMaxPasos` - The total number of steps.
+/// * `f64ProbExito` - Probability that a move goes in the intended direction.
+/// * `u64Semilla` - Seed for the random number generator.
+///
+/// # Returns
+///
+/// The number of steps spent in each state, goal and danger arrivals included.
+pub fn simular_softmax(
+    ref_tabla_q: &TablaQ,
+    f64Temperatura: f64,
+    uiMaxPasos: usize,
+    f64ProbExito: f64,
+    u64Semilla: u64,
+) -> HashMap<String, usize> {
+    let f64ProbLateral = (1.0 - f64ProbExito) / 2.0;
+    let entorno = EntornoAprendizaje {
+        hm_s_hm_s_f64Modelo: construir_modelo_ruido(f64ProbLateral, f64ProbExito, f64ProbLateral),
+        ..Default::default()
+    };
+    let vec_sIniciales: Vec<&'static str> = estados_iniciales_validos()
+        .into_iter()
+        .filter(|sEstado| !es_peligro_con_entorno(&entorno.entorno, sEstado))
+        .collect();
+    let mut rng = StdRng::seed_from_u64(u64Semilla);
+
+    let mut hm_s_uiVisitas: HashMap<String, usize> = HashMap::new();
+    let mut sEstado: &'static str = vec_sIniciales.choose(&mut rng).unwrap();
+    for _ in 0..uiMaxPasos {
+        *hm_s_uiVisitas.entry(sEstado.to_string()).or_insert(0) += 1;
+        if sEstado == ESTADO_META || es_peligro_con_entorno(&entorno.entorno, sEstado) {
+            sEstado = vec_sIniciales.choose(&mut rng).unwrap();
+            continue;
+        }
+        let sAccion = accion_softmax(ref_tabla_q, sEstado, f64Temperatura, &mut rng);
+        sEstado = paso_entorno(&entorno, sEstado, &sAccion, &mut rng).sEstadoSiguiente;
+    }
+    hm_s_uiVisitas
+}
+
+/// Estimates the per-episode probability that a policy reaches the goal.
+///
+/// Unlike `simulacion_1000_pasos`, which runs one continuous walk with resets, this runs
+/// `uiEpisodios` independent episodes (see `episodios_independientes`). An episode succeeds if it
+/// reaches the goal within `uiMaxPasosPorEpisodio` steps and fails if it enters a danger state or
+/// runs out of steps.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_sPolitica` - The policy (State -> Action) to follow.
+/// * `uiEpisodios` - Number of episodes.
+/// * `uiMaxPasosPorEpisodio` - Step cap per episode.
+/// * `f64ProbExito` - Probability that a move goes in the intended direction.
+/// * `u64Semilla` - Seed for the random number generator.
+///
+/// # Returns
+///
+/// The fraction of episodes that reached the goal (0 if `uiEpisodios` is 0).
+pub fn tasa_exito(
+    ref_hm_s_sPolitica: &HashMap<String, String>,
+    uiEpisodios: usize,
+    uiMaxPasosPorEpisodio: usize,
+    f64ProbExito: f64,
+    u64Semilla: u64,
+) -> f64 {
+    if uiEpisodios == 0 {
+        return 0.0;
+    }
+    let f64ProbLateral = (1.0 - f64ProbExito) / 2.0;
+    let vec_tpl_b_f64_uiEpisodios = episodios_independientes(
+        ref_hm_s_sPolitica,
+        uiEpisodios,
+        uiMaxPasosPorEpisodio,
+        (f64ProbLateral, f64ProbExito, f64ProbLateral),
+        u64Semilla,
+        None,
+    );
+    let uiExitos = vec_tpl_b_f64_uiEpisodios.iter().filter(|(bExito, _, _)| *bExito).count();
+    uiExitos as f64 / uiEpisodios as f64
+}
+
+/// Runs the same independent episodes as `tasa_exito` and returns each episode's total reward,
+/// so the spread of outcomes can be inspected (e.g. with `plot_utils::graficar_histograma_retornos`).
+///
+/// # Arguments
+///
+/// * `ref_hm_s_sPolitica` - The policy (State -> Action) to follow.
+/// * `uiEpisodios` - Number of episodes.
+/// * `uiMaxPasosPorEpisodio` - Step cap per episode.
+/// * `f64ProbExito` - Probability that a move goes in the intended direction.
+/// * `u64Semilla` - Seed for the random number generator.
+///
+/// # Returns
+///
+/// The undiscounted return of every episode, in order.
+pub fn retornos_por_episodio(
+    ref_hm_s_sPolitica: &HashMap<String, String>,
+    uiEpisodios: usize,
+    uiMaxPasosPorEpisodio: usize,
+    f64ProbExito: f64,
+    u64Semilla: u64,
+) -> Vec<f64> {
+    let tpl_f64x3Ruido = ((1.0 - f64ProbExito) / 2.0, f64ProbExito, (1.0 - f64ProbExito) / 2.0);
+    episodios_independientes(ref_hm_s_sPolitica, uiEpisodios, uiMaxPasosPorEpisodio, tpl_f64x3Ruido, u64Semilla, None)
+        .into_iter()
+        .map(|(_, f64Retorno, _)| f64Retorno)
+        .collect()
+}
+
+/// Runs independent, seeded episodes of a policy with noisy moves.
+///
+/// Each episode starts from a uniformly random state (`learning::estados_iniciales_validos` minus
+/// the danger states), or from one drawn from `opt_ref_arr_tpl_s_f64Distribucion` if given (an
+/// already validated distribution, as returned by `distribucion_inicial_ordenada`).
+/// Moves slip as in `robustness::construir_modelo_ruido` with `tpl_f64x3Ruido = (izq, centro, der)`:
+/// the intended direction with probability `centro` and each lateral one with its own probability,
+/// so an uneven split models a robot that drifts to one side.
+/// Each step earns R(s) of the state left; reaching the goal or a danger state ends the episode
+/// and adds that state's reward.
+///
+/// # Returns
+///
+/// One `(reached_goal, undiscounted_return, steps)` tuple per episode.
+pub(crate) fn episodios_independientes(
+    ref_hm_s_sPolitica: &HashMap<String, String>,
+    uiEpisodios: usize,
+    uiMaxPasosPorEpisodio: usize,
+    tpl_f64x3Ruido: (f64, f64, f64),
+    u64Semilla: u64,
+    opt_ref_arr_tpl_s_f64Distribucion: Option<&[(&'static str, f64)]>,
+) -> Vec<(bool, f64, usize)> {
+    let (f64ProbIzq, f64ProbCentro, f64ProbDer) = tpl_f64x3Ruido;
+    episodios_con_modelo(
+        ref_hm_s_sPolitica,
+        uiEpisodios,
+        uiMaxPasosPorEpisodio,
+        construir_modelo_ruido(f64ProbIzq, f64ProbCentro, f64ProbDer),
+        u64Semilla,
+        None,
+        opt_ref_arr_tpl_s_f64Distribucion,
+    )
+    .into_iter()
+    .map(|(bExito, _, f64Retorno, uiPasos)| (bExito, f64Retorno, uiPasos))
+    .collect()
+}
+
+/// Same episodes as `episodios_independientes` under any transition model (action -> effective direction ->
+/// probability). If `opt_sEstadoInicial` is set (a state that passes `es_estado_inicial_valido`), every
+/// episode starts there instead, as with `OpcionesSimulacion::opt_sEstadoInicial`.
+///
+/// # Returns
+///
+/// One `(reached_goal, ended_in_danger, undiscounted_return, steps)` tuple per episode.
+pub(crate) fn episodios_con_modelo(
+    ref_hm_s_sPolitica: &HashMap<String, String>,
+    uiEpisodios: usize,
+    uiMaxPasosPorEpisodio: usize,
+    hm_s_hm_s_f64Modelo: HashMap<String, HashMap<String, f64>>,
+    u64Semilla: u64,
+    opt_sEstadoInicial: Option<&str>,
+    opt_ref_arr_tpl_s_f64Distribucion: Option<&[(&'static str, f64)]>,
+) -> Vec<(bool, bool, f64, usize)> {
+    let entorno = EntornoAprendizaje { hm_s_hm_s_f64Modelo, ..Default::default() };
+    let vec_sIniciales: Vec<&'static str> = estados_iniciales_validos()
+        .into_iter()
+        .filter(|sEstado| !es_peligro_con_entorno(&entorno.entorno, sEstado))
+        .collect();
+    let mut rng = StdRng::seed_from_u64(u64Semilla);
+
+    let mut vec_tpl_b_f64_uiEpisodios = Vec::with_capacity(uiEpisodios);
+    for _ in 0..uiEpisodios {
+        let mut sEstado: &str = match (opt_sEstadoInicial, opt_ref_arr_tpl_s_f64Distribucion) {
+            (Some(sInicio), _) => sInicio,
+            (None, Some(ref_arr_tpl_s_f64Distribucion)) => {
+                ref_arr_tpl_s_f64Distribucion.choose_weighted(&mut rng, |tpl| tpl.1).unwrap().0
+            }
+            (None, None) => vec_sIniciales.choose(&mut rng).unwrap(),
+        };
+        let mut bExito = false;
+        let mut bPeligro = false;
+        let mut f64Retorno = 0.0;
+        let mut uiPasos = 0;
+        for _ in 0..uiMaxPasosPorEpisodio {
+            let Some(sAccion) = ref_hm_s_sPolitica.get(sEstado) else {
+                break;
+            };
+            let transicion = paso_entorno(&entorno, sEstado, sAccion, &mut rng);
+            uiPasos += 1;
+            f64Retorno += transicion.f64Recompensa;
+            sEstado = transicion.sEstadoSiguiente;
+            if transicion.bTerminal || es_peligro_con_entorno(&entorno.entorno, sEstado) {
+                f64Retorno += entorno.hm_s_f64Recompensas[sEstado];
+                bExito = transicion.bTerminal;
+                bPeligro = !bExito;
+                break;
+            }
+        }
+        vec_tpl_b_f64_uiEpisodios.push((bExito, bPeligro, f64Retorno, uiPasos));
+    }
+    vec_tpl_b_f64_uiEpisodios
+}
+
+/// Steps after which the remaining discounted reward of a rollout is negligible (λ^H below this).
+const F64_COLA_DESPRECIABLE: f64 = 1e-6;
+
+/// Compares the solver's state values with discounted returns estimated by simulation.
+///
+/// Same as `verificar_consistencia_con_entorno` with `Entorno::default()`, i.e. no extra movement
+/// rules and the goal's reward counted as a terminal value (`ModoRecompensaMeta::Terminal`).
+///
+/// # Arguments
+///
+/// * `f64Lambda` - The discount factor (below 1).
+/// * `uiEpisodios` - Rollouts per start state.
+/// * `u64Semilla` - Seed for the random number generator.
+///
+/// # Returns
+///
+/// A `HashMap<String, (f64, f64, f64)>` with `(V_solver, V_simulado, V_simulado − V_solver)` per start state.
+pub fn verificar_consistencia(f64Lambda: f64, uiEpisodios: usize, u64Semilla: u64) -> HashMap<String, (f64, f64, f64)> {
+    verificar_consistencia_con_entorno(f64Lambda, uiEpisodios, u64Semilla, &Entorno::default())
+}
+
+/// Compares the solver's state values with discounted returns estimated by simulation, under extra
+/// movement rules.
+///
+/// Solves the MDP with `value_iteration_con_opciones` and `ref_entorno`, then from every start state
+/// (`learning::estados_iniciales_validos`) runs `uiEpisodios` rollouts of the optimal policy with
+/// the model's own noise (`learning::paso_entorno` with `ref_entorno`), adding λ^t·(R(s_t) − bump cost)
+/// for each step and the goal's reward with the same timing as the solver (`ModoRecompensaMeta`):
+/// λ^T·R(goal) on reaching it in `Terminal` mode, λ^(T−1)·R(goal) on the move entering it in `AlEntrar`
+/// mode. Reaching a lethal danger state (`Entorno::opt_hs_sPeligrosLetales`) adds λ^T·R(danger) and
+/// ends the rollout, as its fixed value does in the solver. A non-absorbing goal (`Entorno::bMetaAbsorbente`)
+/// does not end the rollout, which keeps following the policy from it.
+/// Rollouts are truncated once λ^t drops below `F64_COLA_DESPRECIABLE`. With enough episodes the
+/// difference should be within sampling noise; a systematic gap points to a mismatch between the
+/// solver and the simulator (terminal handling, reward timing, move resolution).
+///
+/// # Arguments
+///
+/// * `f64Lambda` - The discount factor (below 1).
+/// * `uiEpisodios` - Rollouts per start state.
+/// * `u64Semilla` - Seed for the random number generator.
+/// * `ref_entorno` - The movement rules and goal mode shared by the solver and the rollouts.
+///
+/// # Returns
+///
+/// A `HashMap<String, (f64, f64, f64)>` with `(V_solver, V_simulado, V_simulado − V_solver)` per start state.
+pub fn verificar_consistencia_con_entorno(
+    f64Lambda: f64,
+    uiEpisodios: usize,
+    u64Semilla: u64,
+    ref_entorno: &Entorno,
+) -> HashMap<String, (f64, f64, f64)> {
+    let opciones = OpcionesValueIteration { entorno: ref_entorno.clone(), ..Default::default() };
+    let resultado = value_iteration_con_opciones(f64Lambda, 1e-9, None, &opciones);
+    let (hm_s_f64Valores, hm_s_sPolitica) = (resultado.hm_s_f64Valores, resultado.hm_s_sPolitica);
+    let entorno = EntornoAprendizaje { entorno: ref_entorno.clone(), ..Default::default() };
+    let f64RecompensaMeta = entorno.hm_s_f64Recompensas[ESTADO_META];
+    let uiHorizonte = (F64_COLA_DESPRECIABLE.ln() / f64Lambda.ln()).ceil() as usize;
+    let mut rng = StdRng::seed_from_u64(u64Semilla);
+
+    let mut hm_s_tpl_f64Resultados = HashMap::new();
+    for sInicio in estados_iniciales_validos() {
+        let mut f64SumaRetornos = 0.0;
+        for _ in 0..uiEpisodios {
+            let mut sEstado = sInicio;
+            let mut f64Descuento = 1.0;
+            let mut f64Retorno = 0.0;
+            for _ in 0..uiHorizonte {
+                let transicion = paso_entorno(&entorno, sEstado, &hm_s_sPolitica[sEstado], &mut rng);
+                let sSiguiente = transicion.sEstadoSiguiente;
+                // A step from a non-absorbing goal pays the goal's own step reward, as in the solver.
+                let mut f64RecompensaPaso = transicion.f64Recompensa;
+                if sEstado == ESTADO_META {
+                    f64RecompensaPaso += valor_meta(ref_entorno, f64RecompensaMeta) - f64RecompensaMeta;
+                }
+                f64Retorno += f64Descuento
+                    * (f64RecompensaPaso + recompensa_entrada(ref_entorno, sSiguiente, f64RecompensaMeta));
+                f64Descuento *= f64Lambda;
+                if transicion.bTerminal && ref_entorno.bMetaAbsorbente {
+                    f64Retorno += f64Descuento * valor_meta(ref_entorno, f64RecompensaMeta);
+                    break;
+                }
+                if es_peligro_terminal(ref_entorno, sSiguiente) {
+                    f64Retorno += f64Descuento * entorno.hm_s_f64Recompensas[sSiguiente];
+                    break;
+                }
+                sEstado = sSiguiente;
+            }
+            f64SumaRetornos += f64Retorno;
+        }
+        let f64VSolver = hm_s_f64Valores[sInicio];
+        let f64VSimulado = f64SumaRetornos / uiEpisodios.max(1) as f64;
+        hm_s_tpl_f64Resultados.insert(sInicio.to_string(), (f64VSolver, f64VSimulado, f64VSimulado - f64VSolver));
+    }
+    hm_s_tpl_f64Resultados
+}
+
+/// Largest |V_solver − return| (and per-step Bellman residual) accepted by `auditar_recompensas`.
+const F64_TOLERANCIA_AUDITORIA: f64 = 1e-6;
+
+/// One row of the reward trace of `auditar_recompensas`.
+#[derive(Debug, Clone)]
+pub struct PasoAuditoria {
+    /// Step index t (0 for the start state).
+    pub uiPaso: usize,
+    /// State s_t the robot is in.
+    pub sEstado: String,
+    /// Action taken from `sEstado`; `None` on the goal, which ends the rollout.
+    pub opt_sAccion: Option<String>,
+    /// Undiscounted reward counted at this step: R(s_t) minus the bump cost, or R(goal) on the goal.
+    pub f64Recompensa: f64,
+    /// Discount λ^t applied to `f64Recompensa`.
+    pub f64Descuento: f64,
+    /// Discounted return accumulated up to and including this step.
+    pub f64RetornoAcumulado: f64,
+    /// Solver value V(s_t).
+    pub f64VSolver: f64,
+    /// V(s_t) − (reward + λ·V(s_{t+1})): 0 when the solver and the trace agree on this step.
+    /// On the goal it is V(goal) − R(goal).
+    pub f64Residuo: f64,
+}
+
+/// Result of `auditar_recompensas`.
+#[derive(Debug, Clone)]
+pub struct ReporteAuditoria {
+    /// Start state of the rollout.
+    pub sEstadoInicio: String,
+    /// Solver value V(start).
+    pub f64VSolver: f64,
+    /// Discounted return summed along the rollout.
+    pub f64RetornoSimulado: f64,
+    /// `f64RetornoSimulado − f64VSolver`.
+    pub f64Diferencia: f64,
+    /// `false` if the rollout was truncated at the horizon before reaching the goal.
+    pub bLlegoMeta: bool,
+    /// Per-step reward trace, from the start state to the goal.
+    pub vec_PasosAuditoria: Vec<PasoAuditoria>,
+}
+
+impl ReporteAuditoria {
+    /// Whether the rollout's return matches V(start) within `F64_TOLERANCIA_AUDITORIA`.
+    pub fn coincide(&self) -> bool {
+        self.f64Diferencia.abs() <= F64_TOLERANCIA_AUDITORIA
+    }
+
+    /// Returns the first step whose Bellman residual exceeds `F64_TOLERANCIA_AUDITORIA`, i.e. where the
+    /// solver and the simulator's accounting start to diverge; `None` if every step agrees.
+    pub fn primer_paso_divergente(&self) -> Option<&PasoAuditoria> {
+        self.vec_PasosAuditoria
+            .iter()
+            .find(|paso| paso.f64Residuo.abs() > F64_TOLERANCIA_AUDITORIA)
+    }
+}
+
+/// Checks that the simulator's reward accounting matches the solver's on a single noise-free rollout.
+///
+/// Solves the MDP with `robustness::modelo_determinista` (the intended direction always succeeds),
+/// then follows the resulting policy from a start state picked with `u64Semilla` among
+/// `learning::estados_iniciales_validos`, counting rewards as `simular_pasos` does: R(s_t) for every
+/// state the robot stands in (the start state included), minus the bump cost, and R(goal) once on
+/// arrival. Each reward is discounted by λ^t, so without noise the sum must equal V(start) exactly.
+/// A mismatch beyond `F64_TOLERANCIA_AUDITORIA` points to a reward counted twice or not at all;
+/// `ReporteAuditoria::primer_paso_divergente` locates the step.
+///
+/// # Arguments
+///
+/// * `f64Lambda` - The discount factor (below 1).
+/// * `u64Semilla` - Seed that picks the start state; a given seed always audits the same one.
+///
+/// # Returns
+///
+/// A `ReporteAuditoria` with both values and the per-step reward trace.
+///
+/// # Panics
+///
+/// Panics if `f64Lambda` is not in (0, 1).
+pub fn auditar_recompensas(f64Lambda: f64, u64Semilla: u64) -> ReporteAuditoria {
+    assert!(f64Lambda > 0.0 && f64Lambda < 1.0, "auditar_recompensas: λ debe estar en (0, 1)");
+    let hm_s_hm_s_f64ModeloDeterminista = modelo_determinista();
+    let opciones = OpcionesValueIteration::default();
+    let resultado = value_iteration_con_opciones(f64Lambda, 1e-12, Some(&hm_s_hm_s_f64ModeloDeterminista), &opciones);
+    let entorno = &opciones.entorno;
+    let hm_s_f64Recompensas = recompensas_con_entorno(entorno);
+    let f64RecompensaMeta = hm_s_f64Recompensas[ESTADO_META];
+    let uiHorizonte = (F64_COLA_DESPRECIABLE.ln() / f64Lambda.ln()).ceil() as usize;
+
+    let mut rng = StdRng::seed_from_u64(u64Semilla);
+    let sInicio = *estados_iniciales_validos().choose(&mut rng).unwrap();
+
+    let mut vec_PasosAuditoria = Vec::new();
+    let mut sEstado = sInicio;
+    let mut f64Descuento = 1.0;
+    let mut f64Retorno = 0.0;
+    let mut bLlegoMeta = false;
+    for uiPaso in 0..=uiHorizonte {
+        let f64VEstado = resultado.hm_s_f64Valores[sEstado];
+        if sEstado == ESTADO_META {
+            let f64ValorMeta = valor_meta(entorno, f64RecompensaMeta);
+            f64Retorno += f64Descuento * f64ValorMeta;
+            vec_PasosAuditoria.push(PasoAuditoria {
+                uiPaso,
+                sEstado: sEstado.to_string(),
+                opt_sAccion: None,
+                f64Recompensa: f64ValorMeta,
+                f64Descuento,
+                f64RetornoAcumulado: f64Retorno,
+                f64VSolver: f64VEstado,
+                f64Residuo: f64VEstado - f64ValorMeta,
+            });
+            bLlegoMeta = true;
+            break;
+        }
+        let sAccion = &resultado.hm_s_sPolitica[sEstado];
+        let sSiguiente = resolver_destino(entorno, sEstado, sAccion).unwrap();
+        let mut f64Recompensa = hm_s_f64Recompensas[sEstado];
+        if es_choque(sEstado, sSiguiente) {
+            f64Recompensa -= entorno.f64CostoChoque;
+        }
+        f64Recompensa += recompensa_entrada(entorno, sSiguiente, f64RecompensaMeta);
+        f64Retorno += f64Descuento * f64Recompensa;
+        vec_PasosAuditoria.push(PasoAuditoria {
+            uiPaso,
+            sEstado: sEstado.to_string(),
+            opt_sAccion: Some(sAccion.clone()),
+            f64Recompensa,
+            f64Descuento,
+            f64RetornoAcumulado: f64Retorno,
+            f64VSolver: f64VEstado,
+            f64Residuo: f64VEstado - (f64Recompensa + f64Lambda * resultado.hm_s_f64Valores[sSiguiente]),
+        });
+        f64Descuento *= f64Lambda;
+        sEstado = sSiguiente;
+    }
+
+    let f64VSolver = resultado.hm_s_f64Valores[sInicio];
+    ReporteAuditoria {
+        sEstadoInicio: sInicio.to_string(),
+        f64VSolver,
+        f64RetornoSimulado: f64Retorno,
+        f64Diferencia: f64Retorno - f64VSolver,
+        bLlegoMeta,
+        vec_PasosAuditoria,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verificar_consistencia_dentro_de_tolerancia() {
+        // Seeded, so the sampling error is fixed; 0.2 leaves room for it while catching reward-timing bugs.
+        for (sEstado, (f64VSolver, f64VSimulado, f64Diferencia)) in verificar_consistencia(0.9, 2000, 7) {
+            assert!(
+                f64Diferencia.abs() < 0.2,
+                "{}: V_solver = {}, V_simulado = {}",
+                sEstado,
+                f64VSolver,
+                f64VSimulado
+            );
+        }
+    }
+
+    #[test]
+    fn auditar_recompensas_coincide() {
+        for u64Semilla in 0..10 {
+            let reporte = auditar_recompensas(0.9, u64Semilla);
+            assert!(reporte.bLlegoMeta, "{} no llega a la meta", reporte.sEstadoInicio);
+            assert!(
+                reporte.coincide(),
+                "{}: diferencia {}, primer paso divergente {:?}",
+                reporte.sEstadoInicio,
+                reporte.f64Diferencia,
+                reporte.primer_paso_divergente()
+            );
+        }
+    }
+
+    #[test]
+    fn politica_determinista_sigue_camino_mas_corto() {
+        let hm_s_uiDistancias = crate::mdp_model::distancias_a_meta();
+        let (_, hm_s_sPolitica) = crate::mdp_model::value_iteration(0.9, 1e-9, Some(&modelo_determinista()));
+        for sEstado in estados_iniciales_validos() {
+            let sSiguiente = siguiente_estado_determinista(sEstado, &hm_s_sPolitica[sEstado]).unwrap();
+            assert_eq!(
+                hm_s_uiDistancias[sSiguiente] + 1,
+                hm_s_uiDistancias[sEstado],
+                "{} -> {} no acerca a la meta",
+                sEstado,
+                sSiguiente
+            );
+        }
+    }
 }
@@ -0,0 +1,128 @@
+/// Saving and restoring a solved MDP (configuration, state values, Q-table and policy) as JSON.
+// src/snapshot.rs
+use crate::config::mapa_estados_como_filas;
+use crate::entorno::{recompensas_con_entorno, Entorno};
+use crate::learning::TablaQ;
+use crate::mdp_model::{tabla_q_con_opciones, OpcionesValueIteration};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+
+/// A solved MDP: the parameters used, the map and rewards it was solved on, and the results.
+///
+/// The policy has the same type as the one returned by `value_iteration`, so a restored
+/// snapshot can be passed directly to `simulacion_1000_pasos` or `evaluar_robustez`, and the Q-table
+/// to anything that takes a learned one (e.g. `simulation::RecocidoSoftmax`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotMdp {
+    /// The discount factor used to solve.
+    pub f64Lambda: f64,
+    /// The convergence threshold used to solve.
+    pub f64Epsilon: f64,
+    /// The map layout (rows of state names) at the time of solving.
+    pub vec_vec_sMapa: Vec<Vec<String>>,
+    /// The reward of each state at the time of solving.
+    pub hm_s_f64Recompensas: HashMap<String, f64>,
+    /// State values (V).
+    pub hm_s_f64Valores: HashMap<String, f64>,
+    /// Action values implied by the state values (`mdp_model::tabla_q_con_opciones`).
+    pub tabla_q: TablaQ,
+    /// The optimal policy (State -> Action).
+    pub hm_s_sPolitica: HashMap<String, String>,
+}
+
+/// Builds a snapshot from the output of `value_iteration` and the current configuration.
+///
+/// Same as `crear_snapshot_con_entorno` with `Entorno::default()` (the default rewards).
+pub fn crear_snapshot(
+    f64Lambda: f64,
+    f64Epsilon: f64,
+    ref_hm_s_f64Valores: &HashMap<&'static str, f64>,
+    ref_hm_s_sPolitica: &HashMap<String, String>,
+) -> SnapshotMdp {
+    crear_snapshot_con_entorno(f64Lambda, f64Epsilon, ref_hm_s_f64Valores, ref_hm_s_sPolitica, &Entorno::default())
+}
+
+/// Builds a snapshot from the output of a solve under `ref_entorno`, recording that entorno's rewards.
+///
+/// The Q-table is computed from the values with `tabla_q_con_opciones` under `ref_entorno` and the base
+/// transition model.
+///
+/// # Arguments
+///
+/// * `f64Lambda` - The discount factor used to solve.
+/// * `f64Epsilon` - The convergence threshold used to solve.
+/// * `ref_hm_s_f64Valores` - State values returned by the solver.
+/// * `ref_hm_s_sPolitica` - Policy returned by the solver.
+/// * `ref_entorno` - The rules (and reward overrides) the solve used.
+///
+/// # Returns
+///
+/// A `SnapshotMdp` holding copies of all the data.
+pub fn crear_snapshot_con_entorno(
+    f64Lambda: f64,
+    f64Epsilon: f64,
+    ref_hm_s_f64Valores: &HashMap<&'static str, f64>,
+    ref_hm_s_sPolitica: &HashMap<String, String>,
+    ref_entorno: &Entorno,
+) -> SnapshotMdp {
+    SnapshotMdp {
+        f64Lambda,
+        f64Epsilon,
+        vec_vec_sMapa: mapa_estados_como_filas(),
+        hm_s_f64Recompensas: recompensas_con_entorno(ref_entorno)
+            .into_iter()
+            .map(|(sEstado, f64Recompensa)| (sEstado.to_string(), f64Recompensa))
+            .collect(),
+        hm_s_f64Valores: ref_hm_s_f64Valores
+            .iter()
+            .map(|(sEstado, f64Valor)| (sEstado.to_string(), *f64Valor))
+            .collect(),
+        tabla_q: tabla_q_con_opciones(
+            ref_hm_s_f64Valores,
+            f64Lambda,
+            None,
+            &OpcionesValueIteration { entorno: ref_entorno.clone(), ..Default::default() },
+        ),
+        hm_s_sPolitica: ref_hm_s_sPolitica.clone(),
+    }
+}
+
+/// Writes a snapshot to a JSON file.
+///
+/// # Arguments
+///
+/// * `ref_snapshot` - The snapshot to save.
+/// * `sRuta` - Path of the output file.
+///
+/// # Returns
+///
+/// `Result<(), Box<dyn std::error::Error>>` indicating success or an I/O/serialization error.
+pub fn guardar_snapshot(ref_snapshot: &SnapshotMdp, sRuta: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut bwArchivo = BufWriter::new(File::create(sRuta)?);
+    serde_json::to_writer_pretty(&mut bwArchivo, ref_snapshot)?;
+    bwArchivo.flush()?;
+
+    log::info!("Snapshot '{}' guardado.", sRuta);
+    Ok(())
+}
+
+/// Reads a snapshot previously written by `guardar_snapshot`.
+///
+/// # Arguments
+///
+/// * `sRuta` - Path of the JSON file.
+///
+/// # Returns
+///
+/// The restored `SnapshotMdp`, or an I/O/deserialization error, or an error if it was solved on a map
+/// other than the current `MAPA_ESTADOS` (its states would not match the solver's).
+pub fn cargar_snapshot(sRuta: &str) -> Result<SnapshotMdp, Box<dyn std::error::Error>> {
+    let fArchivo = File::open(sRuta)?;
+    let snapshot: SnapshotMdp = serde_json::from_reader(BufReader::new(fArchivo))?;
+    if snapshot.vec_vec_sMapa != mapa_estados_como_filas() {
+        return Err(format!("El snapshot '{}' se resolvió en un mapa distinto de MAPA_ESTADOS", sRuta).into());
+    }
+    Ok(snapshot)
+}
@@ -0,0 +1,129 @@
+/// Plain-text (and, with the `color` feature, colored) terminal rendering of policies on the map grid.
+// src/texto.rs
+use crate::config::{ESTADOS_PELIGRO, ESTADO_META, MAPA_ESTADOS, OBSTACULOS};
+use std::collections::HashMap;
+#[cfg(feature = "color")]
+use std::io::IsTerminal;
+
+/// Kind of map cell, used to pick how a cell is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TipoCelda {
+    /// A regular state.
+    Normal,
+    /// A danger state (`ESTADOS_PELIGRO`).
+    Peligro,
+    /// The goal state (`ESTADO_META`).
+    Meta,
+    /// An obstacle (`OBSTACULOS`).
+    Obstaculo,
+}
+
+/// Returns the arrow for an action ("N" → "↑", "S" → "↓", "E" → "→", "O" → "←"), or "?" if unknown.
+pub fn flecha_accion(sAccion: &str) -> &'static str {
+    match sAccion {
+        "N" => "↑",
+        "S" => "↓",
+        "E" => "→",
+        "O" => "←",
+        _ => "?",
+    }
+}
+
+/// Classifies a state of the map.
+pub fn tipo_celda(sEstado: &str) -> TipoCelda {
+    if OBSTACULOS.contains(&sEstado) {
+        TipoCelda::Obstaculo
+    } else if sEstado == ESTADO_META {
+        TipoCelda::Meta
+    } else if ESTADOS_PELIGRO.contains(&sEstado) {
+        TipoCelda::Peligro
+    } else {
+        TipoCelda::Normal
+    }
+}
+
+/// Lays out a policy on the map grid, shared by every renderer.
+///
+/// Each cell holds its type and its symbol: "M" for the goal, "■" for obstacles,
+/// the policy arrow for any other state ("·" if the state has no action).
+///
+/// # Arguments
+///
+/// * `ref_hm_s_sPolitica` - The policy (State -> Action).
+///
+/// # Returns
+///
+/// One `Vec` per map row, in `MAPA_ESTADOS` order.
+pub fn celdas_mapa_politica(ref_hm_s_sPolitica: &HashMap<String, String>) -> Vec<Vec<(TipoCelda, &'static str)>> {
+    MAPA_ESTADOS
+        .iter()
+        .map(|arr_sFilaEstados| {
+            arr_sFilaEstados
+                .iter()
+                .map(|&sEstado| {
+                    let eTipo = tipo_celda(sEstado);
+                    let sSimbolo = match eTipo {
+                        TipoCelda::Meta => "M",
+                        TipoCelda::Obstaculo => "■",
+                        TipoCelda::Normal | TipoCelda::Peligro => ref_hm_s_sPolitica
+                            .get(sEstado)
+                            .map(|sAccion| flecha_accion(sAccion))
+                            .unwrap_or("·"),
+                    };
+                    (eTipo, sSimbolo)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Renders a policy as a plain-text arrow map, one line per map row.
+pub fn mapa_politica_texto(ref_hm_s_sPolitica: &HashMap<String, String>) -> String {
+    celdas_mapa_politica(ref_hm_s_sPolitica)
+        .iter()
+        .map(|vec_tplFila| {
+            vec_tplFila
+                .iter()
+                .map(|(_, sSimbolo)| *sSimbolo)
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Renders a policy as an arrow map with ANSI colors: goal in green, danger in red,
+/// obstacles in dark gray and regular arrows uncolored (`color` feature).
+#[cfg(feature = "color")]
+pub fn mapa_politica_texto_color(ref_hm_s_sPolitica: &HashMap<String, String>) -> String {
+    use colored::Colorize;
+
+    celdas_mapa_politica(ref_hm_s_sPolitica)
+        .iter()
+        .map(|vec_tplFila| {
+            vec_tplFila
+                .iter()
+                .map(|(eTipo, sSimbolo)| match eTipo {
+                    TipoCelda::Normal => sSimbolo.normal().to_string(),
+                    TipoCelda::Peligro => sSimbolo.red().bold().to_string(),
+                    TipoCelda::Meta => sSimbolo.green().bold().to_string(),
+                    TipoCelda::Obstaculo => sSimbolo.bright_black().to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Renders a policy's arrow map for printing to stdout.
+///
+/// Uses colors when built with the `color` feature and stdout is a terminal
+/// (`colored` also honors `NO_COLOR`); plain text otherwise.
+pub fn mapa_politica_terminal(ref_hm_s_sPolitica: &HashMap<String, String>) -> String {
+    #[cfg(feature = "color")]
+    if std::io::stdout().is_terminal() {
+        return mapa_politica_texto_color(ref_hm_s_sPolitica);
+    }
+    mapa_politica_texto(ref_hm_s_sPolitica)
+}
@@ -1,10 +1,30 @@
-/// Functions for constructing and saving transition probability matrices for the MDP.
-use crate::config::{prob_transicion, MAPA_ESTADOS, OBSTACULOS};
-use crate::mdp_model::{mover, obtener_estado, obtener_posicion};
+/// Functions for constructing and saving transition probability matrices for the MDP, and analyses
+/// and CSV exports built on them.
+use crate::config::{
+    acciones, obtener_recompensas, prob_transicion, ESTADOS_PELIGRO, ESTADO_META, MAPA_ESTADOS, OBSTACULOS,
+};
+use crate::entorno::{recompensas_con_entorno, Entorno};
+use crate::learning::estados_iniciales_validos;
+use crate::mdp_model::{
+    modelo_transicion_base, mover, obtener_estado, obtener_posicion, siguiente_estado_determinista, ModeloTransicion,
+    ModelosPorEstado,
+};
 use ndarray::Array2;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
-use std::io::Write;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Returns the states indexing the rows and columns of the transition matrices, in map order.
+/// Obstacles are excluded.
+pub fn estados_matriz() -> Vec<String> {
+    MAPA_ESTADOS
+        .iter()
+        .flatten()
+        .filter(|s| !s.is_empty() && !OBSTACULOS.contains(s))
+        .map(|s| s.to_string())
+        .collect()
+}
 
 /// Constructs a 2D transition matrix for a given action.
 ///
@@ -23,12 +43,7 @@ use std::io::Write;
 pub fn construir_matriz_transicion(sAccion: &str) -> Array2<f32> {
     let hm_s_hm_s_f64ModeloTransicion = prob_transicion();
 
-    let vec_sEstados: Vec<String> = MAPA_ESTADOS
-        .iter()
-        .flatten()
-        .filter(|s| !s.is_empty() && !OBSTACULOS.contains(s))
-        .map(|s| s.to_string())
-        .collect();
+    let vec_sEstados = estados_matriz();
 
     let uiTotalEstados = vec_sEstados.len();
     let mut hm_s_uiEstadoAIndice: HashMap<String, usize> = HashMap::new();
@@ -66,22 +81,551 @@ pub fn construir_matriz_transicion(sAccion: &str) -> Array2<f32> {
     arr2_f32Matriz
 }
 
+/// Builds the transition matrix of the Markov chain induced by a policy.
+///
+/// Row s is P(s' | s, π(s)) under the given model: each direction the action can end up moving in
+/// is resolved with `mdp_model::siguiente_estado_determinista`, so moves into an obstacle or the border
+/// add to the self-loop of s. States without an action in the policy (the goal) are absorbing and get
+/// a self-loop of 1, so every row sums to 1.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_sPolitica` - The policy (State -> Action).
+/// * `opt_hm_s_hm_s_f64ProbTransExt` - An optional external transition probability model. If `None`, uses `modelo_transicion_base()`.
+///
+/// # Returns
+///
+/// The matrix (`f32` entries, as `construir_matriz_transicion`) and the states indexing its rows and
+/// columns, which are those of `estados_matriz()`.
+pub fn matriz_transicion_politica(
+    ref_hm_s_sPolitica: &HashMap<String, String>,
+    opt_hm_s_hm_s_f64ProbTransExt: Option<&ModeloTransicion>,
+) -> (Array2<f32>, Vec<String>) {
+    let hm_s_hm_s_f64ModeloBase;
+    let ref_hm_s_hm_s_f64Modelo = match opt_hm_s_hm_s_f64ProbTransExt {
+        Some(ref_hm_s_hm_s_f64ModeloExt) => ref_hm_s_hm_s_f64ModeloExt,
+        None => {
+            hm_s_hm_s_f64ModeloBase = modelo_transicion_base();
+            &hm_s_hm_s_f64ModeloBase
+        }
+    };
+    let vec_sEstados = estados_matriz();
+    let hm_s_uiIndice: HashMap<&str, usize> =
+        vec_sEstados.iter().enumerate().map(|(uiIndice, sEstado)| (sEstado.as_str(), uiIndice)).collect();
+
+    let mut arr2_f32Matriz = Array2::<f32>::zeros((vec_sEstados.len(), vec_sEstados.len()));
+    for (uiOrigen, sEstado) in vec_sEstados.iter().enumerate() {
+        let Some(sAccion) = ref_hm_s_sPolitica.get(sEstado) else {
+            arr2_f32Matriz[[uiOrigen, uiOrigen]] = 1.0;
+            continue;
+        };
+        for (sDireccion, f64Prob) in &ref_hm_s_hm_s_f64Modelo[sAccion] {
+            let sDestino = siguiente_estado_determinista(sEstado, sDireccion).unwrap();
+            arr2_f32Matriz[[uiOrigen, hm_s_uiIndice[sDestino]]] += *f64Prob as f32;
+        }
+    }
+    (arr2_f32Matriz, vec_sEstados)
+}
+
+/// Convergence threshold (L1 change per iteration) of `distribucion_estacionaria`.
+const F64_TOLERANCIA_ESTACIONARIA: f64 = 1e-12;
+/// Iteration cap of `distribucion_estacionaria`.
+const UI_MAX_ITERACIONES_ESTACIONARIA: usize = 100_000;
+/// Long-run probability at or below which an absorbing goal is treated as unreached by
+/// `recompensa_promedio_estacionaria`.
+const F64_MASA_META_DESPRECIABLE: f64 = 1e-9;
+
+/// Computes the long-run (stationary) distribution of the chain induced by a policy.
+///
+/// Starting from the uniform distribution over `learning::estados_iniciales_validos()`, the distribution is
+/// pushed through the lazy chain (I + P_π)/2 of `matriz_transicion_politica` until it stops changing. The
+/// lazy chain has the same stationary distributions as P_π but is aperiodic, so the iteration converges
+/// even when the policy cycles. With an absorbing goal (the default solver) every state that reaches it
+/// ends there, so the result is the probability of ending at the goal or in each trap of the policy.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_sPolitica` - The policy (State -> Action).
+/// * `opt_hm_s_hm_s_f64ProbTransExt` - An optional external transition probability model. If `None`, uses `modelo_transicion_base()`.
+///
+/// # Returns
+///
+/// The long-run probability of every non-obstacle state, summing to 1.
+pub fn distribucion_estacionaria(
+    ref_hm_s_sPolitica: &HashMap<String, String>,
+    opt_hm_s_hm_s_f64ProbTransExt: Option<&ModeloTransicion>,
+) -> HashMap<String, f64> {
+    let (arr2_f32Politica, vec_sEstados) =
+        matriz_transicion_politica(ref_hm_s_sPolitica, opt_hm_s_hm_s_f64ProbTransExt);
+    let uiN = vec_sEstados.len();
+
+    let vec_sIniciales = estados_iniciales_validos();
+    let f64Inicial = 1.0 / vec_sIniciales.len() as f64;
+    let mut vec_f64D: Vec<f64> = vec_sEstados
+        .iter()
+        .map(|sEstado| if vec_sIniciales.contains(&sEstado.as_str()) { f64Inicial } else { 0.0 })
+        .collect();
+
+    for _ in 0..UI_MAX_ITERACIONES_ESTACIONARIA {
+        let mut vec_f64Siguiente: Vec<f64> = vec_f64D.iter().map(|f64Prob| f64Prob / 2.0).collect();
+        for uiOrigen in 0..uiN {
+            if vec_f64D[uiOrigen] == 0.0 {
+                continue;
+            }
+            for uiDestino in 0..uiN {
+                let f64Prob = arr2_f32Politica[[uiOrigen, uiDestino]] as f64;
+                vec_f64Siguiente[uiDestino] += vec_f64D[uiOrigen] * f64Prob / 2.0;
+            }
+        }
+        let f64Cambio: f64 = vec_f64D.iter().zip(&vec_f64Siguiente).map(|(f64A, f64B)| (f64A - f64B).abs()).sum();
+        vec_f64D = vec_f64Siguiente;
+        if f64Cambio < F64_TOLERANCIA_ESTACIONARIA {
+            break;
+        }
+    }
+
+    vec_sEstados.into_iter().zip(vec_f64D).collect()
+}
+
+/// Computes the long-run average reward per step of a policy, Σ_s π_∞(s)·R(s).
+///
+/// π_∞ is `distribucion_estacionaria` and R the default rewards of `config::obtener_recompensas`.
+/// For a continuing task (a policy with an action at the goal) this is the gain of the policy.
+/// For the episodic default the goal is absorbing, so π_∞ piles up on it and the sum would just be
+/// R(goal), which says nothing about the policy (the simulator resets the robot instead); that case
+/// has no answer.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_sPolitica` - The policy (State -> Action).
+/// * `opt_hm_s_hm_s_f64ProbTransExt` - An optional external transition probability model. If `None`, uses `modelo_transicion_base()`.
+///
+/// # Returns
+///
+/// The average reward per step in the long run, or `None` if the policy has no action at the goal and
+/// some long-run mass ends there. A policy that never reaches such a goal gets the average of its traps.
+pub fn recompensa_promedio_estacionaria(
+    ref_hm_s_sPolitica: &HashMap<String, String>,
+    opt_hm_s_hm_s_f64ProbTransExt: Option<&ModeloTransicion>,
+) -> Option<f64> {
+    let hm_s_f64Recompensas = obtener_recompensas();
+    let hm_s_f64Estacionaria = distribucion_estacionaria(ref_hm_s_sPolitica, opt_hm_s_hm_s_f64ProbTransExt);
+    let f64MasaMeta = hm_s_f64Estacionaria.get(ESTADO_META).copied().unwrap_or(0.0);
+    if !ref_hm_s_sPolitica.contains_key(ESTADO_META) && f64MasaMeta > F64_MASA_META_DESPRECIABLE {
+        return None;
+    }
+    Some(
+        hm_s_f64Estacionaria
+            .iter()
+            .map(|(sEstado, f64Prob)| f64Prob * hm_s_f64Recompensas[sEstado.as_str()])
+            .sum(),
+    )
+}
+
 /// Constructs transition matrices for all actions (N, S, E, O) and saves them to CSV files.
 ///
-/// File names are in the format "matriz_transicion_{ACCION}.csv".
+/// File names are in the format "matriz_transicion_{ACCION}.csv", inside `sDirSalida` ("." for the
+/// current directory).
 /// Each row in the CSV corresponds to an origin state, and each column to a destination state.
 /// Values are probabilities formatted to two decimal places.
-pub fn guardar_matrices_transicion_csv() {
+/// Rows are written through a `BufWriter`, which is flushed before the file is reported as saved.
+pub fn guardar_matrices_transicion_csv(sDirSalida: &str) {
     for sAccion in ["N", "S", "E", "O"].iter() { // Iterate over references to avoid moving
         let arr2_f32Matriz = construir_matriz_transicion(sAccion);
-        let sNombreArchivo = format!("matriz_transicion_{}.csv", sAccion);
-        let mut fArchivo = File::create(&sNombreArchivo).expect("No se pudo crear el archivo");
+        let sNombreArchivo =
+            Path::new(sDirSalida).join(format!("matriz_transicion_{}.csv", sAccion)).to_string_lossy().to_string();
+        let mut bwArchivo = BufWriter::new(File::create(&sNombreArchivo).expect("No se pudo crear el archivo"));
 
         for view_f32Fila in arr2_f32Matriz.rows() {
-            let vec_sLinea: Vec<String> = view_f32Fila.iter().map(|f32Val| format!("{:.2}", f32Val)).collect();
-            writeln!(fArchivo, "{}", vec_sLinea.join(",")).expect("Error escribiendo archivo");
+            let vec_sLinea: Vec<String> = view_f32Fila.iter().map(|f32Prob| format!("{:.2}", f32Prob)).collect();
+            writeln!(bwArchivo, "{}", vec_sLinea.join(",")).expect("Error escribiendo archivo");
+        }
+        bwArchivo.flush().expect("Error escribiendo archivo");
+
+        log::info!("{} guardada.", sNombreArchivo);
+    }
+}
+
+/// Tolerance on the row sums of a loaded matrix; `guardar_matrices_transicion_csv` rounds entries to two decimals.
+const F64_TOLERANCIA_FILA_CSV: f64 = 0.01;
+
+/// Reads a transition matrix in the format written by `guardar_matrices_transicion_csv`.
+///
+/// The file has no header; row i and column j hold P(s_j | s_i, a) for the states of `estados_matriz()`
+/// (or whatever ordering the file was written with).
+///
+/// # Arguments
+///
+/// * `sRuta` - Path of the CSV file.
+///
+/// # Returns
+///
+/// The matrix, or an error if a value does not parse, the matrix is not square, or a row does not sum
+/// to 1 (within `F64_TOLERANCIA_FILA_CSV`); the error names the offending row.
+pub fn cargar_matriz_transicion_csv(sRuta: &str) -> Result<Array2<f32>, Box<dyn std::error::Error>> {
+    let mut rdrLector = csv::ReaderBuilder::new().has_headers(false).flexible(true).from_path(sRuta)?;
+    let mut vec_vec_f32Filas: Vec<Vec<f32>> = Vec::new();
+    for resRegistro in rdrLector.records() {
+        let srRegistro = resRegistro?;
+        let vec_f32Fila = srRegistro
+            .iter()
+            .map(|sCampo| sCampo.trim().parse::<f32>())
+            .collect::<Result<Vec<f32>, _>>()?;
+        vec_vec_f32Filas.push(vec_f32Fila);
+    }
+
+    let uiN = vec_vec_f32Filas.len();
+    for (uiFila, vec_f32Fila) in vec_vec_f32Filas.iter().enumerate() {
+        if vec_f32Fila.len() != uiN {
+            return Err(format!(
+                "{}: la fila {} tiene {} columnas, se esperaban {} (matriz cuadrada)",
+                sRuta,
+                uiFila,
+                vec_f32Fila.len(),
+                uiN
+            )
+            .into());
         }
+        let f64Suma: f64 = vec_f32Fila.iter().map(|f32Prob| *f32Prob as f64).sum();
+        if (f64Suma - 1.0).abs() > F64_TOLERANCIA_FILA_CSV {
+            return Err(format!("{}: la fila {} suma {:.4}, debería sumar 1", sRuta, uiFila, f64Suma).into());
+        }
+    }
+
+    Ok(Array2::from_shape_vec((uiN, uiN), vec_vec_f32Filas.into_iter().flatten().collect())?)
+}
+
+/// Builds a per-state transition model from one transition matrix per action, for use with
+/// `mdp_model::value_iteration_por_estado`.
+///
+/// Each matrix entry P(s'|s,a) is turned into a probability of moving in the direction that takes
+/// `s` to `s'` (staying in place maps to a direction that bumps into a wall, obstacle or border).
+/// Rows are renormalized to sum exactly 1, absorbing the rounding of the CSV export.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_arr2Matrices` - One matrix per action ("N", "S", "E", "O"), e.g. from `cargar_matriz_transicion_csv`.
+/// * `ref_vec_sEstados` - The states indexing rows and columns (`estados_matriz()` for exported matrices).
+///
+/// # Returns
+///
+/// A `ModelosPorEstado` with a model for every state, or an error if an action is missing, a matrix
+/// does not match the number of states, a state is not on the map, or a transition cannot be made by
+/// a single move from its origin.
+pub fn modelo_desde_matrices(
+    ref_hm_s_arr2Matrices: &HashMap<String, Array2<f32>>,
+    ref_vec_sEstados: &[String],
+) -> Result<ModelosPorEstado, Box<dyn std::error::Error>> {
+    let uiN = ref_vec_sEstados.len();
+    for sAccion in acciones() {
+        let arr2_f32Matriz = ref_hm_s_arr2Matrices
+            .get(sAccion)
+            .ok_or_else(|| format!("falta la matriz de la acción {}", sAccion))?;
+        if arr2_f32Matriz.dim() != (uiN, uiN) {
+            return Err(format!(
+                "la matriz de {} es {:?}, se esperaba {}x{} para {} estados",
+                sAccion,
+                arr2_f32Matriz.dim(),
+                uiN,
+                uiN,
+                uiN
+            )
+            .into());
+        }
+    }
+
+    let mut hm_s_ModelosPorEstado: ModelosPorEstado = HashMap::new();
+    for (uiOrigen, sOrigen) in ref_vec_sEstados.iter().enumerate() {
+        // Destination of each direction from this state, as the solver resolves it.
+        let mut vec_tpl_s_sDestinos: Vec<(&str, &'static str)> = Vec::new();
+        for sDireccion in acciones() {
+            let sDestino = siguiente_estado_determinista(sOrigen, sDireccion)
+                .ok_or_else(|| format!("el estado {} no está en el mapa", sOrigen))?;
+            vec_tpl_s_sDestinos.push((sDireccion, sDestino));
+        }
+
+        let mut hm_s_hm_s_f64Modelo: HashMap<String, HashMap<String, f64>> = HashMap::new();
+        for sAccion in acciones() {
+            let arr2_f32Matriz = &ref_hm_s_arr2Matrices[sAccion];
+            let mut hm_s_f64Direcciones: HashMap<String, f64> = HashMap::new();
+            for (uiDestino, sDestino) in ref_vec_sEstados.iter().enumerate() {
+                let f64Prob = arr2_f32Matriz[[uiOrigen, uiDestino]] as f64;
+                if f64Prob == 0.0 {
+                    continue;
+                }
+                let sDireccion = vec_tpl_s_sDestinos
+                    .iter()
+                    .find(|(_, sDestinoDireccion)| *sDestinoDireccion == sDestino.as_str())
+                    .map(|(sDireccion, _)| *sDireccion)
+                    .ok_or_else(|| {
+                        format!("{} -> {} con {} no se puede hacer con un solo movimiento", sOrigen, sDestino, sAccion)
+                    })?;
+                *hm_s_f64Direcciones.entry(sDireccion.to_string()).or_insert(0.0) += f64Prob;
+            }
+            let f64Suma: f64 = hm_s_f64Direcciones.values().sum();
+            if f64Suma > 0.0 {
+                hm_s_f64Direcciones.values_mut().for_each(|f64Prob| *f64Prob /= f64Suma);
+            }
+            hm_s_hm_s_f64Modelo.insert(sAccion.to_string(), hm_s_f64Direcciones);
+        }
+        hm_s_ModelosPorEstado.insert(sOrigen.clone(), hm_s_hm_s_f64Modelo);
+    }
+    Ok(hm_s_ModelosPorEstado)
+}
+
+/// Writes one CSV row per non-obstacle state with its position, reward, value and policy action.
+///
+/// Same as `guardar_resumen_estados_csv_con_entorno` with `Entorno::default()` (the default rewards).
+pub fn guardar_resumen_estados_csv(
+    ref_hm_s_f64Valores: &HashMap<&'static str, f64>,
+    ref_hm_s_sPolitica: &HashMap<String, String>,
+    sRuta: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    guardar_resumen_estados_csv_con_entorno(ref_hm_s_f64Valores, ref_hm_s_sPolitica, &Entorno::default(), sRuta)
+}
+
+/// Writes one CSV row per non-obstacle state with its position, reward, value and policy action.
+///
+/// Columns are `estado,fila,col,recompensa,valor,accion`, rows are sorted by state name, and the
+/// action is empty for states without one (the goal). Rewards are those of `ref_entorno`
+/// (`entorno::recompensas_con_entorno`), so they match the solve the values came from.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_f64Valores` - State values (as returned by `value_iteration`).
+/// * `ref_hm_s_sPolitica` - The policy (State -> Action).
+/// * `ref_entorno` - The rules the values were solved under.
+/// * `sRuta` - Path of the output file.
+///
+/// # Returns
+///
+/// `Result<(), Box<dyn std::error::Error>>` indicating success or an I/O error.
+pub fn guardar_resumen_estados_csv_con_entorno(
+    ref_hm_s_f64Valores: &HashMap<&'static str, f64>,
+    ref_hm_s_sPolitica: &HashMap<String, String>,
+    ref_entorno: &Entorno,
+    sRuta: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let hm_s_f64Recompensas = recompensas_con_entorno(ref_entorno);
+    let mut vec_sEstados = estados_matriz();
+    vec_sEstados.sort();
+
+    let mut bwArchivo = BufWriter::new(File::create(sRuta)?);
+    writeln!(bwArchivo, "estado,fila,col,recompensa,valor,accion")?;
+    for sEstado in &vec_sEstados {
+        let (uiFila, uiCol) = obtener_posicion(sEstado).unwrap();
+        writeln!(
+            bwArchivo,
+            "{},{},{},{},{:.4},{}",
+            sEstado,
+            uiFila,
+            uiCol,
+            hm_s_f64Recompensas[sEstado.as_str()],
+            ref_hm_s_f64Valores.get(sEstado.as_str()).copied().unwrap_or(0.0),
+            ref_hm_s_sPolitica.get(sEstado).map(String::as_str).unwrap_or("")
+        )?;
+    }
+    bwArchivo.flush()?;
+
+    log::info!("{} guardado.", sRuta);
+    Ok(())
+}
+
+/// Computes the discounted state-occupancy measure of a policy.
+///
+/// Builds the policy-induced matrix P_π with `matriz_transicion_politica` and solves
+/// d = (I − λ·P_π^T)^-1 · d0, where d0 is uniform over `learning::estados_iniciales_validos()`.
+/// States without an action in the policy (the goal) end the episode: their absorbing row of P_π is
+/// left out, so they accumulate occupancy on arrival but pass none on. The result is normalized to sum to 1, which
+/// makes it comparable with empirical visit frequencies from the simulator.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_sPolitica` - The policy to evaluate (State -> Action).
+/// * `f64Lambda` - The discount factor (below 1).
+///
+/// # Returns
+///
+/// A `HashMap<String, f64>` with the normalized occupancy of every non-obstacle state.
+pub fn ocupancia_descontada(ref_hm_s_sPolitica: &HashMap<String, String>, f64Lambda: f64) -> HashMap<String, f64> {
+    let (arr2_f32Politica, vec_sEstados) = matriz_transicion_politica(ref_hm_s_sPolitica, None);
+    let uiTotalEstados = vec_sEstados.len();
+
+    // A = I − λ·P_π^T
+    let mut arr2_f64Sistema = Array2::<f64>::eye(uiTotalEstados);
+    for (uiOrigen, sEstado) in vec_sEstados.iter().enumerate() {
+        if !ref_hm_s_sPolitica.contains_key(sEstado) {
+            continue;
+        }
+        for uiDestino in 0..uiTotalEstados {
+            let f64Prob = arr2_f32Politica[[uiOrigen, uiDestino]] as f64;
+            arr2_f64Sistema[[uiDestino, uiOrigen]] -= f64Lambda * f64Prob;
+        }
+    }
+
+    let vec_sIniciales = estados_iniciales_validos();
+    let f64Inicial = 1.0 / vec_sIniciales.len() as f64;
+    let vec_f64D0: Vec<f64> = vec_sEstados
+        .iter()
+        .map(|sEstado| if vec_sIniciales.contains(&sEstado.as_str()) { f64Inicial } else { 0.0 })
+        .collect();
+
+    let vec_f64Ocupancia = resolver_sistema_lineal(arr2_f64Sistema, vec_f64D0);
+    let f64Total: f64 = vec_f64Ocupancia.iter().sum();
+    vec_sEstados
+        .into_iter()
+        .zip(vec_f64Ocupancia)
+        .map(|(sEstado, f64Valor)| (sEstado, f64Valor / f64Total))
+        .collect()
+}
+
+/// Computes, for every state, the probability of eventually reaching the goal versus a danger state
+/// when following a policy under a noisy transition model.
+///
+/// The goal and the danger states are absorbing. For the remaining (transient) states the chain
+/// induced by the policy is split into Q (transient -> transient) and R (transient -> absorbing),
+/// and (I − Q)·B = R is solved, which is the fundamental-matrix result B = (I − Q)^-1·R.
+/// Transient states from which no absorbing state is reachable (e.g. a policy loop that the noise
+/// cannot escape) would make I − Q singular; they are excluded from the system, get (0, 0) and are
+/// reported with `log::warn!`.
+///
+/// # Arguments
+///
+/// * `ref_hm_s_sPolitica` - The policy (State -> Action).
+/// * `opt_hm_s_hm_s_f64ProbTransExt` - An optional external transition probability model. If `None`, uses `modelo_transicion_base()`.
+///
+/// # Returns
+///
+/// A `HashMap<String, (f64, f64)>` with `(P_meta, P_peligro)` for every non-obstacle state; the
+/// goal is (1, 0) and each danger state (0, 1).
+pub fn probabilidades_absorcion(
+    ref_hm_s_sPolitica: &HashMap<String, String>,
+    opt_hm_s_hm_s_f64ProbTransExt: Option<&HashMap<String, HashMap<String, f64>>>,
+) -> HashMap<String, (f64, f64)> {
+    let hm_s_hm_s_f64ModeloBase;
+    let ref_hm_s_hm_s_f64Modelo = match opt_hm_s_hm_s_f64ProbTransExt {
+        Some(ref_hm_s_hm_s_f64ModeloExt) => ref_hm_s_hm_s_f64ModeloExt,
+        None => {
+            hm_s_hm_s_f64ModeloBase = modelo_transicion_base();
+            &hm_s_hm_s_f64ModeloBase
+        }
+    };
+    let es_absorbente = |sEstado: &str| sEstado == ESTADO_META || ESTADOS_PELIGRO.contains(&sEstado);
+
+    // Policy-induced transitions of every transient state: state -> [(destination, probability)]
+    let hm_s_vec_tpl_s_f64Transiciones: HashMap<String, Vec<(&'static str, f64)>> = estados_matriz()
+        .into_iter()
+        .filter(|sEstado| !es_absorbente(sEstado))
+        .map(|sEstado| {
+            let vec_tpl_s_f64Destinos = match ref_hm_s_sPolitica.get(&sEstado) {
+                Some(sAccion) => ref_hm_s_hm_s_f64Modelo[sAccion]
+                    .iter()
+                    .filter(|(_, f64Prob)| **f64Prob > 0.0)
+                    .map(|(sDireccion, f64Prob)| (siguiente_estado_determinista(&sEstado, sDireccion).unwrap(), *f64Prob))
+                    .collect(),
+                None => Vec::new(),
+            };
+            (sEstado, vec_tpl_s_f64Destinos)
+        })
+        .collect();
+
+    // Transient states that can reach an absorbing state (fixed point of backward reachability)
+    let mut hs_sAlcanzan: HashSet<&str> = HashSet::new();
+    let mut bCambios = true;
+    while bCambios {
+        bCambios = false;
+        for (sEstado, vec_tpl_s_f64Destinos) in &hm_s_vec_tpl_s_f64Transiciones {
+            if !hs_sAlcanzan.contains(sEstado.as_str())
+                && vec_tpl_s_f64Destinos
+                    .iter()
+                    .any(|(sDestino, _)| es_absorbente(sDestino) || hs_sAlcanzan.contains(sDestino))
+            {
+                hs_sAlcanzan.insert(sEstado.as_str());
+                bCambios = true;
+            }
+        }
+    }
+
+    let mut vec_sTransitorios: Vec<&str> = hs_sAlcanzan.iter().copied().collect();
+    vec_sTransitorios.sort();
+    let hm_s_uiIndice: HashMap<&str, usize> =
+        vec_sTransitorios.iter().enumerate().map(|(uiIndice, sEstado)| (*sEstado, uiIndice)).collect();
+    let uiN = vec_sTransitorios.len();
+
+    // I − Q and the two columns of R (to the goal, to any danger state)
+    let mut arr2_f64Sistema = Array2::<f64>::eye(uiN);
+    let mut vec_f64RMeta = vec![0.0; uiN];
+    let mut vec_f64RPeligro = vec![0.0; uiN];
+    for (uiFila, sEstado) in vec_sTransitorios.iter().enumerate() {
+        for (sDestino, f64Prob) in &hm_s_vec_tpl_s_f64Transiciones[*sEstado] {
+            if *sDestino == ESTADO_META {
+                vec_f64RMeta[uiFila] += f64Prob;
+            } else if ESTADOS_PELIGRO.contains(sDestino) {
+                vec_f64RPeligro[uiFila] += f64Prob;
+            } else if let Some(uiCol) = hm_s_uiIndice.get(sDestino) {
+                arr2_f64Sistema[[uiFila, *uiCol]] -= f64Prob;
+            }
+        }
+    }
+    let vec_f64PMeta = resolver_sistema_lineal(arr2_f64Sistema.clone(), vec_f64RMeta);
+    let vec_f64PPeligro = resolver_sistema_lineal(arr2_f64Sistema, vec_f64RPeligro);
+
+    let mut hm_s_tpl_f64Absorcion: HashMap<String, (f64, f64)> = HashMap::new();
+    hm_s_tpl_f64Absorcion.insert(ESTADO_META.to_string(), (1.0, 0.0));
+    for sPeligro in ESTADOS_PELIGRO.iter() {
+        hm_s_tpl_f64Absorcion.insert(sPeligro.to_string(), (0.0, 1.0));
+    }
+    for (uiIndice, sEstado) in vec_sTransitorios.iter().enumerate() {
+        hm_s_tpl_f64Absorcion.insert(sEstado.to_string(), (vec_f64PMeta[uiIndice], vec_f64PPeligro[uiIndice]));
+    }
+
+    // Transient states with no path to an absorbing state
+    let mut vec_sAtrapados: Vec<&str> = hm_s_vec_tpl_s_f64Transiciones
+        .keys()
+        .map(|sEstado| sEstado.as_str())
+        .filter(|sEstado| !hs_sAlcanzan.contains(sEstado))
+        .collect();
+    vec_sAtrapados.sort();
+    for sEstado in &vec_sAtrapados {
+        hm_s_tpl_f64Absorcion.insert(sEstado.to_string(), (0.0, 0.0));
+    }
+    if !vec_sAtrapados.is_empty() {
+        log::warn!("Estados que no alcanzan ni la meta ni un peligro: {}", vec_sAtrapados.join(", "));
+    }
+
+    hm_s_tpl_f64Absorcion
+}
+
+/// Solves A·x = b by Gaussian elimination with partial pivoting.
+/// `arr2_f64A` must be square and non-singular (I − λ·P^T always is for λ < 1).
+fn resolver_sistema_lineal(mut arr2_f64A: Array2<f64>, mut vec_f64B: Vec<f64>) -> Vec<f64> {
+    let uiN = vec_f64B.len();
+    for uiCol in 0..uiN {
+        let uiPivote = (uiCol..uiN)
+            .max_by(|uiA, uiB| arr2_f64A[[*uiA, uiCol]].abs().partial_cmp(&arr2_f64A[[*uiB, uiCol]].abs()).unwrap())
+            .unwrap();
+        if uiPivote != uiCol {
+            for uiK in 0..uiN {
+                arr2_f64A.swap([uiCol, uiK], [uiPivote, uiK]);
+            }
+            vec_f64B.swap(uiCol, uiPivote);
+        }
+        for uiFila in uiCol + 1..uiN {
+            let f64Factor = arr2_f64A[[uiFila, uiCol]] / arr2_f64A[[uiCol, uiCol]];
+            if f64Factor == 0.0 {
+                continue;
+            }
+            for uiK in uiCol..uiN {
+                arr2_f64A[[uiFila, uiK]] -= f64Factor * arr2_f64A[[uiCol, uiK]];
+            }
+            vec_f64B[uiFila] -= f64Factor * vec_f64B[uiCol];
+        }
+    }
 
-        println!("✅ {} guardada.", sNombreArchivo);
+    let mut vec_f64X = vec![0.0; uiN];
+    for uiFila in (0..uiN).rev() {
+        let f64Suma: f64 = (uiFila + 1..uiN).map(|uiK| arr2_f64A[[uiFila, uiK]] * vec_f64X[uiK]).sum();
+        vec_f64X[uiFila] = (vec_f64B[uiFila] - f64Suma) / arr2_f64A[[uiFila, uiFila]];
     }
+    vec_f64X
 }